@@ -1,6 +1,7 @@
 ﻿use clap::{ArgAction, Parser, Subcommand};
 
 use crate::commands::*;
+use crate::term::progress::ProgressMode;
 
 #[derive(Parser)]
 #[command(
@@ -21,6 +22,10 @@ pub(crate) struct Cli {
     #[arg(long, action = ArgAction::Count, global = true)]
     pub verbose: u8,
 
+    /// Progress output, `tty` unless stderr is not a terminal, then `plain`
+    #[arg(long, value_enum, global = true)]
+    pub progress: Option<ProgressMode>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -42,6 +47,12 @@ pub(crate) enum Commands {
     /// Clean unused git repos
     Clean(CleanCommand),
 
+    /// Check workspace health
+    Doctor(DoctorCommand),
+
+    /// Report where the workspace deviates from the config
+    Drift(DriftCommand),
+
     /// List tree files
     #[command(name = "ls-files")]
     ListFiles(ListFilesCommand),
@@ -65,6 +76,15 @@ pub(crate) enum Commands {
     #[command(name = "new-tag")]
     NewTag(NewTagCommand),
 
+    /// Maintain the local mirror cache
+    Cache(CacheCommand),
+
+    /// Manage workspaces of git worktrees
+    Worktree(WorktreeCommand),
+
+    /// Manage the stashes left by sync
+    Stash(StashCommand),
+
     /// Upgrade mgit CLI to the latest release
     Upgrade(UpgradeCommand),
 }
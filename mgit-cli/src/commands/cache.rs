@@ -0,0 +1,102 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use mgit::error::MgitResult;
+use mgit::ops::{self, CacheOptions, CachePruneOptions};
+
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs};
+use crate::term::print_style_message;
+use crate::term::progress::CliProgress;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Args)]
+/// Maintain the local mirror cache shared by all workspaces
+pub(crate) struct CacheCommand {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Subcommand)]
+pub(crate) enum CacheAction {
+    /// Create or refresh the mirrors of a workspace's repos
+    Update(CacheUpdateCommand),
+
+    /// Run git gc in every mirror
+    Gc,
+
+    /// Remove mirrors not used for a while
+    Prune(CachePruneCommand),
+
+    /// Show the disk usage of every mirror
+    Du,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct CacheUpdateCommand {
+    /// The work directory
+    pub path: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
+    /// Use specified config file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Sets the number of threads to be used
+    #[arg(short, long, default_value_t = 4, value_name = "NUMBER")]
+    pub thread: usize,
+
+    /// Ignore specified repositories to cache, by path or glob
+    #[arg(long)]
+    ignore: Option<Vec<String>>,
+
+    /// Labels for cache
+    #[arg(long)]
+    labels: Option<Vec<String>>,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct CachePruneCommand {
+    /// Remove mirrors unused for more than this many days, default is 30
+    #[arg(long, value_name = "DAYS")]
+    pub older_than: Option<u64>,
+}
+
+impl CliCommand for CacheCommand {
+    async fn exec(self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let msg = match self.action {
+            CacheAction::Update(mut cmd) => {
+                let only = repo_selectors(&mut cmd.path, &cmd.config, &cmd.selector);
+                let options = CacheOptions {
+                    only,
+                    states: cmd.state.states(),
+                    ..cmd.into()
+                };
+                ops::cache_update(options, progress).await?
+            }
+            CacheAction::Gc => ops::cache_gc(progress).await?,
+            CacheAction::Prune(cmd) => {
+                ops::cache_prune(CachePruneOptions::new(cmd.older_than), progress).await?
+            }
+            CacheAction::Du => ops::cache_du(progress).await?,
+        };
+        print_style_message(&msg);
+        Ok(())
+    }
+}
+
+impl From<CacheUpdateCommand> for CacheOptions {
+    fn from(value: CacheUpdateCommand) -> Self {
+        CacheOptions::new(
+            value.path,
+            value.config,
+            Some(value.thread),
+            value.ignore,
+            value.labels,
+        )
+    }
+}
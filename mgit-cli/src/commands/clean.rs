@@ -1,4 +1,4 @@
-use clap::Args;
+use clap::{ArgAction, Args};
 use std::path::PathBuf;
 
 use mgit::error::MgitResult;
@@ -6,7 +6,7 @@ use mgit::ops::{self, CleanOptions};
 
 use crate::commands::CliCommand;
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Clean unused git repos
@@ -20,11 +20,19 @@ pub(crate) struct CleanCommand {
 
     #[arg(long)]
     pub labels: Option<Vec<String>>,
+
+    /// Keep specified repositories not in the config, by path or glob
+    #[arg(long)]
+    pub ignore: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for CleanCommand {
     async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
+        let progress = CliProgress::default();
         let msg = ops::clean_repo(self.into(), progress).await?;
         print_style_message(&msg);
         Ok(())
@@ -33,6 +41,10 @@ impl CliCommand for CleanCommand {
 
 impl From<CleanCommand> for CleanOptions {
     fn from(value: CleanCommand) -> Self {
-        CleanOptions::new(value.path, value.config, value.labels)
+        CleanOptions {
+            ignore: value.ignore,
+            wait: value.wait,
+            ..CleanOptions::new(value.path, value.config, value.labels)
+        }
     }
 }
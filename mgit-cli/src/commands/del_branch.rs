@@ -4,9 +4,9 @@ use std::path::PathBuf;
 use mgit::error::MgitResult;
 use mgit::ops::{self, DelBranchOptions};
 
-use crate::commands::CliCommand;
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs};
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Delete remote branch
@@ -14,6 +14,12 @@ pub(crate) struct DelRemoteBranchCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -26,15 +32,25 @@ pub(crate) struct DelRemoteBranchCommand {
     #[arg(long, action = ArgAction::SetTrue)]
     pub force: bool,
 
-    /// Ignore specified repositories
+    /// Ignore specified repositories, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for DelRemoteBranchCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let msg = ops::del_remote_branch(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector);
+        let options = DelBranchOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::del_remote_branch(options, progress).await?;
         print_style_message(&msg);
         Ok(())
     }
@@ -42,6 +58,9 @@ impl CliCommand for DelRemoteBranchCommand {
 
 impl From<DelRemoteBranchCommand> for DelBranchOptions {
     fn from(value: DelRemoteBranchCommand) -> Self {
-        DelBranchOptions::new(value.path, value.config, value.branch, value.ignore)
+        DelBranchOptions {
+            wait: value.wait,
+            ..DelBranchOptions::new(value.path, value.config, value.branch, value.ignore)
+        }
     }
 }
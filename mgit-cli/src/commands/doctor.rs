@@ -0,0 +1,84 @@
+use clap::{ArgAction, Args};
+use std::path::PathBuf;
+
+use mgit::error::MgitResult;
+use mgit::ops::{self, DoctorOptions};
+
+use crate::commands::{
+    current_repo_only, repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs,
+};
+use crate::term::print_style_message;
+use crate::term::progress::CliProgress;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+/// Check workspace health
+pub(crate) struct DoctorCommand {
+    /// The work directory
+    pub path: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
+    /// Use specified config file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Sets the number of threads to be used
+    #[arg(short, long, default_value_t = 4, value_name = "NUMBER")]
+    pub thread: usize,
+
+    /// Repair the safe cases (lock files, remote url, sparse patterns)
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub fix: bool,
+
+    /// Ignore specified repositories to check, by path or glob
+    #[arg(long)]
+    ignore: Option<Vec<String>>,
+
+    /// Labels for check
+    #[arg(long)]
+    labels: Option<Vec<String>>,
+
+    /// Act on every repo of the workspace, not just the one holding the current directory
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub all: bool,
+
+    /// Wait for other mgit runs in the workspace instead of failing, with `--fix`
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
+}
+
+impl CliCommand for DoctorCommand {
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector)
+            .or_else(|| current_repo_only(&self.path, &self.config, self.all));
+        let options = DoctorOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::doctor(options, progress).await?;
+        print_style_message(&msg);
+        Ok(())
+    }
+}
+
+impl From<DoctorCommand> for DoctorOptions {
+    fn from(value: DoctorCommand) -> Self {
+        DoctorOptions {
+            wait: value.wait,
+            ..DoctorOptions::new(
+                value.path,
+                value.config,
+                Some(value.thread),
+                value.ignore,
+                value.labels,
+                Some(value.fix),
+            )
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use clap::{ArgAction, Args};
+use std::path::PathBuf;
+
+use mgit::error::MgitResult;
+use mgit::ops::{self, DriftOptions};
+
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs};
+use crate::term::print_style_message;
+use crate::term::progress::CliProgress;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+/// Report where the workspace deviates from the config
+pub(crate) struct DriftCommand {
+    /// The work directory
+    pub path: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
+    /// Use specified config file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Sets the number of threads to be used
+    #[arg(short, long, default_value_t = 4, value_name = "NUMBER")]
+    pub thread: usize,
+
+    /// Update config from the workspace for the given repos, all drifted repos if none given
+    #[arg(long, value_name = "LOCAL", num_args = 0..)]
+    pub write: Option<Vec<String>>,
+
+    /// Ignore specified repositories to check, by path or glob
+    #[arg(long)]
+    ignore: Option<Vec<String>>,
+
+    /// Labels for check
+    #[arg(long)]
+    labels: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing, with `--write`
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
+}
+
+impl CliCommand for DriftCommand {
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector);
+        let options = DriftOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::drift(options, progress).await?;
+        print_style_message(&msg);
+        Ok(())
+    }
+}
+
+impl From<DriftCommand> for DriftOptions {
+    fn from(value: DriftCommand) -> Self {
+        DriftOptions {
+            wait: value.wait,
+            ..DriftOptions::new(
+                value.path,
+                value.config,
+                Some(value.thread),
+                value.ignore,
+                value.labels,
+                value.write,
+            )
+        }
+    }
+}
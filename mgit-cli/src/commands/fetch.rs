@@ -1,12 +1,14 @@
-﻿use clap::{ArgAction, Args};
+use clap::{ArgAction, Args};
 use std::path::PathBuf;
 
 use mgit::error::MgitResult;
 use mgit::ops::{self, FetchOptions};
 
-use crate::commands::CliCommand;
+use crate::commands::{
+    current_repo_only, repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs,
+};
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Fetch git repos
@@ -14,6 +16,12 @@ pub(crate) struct FetchCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -30,19 +38,38 @@ pub(crate) struct FetchCommand {
     #[arg(short, long, value_name = "NUMBER")]
     pub depth: Option<usize>,
 
-    /// Ignore specified repositories to fetch
+    /// Ignore specified repositories to fetch, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
 
     /// Labels for fetch
     #[arg(long)]
     labels: Option<Vec<String>>,
+
+    /// Do not download Git LFS objects
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub skip_lfs: bool,
+
+    /// Act on every repo of the workspace, not just the one holding the current directory
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub all: bool,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for FetchCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let msg = ops::fetch_repos(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector)
+            .or_else(|| current_repo_only(&self.path, &self.config, self.all));
+        let options = FetchOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::fetch_repos(options, progress).await?;
         print_style_message(&msg);
         Ok(())
     }
@@ -50,14 +77,18 @@ impl CliCommand for FetchCommand {
 
 impl From<FetchCommand> for FetchOptions {
     fn from(value: FetchCommand) -> Self {
-        FetchOptions::new(
-            value.path,
-            value.config,
-            Some(value.thread),
-            Some(value.silent),
-            value.depth,
-            value.ignore,
-            value.labels,
-        )
+        FetchOptions {
+            wait: value.wait,
+            ..FetchOptions::new(
+                value.path,
+                value.config,
+                Some(value.thread),
+                Some(value.silent),
+                value.depth,
+                value.ignore,
+                value.labels,
+                Some(value.skip_lfs),
+            )
+        }
     }
 }
@@ -6,7 +6,7 @@ use mgit::ops::{self, InitOptions};
 
 use crate::commands::CliCommand;
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Init git repos
@@ -18,14 +18,18 @@ pub(crate) struct InitCommand {
     #[arg(short, long, action = ArgAction::SetTrue)]
     pub force: bool,
 
-    /// Ignore specified repositories to init
+    /// Ignore specified repositories to init, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for InitCommand {
     async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
+        let progress = CliProgress::default();
         let msg = ops::init_repo(self.into(), progress).await?;
         print_style_message(&msg);
         Ok(())
@@ -34,6 +38,10 @@ impl CliCommand for InitCommand {
 
 impl From<InitCommand> for InitOptions {
     fn from(value: InitCommand) -> Self {
-        InitOptions::new(value.path, Some(value.force))
+        InitOptions {
+            ignore: value.ignore,
+            wait: value.wait,
+            ..InitOptions::new(value.path, Some(value.force))
+        }
     }
 }
@@ -4,7 +4,7 @@ use std::path::PathBuf;
 use mgit::error::MgitResult;
 use mgit::ops::{self, ListFilesOptions};
 
-use crate::commands::CliCommand;
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// List tree files
@@ -12,6 +12,9 @@ pub(crate) struct ListFilesCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -30,8 +33,13 @@ pub(crate) struct ListFilesCommand {
 }
 
 impl CliCommand for ListFilesCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let files = ops::list_files(self.into()).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector);
+        let options = ListFilesOptions {
+            only,
+            ..self.into()
+        };
+        let files = ops::list_files(options).await?;
         println!("{}", files.join("\n"));
 
         Ok(())
@@ -4,8 +4,8 @@ use std::path::PathBuf;
 use mgit::error::MgitResult;
 use mgit::ops::{self, LogReposOptions};
 
-use crate::commands::CliCommand;
-use crate::term::progress::MultiProgress;
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs};
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Log git repos
@@ -13,6 +13,12 @@ pub(crate) struct LogReposCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -27,9 +33,15 @@ pub(crate) struct LogReposCommand {
 }
 
 impl CliCommand for LogReposCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let repo_logs = ops::log_repos(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector);
+        let options = LogReposOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let repo_logs = ops::log_repos(options, progress).await?;
 
         for repo_log in repo_logs {
             match repo_log {
@@ -1,7 +1,18 @@
+use clap::{ArgAction, Args};
+use std::path::{Component, Path, PathBuf};
+
+use mgit::config::{RepoState, Workspace, CONFIG_FILE_NAME};
 use mgit::error::MgitResult;
+use mgit::utils::path::PathExtension;
+use mgit::utils::style_message::StyleMessage;
+
+use crate::term::print_style_message;
 
+pub(crate) use cache::CacheCommand;
 pub(crate) use clean::CleanCommand;
 pub(crate) use del_branch::DelRemoteBranchCommand;
+pub(crate) use doctor::DoctorCommand;
+pub(crate) use drift::DriftCommand;
 pub(crate) use fetch::FetchCommand;
 pub(crate) use init::InitCommand;
 pub(crate) use list_files::ListFilesCommand;
@@ -9,12 +20,17 @@ pub(crate) use log_repos::LogReposCommand;
 pub(crate) use new_branch::NewRemoteBranchCommand;
 pub(crate) use new_tag::NewTagCommand;
 pub(crate) use snapshot::SnapshotCommand;
+pub(crate) use stash::StashCommand;
 pub(crate) use sync::SyncCommand;
 pub(crate) use track::TrackCommand;
 pub(crate) use upgrade::UpgradeCommand;
+pub(crate) use worktree::WorktreeCommand;
 
+mod cache;
 mod clean;
 mod del_branch;
+mod doctor;
+mod drift;
 mod fetch;
 mod init;
 mod list_files;
@@ -22,10 +38,145 @@ mod log_repos;
 mod new_branch;
 mod new_tag;
 mod snapshot;
+mod stash;
 mod sync;
 mod track;
 mod upgrade;
+mod worktree;
 
 pub trait CliCommand {
     async fn exec(self) -> MgitResult<()>;
 }
+
+/// Repos of a command run without a path or config
+///
+/// Inside a repo of the workspace that is the repo alone, unless `all` is set.
+pub(crate) fn current_repo_only(
+    path: &Option<PathBuf>,
+    config: &Option<PathBuf>,
+    all: bool,
+) -> Option<Vec<String>> {
+    if all || path.is_some() || config.is_some() {
+        return None;
+    }
+    let dir = std::env::current_dir().ok()?;
+    let local = Workspace::discover(&dir)?.repo_of(&dir)?;
+    print_style_message(&StyleMessage::current_repo_only(&local));
+    Some(vec![local])
+}
+
+/// Repos a batch command acts on
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct RepoSelectorArgs {
+    /// Only act on these repos, by path, glob or alias
+    #[arg(value_name = "REPOS")]
+    pub repos: Vec<String>,
+
+    /// Only act on this repo, by path, glob or alias
+    #[arg(long, value_name = "REPO")]
+    pub only: Vec<String>,
+}
+
+impl RepoSelectorArgs {
+    /// Selected repos, `None` for all of them
+    pub(crate) fn selectors(&self) -> Option<Vec<String>> {
+        let selectors = self
+            .repos
+            .iter()
+            .chain(&self.only)
+            .cloned()
+            .collect::<Vec<_>>();
+        (!selectors.is_empty()).then_some(selectors)
+    }
+}
+
+/// States of the repos a batch command acts on, any of them
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct RepoStateArgs {
+    /// Only act on repos with staged, unstaged or untracked changes
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub dirty: bool,
+
+    /// Only act on repos behind the ref of the config, as last fetched
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub behind: bool,
+
+    /// Only act on repos with commits missing on the ref of the config
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub ahead: bool,
+
+    /// Only act on repos both ahead and behind
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub diverged: bool,
+
+    /// Only act on repos not cloned yet
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub missing: bool,
+
+    /// Only act on repos with a detached HEAD
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub detached: bool,
+}
+
+impl RepoStateArgs {
+    /// Selected states, `None` for repos in any state
+    pub(crate) fn states(&self) -> Option<Vec<RepoState>> {
+        let states = [
+            (self.dirty, RepoState::Dirty),
+            (self.behind, RepoState::Behind),
+            (self.ahead, RepoState::Ahead),
+            (self.diverged, RepoState::Diverged),
+            (self.missing, RepoState::Missing),
+            (self.detached, RepoState::Detached),
+        ]
+        .into_iter()
+        .filter_map(|(selected, state)| selected.then_some(state))
+        .collect::<Vec<_>>();
+        (!states.is_empty()).then_some(states)
+    }
+}
+
+/// Repos selected on the command line, `None` for all of them
+///
+/// Inside a workspace, a work directory without a config selects a repo of
+/// that workspace too, so `mgit sync client/engine tools/*` syncs just those.
+/// The directory is relative to where mgit runs, like any path argument.
+pub(crate) fn repo_selectors(
+    path: &mut Option<PathBuf>,
+    config: &Option<PathBuf>,
+    selector: &RepoSelectorArgs,
+) -> Option<Vec<String>> {
+    let mut selectors = selector.selectors().unwrap_or_default();
+    let workspace_local = |dir: &PathBuf| {
+        if config.is_some() || dir.join(CONFIG_FILE_NAME).is_file() {
+            return None;
+        }
+        let current_dir = std::env::current_dir().ok()?;
+        let root = Workspace::discover(&current_dir)?.root;
+        let local = normalize_path(&current_dir.join(dir))
+            .strip_prefix(normalize_path(&root))
+            .ok()?
+            .norm_path();
+        (!local.is_empty()).then_some(local)
+    };
+    if let Some(local) = path.as_ref().and_then(workspace_local) {
+        path.take();
+        selectors.insert(0, local);
+    }
+    (!selectors.is_empty()).then_some(selectors)
+}
+
+/// `path` with `.` and `..` resolved without touching the file system
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
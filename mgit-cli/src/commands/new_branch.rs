@@ -4,9 +4,9 @@ use std::path::PathBuf;
 use mgit::error::MgitResult;
 use mgit::ops::{self, NewBranchOptions};
 
-use crate::commands::CliCommand;
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs};
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// new branch base on current branch in config
@@ -14,6 +14,12 @@ pub(crate) struct NewRemoteBranchCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -30,15 +36,25 @@ pub(crate) struct NewRemoteBranchCommand {
     #[arg(long, action = ArgAction::SetTrue)]
     pub force: bool,
 
-    /// Ignore specified repositories to create new branch
+    /// Ignore specified repositories to create new branch, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for NewRemoteBranchCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let msg = ops::new_remote_branch(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector);
+        let options = NewBranchOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::new_remote_branch(options, progress).await?;
         print_style_message(&msg);
         Ok(())
     }
@@ -46,13 +62,16 @@ impl CliCommand for NewRemoteBranchCommand {
 
 impl From<NewRemoteBranchCommand> for NewBranchOptions {
     fn from(value: NewRemoteBranchCommand) -> Self {
-        NewBranchOptions::new(
-            value.path,
-            value.config,
-            value.new_config,
-            value.branch,
-            value.force,
-            value.ignore,
-        )
+        NewBranchOptions {
+            wait: value.wait,
+            ..NewBranchOptions::new(
+                value.path,
+                value.config,
+                value.new_config,
+                value.branch,
+                value.force,
+                value.ignore,
+            )
+        }
     }
 }
@@ -4,9 +4,9 @@ use std::path::PathBuf;
 use mgit::error::MgitResult;
 use mgit::ops::{self, NewTagOptions};
 
-use crate::commands::CliCommand;
+use crate::commands::{repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs};
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// New tag
@@ -14,6 +14,12 @@ pub(crate) struct NewTagCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -26,15 +32,25 @@ pub(crate) struct NewTagCommand {
     #[arg(long, action = ArgAction::SetTrue)]
     pub push: bool,
 
-    /// Ignore specified repositories to create new tag
+    /// Ignore specified repositories to create new tag, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for NewTagCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let msg = ops::new_tag(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector);
+        let options = NewTagOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::new_tag(options, progress).await?;
         print_style_message(&msg);
         Ok(())
     }
@@ -42,12 +58,15 @@ impl CliCommand for NewTagCommand {
 
 impl From<NewTagCommand> for NewTagOptions {
     fn from(value: NewTagCommand) -> Self {
-        NewTagOptions::new(
-            value.path,
-            value.config,
-            value.tag,
-            value.push,
-            value.ignore,
-        )
+        NewTagOptions {
+            wait: value.wait,
+            ..NewTagOptions::new(
+                value.path,
+                value.config,
+                value.tag,
+                value.push,
+                value.ignore,
+            )
+        }
     }
 }
@@ -6,7 +6,7 @@ use mgit::ops::{self, SnapshotOptions, SnapshotType};
 
 use crate::commands::CliCommand;
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 /// Snapshot git repos
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
@@ -26,14 +26,18 @@ pub(crate) struct SnapshotCommand {
     #[arg(long, action = ArgAction::SetTrue)]
     pub force: bool,
 
-    /// Ignore specified repositories for snapshot
+    /// Ignore specified repositories for snapshot, by path or glob
     #[arg(long)]
     pub ignore: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for SnapshotCommand {
     async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
+        let progress = CliProgress::default();
         let msg = ops::snapshot_repo(self.into(), progress).await?;
         print_style_message(&msg);
         Ok(())
@@ -42,15 +46,18 @@ impl CliCommand for SnapshotCommand {
 
 impl From<SnapshotCommand> for SnapshotOptions {
     fn from(value: SnapshotCommand) -> Self {
-        SnapshotOptions::new(
-            value.path,
-            value.config,
-            Some(value.force),
-            match value.branch {
-                true => Some(SnapshotType::Branch),
-                false => Some(SnapshotType::Commit),
-            },
-            value.ignore,
-        )
+        SnapshotOptions {
+            wait: value.wait,
+            ..SnapshotOptions::new(
+                value.path,
+                value.config,
+                Some(value.force),
+                match value.branch {
+                    true => Some(SnapshotType::Branch),
+                    false => Some(SnapshotType::Commit),
+                },
+                value.ignore,
+            )
+        }
     }
 }
@@ -0,0 +1,103 @@
+use clap::{ArgAction, Args, Subcommand};
+use std::path::PathBuf;
+
+use mgit::error::MgitResult;
+use mgit::ops::{self, StashAction, StashOptions};
+
+use crate::commands::{
+    current_repo_only, repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs,
+};
+use crate::term::print_style_message;
+use crate::term::progress::CliProgress;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Args)]
+/// Manage the stashes `sync --stash` left in the repos
+pub(crate) struct StashCommand {
+    #[command(subcommand)]
+    pub action: StashSubcommand,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Subcommand)]
+pub(crate) enum StashSubcommand {
+    /// List the stashes created by mgit, grouped by repo
+    List(StashArgs),
+
+    /// List the stashes created by mgit with the files they change
+    Show(StashArgs),
+
+    /// Restore the newest stash created by mgit in every repo and keep it
+    Apply(StashArgs),
+
+    /// Restore the newest stash created by mgit in every repo and drop it
+    Pop(StashArgs),
+
+    /// Drop the newest stash created by mgit in every repo
+    Drop(StashArgs),
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct StashArgs {
+    /// The work directory
+    pub path: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
+    /// Use specified config file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Sets the number of threads to be used
+    #[arg(short, long, default_value_t = 4, value_name = "NUMBER")]
+    pub thread: usize,
+
+    /// Ignore specified repositories, by path or glob
+    #[arg(long)]
+    ignore: Option<Vec<String>>,
+
+    /// Labels for stash
+    #[arg(long)]
+    labels: Option<Vec<String>>,
+
+    /// Act on every repo of the workspace, not just the one holding the current directory
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub all: bool,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
+}
+
+impl CliCommand for StashCommand {
+    async fn exec(self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let (action, mut args) = match self.action {
+            StashSubcommand::List(args) => (StashAction::List, args),
+            StashSubcommand::Show(args) => (StashAction::Show, args),
+            StashSubcommand::Apply(args) => (StashAction::Apply, args),
+            StashSubcommand::Pop(args) => (StashAction::Pop, args),
+            StashSubcommand::Drop(args) => (StashAction::Drop, args),
+        };
+        let only = repo_selectors(&mut args.path, &args.config, &args.selector)
+            .or_else(|| current_repo_only(&args.path, &args.config, args.all));
+        let options = StashOptions {
+            only,
+            states: args.state.states(),
+            wait: args.wait,
+            ..StashOptions::new(
+                args.path,
+                args.config,
+                action,
+                Some(args.thread),
+                args.ignore,
+                args.labels,
+            )
+        };
+        let msg = ops::stash_repos(options, progress).await?;
+        print_style_message(&msg);
+        Ok(())
+    }
+}
@@ -3,10 +3,13 @@ use std::path::PathBuf;
 
 use mgit::error::MgitResult;
 use mgit::ops::{self, SyncOptions};
+use mgit::utils::concurrency::NetConcurrency;
 
-use crate::commands::CliCommand;
+use crate::commands::{
+    current_repo_only, repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs,
+};
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Sync git repos
@@ -14,6 +17,12 @@ pub(crate) struct SyncCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
@@ -30,6 +39,10 @@ pub(crate) struct SyncCommand {
     #[arg(short, long, default_value_t = 4, value_name = "NUMBER")]
     thread: usize,
 
+    /// Sets the number of network commands run at once, a number or `auto`
+    #[arg(long, value_name = "NUMBER|auto")]
+    net_thread: Option<NetConcurrency>,
+
     /// Do not report git status
     #[arg(long, action = ArgAction::SetTrue)]
     silent: bool,
@@ -42,23 +55,50 @@ pub(crate) struct SyncCommand {
     #[arg(long, action = ArgAction::SetTrue)]
     no_checkout: bool,
 
+    /// Do not go through the mirror cache even if enabled in the user config
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_cache: bool,
+
+    /// Do not download Git LFS objects, leave pointer files in the work tree
+    #[arg(long, action = ArgAction::SetTrue)]
+    skip_lfs: bool,
+
+    /// Fetch and reset every repo, even those already on the remote ref
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_fast_path: bool,
+
     /// Deepen history of shallow clone
     #[arg(short, long, value_name = "NUMBER")]
     depth: Option<usize>,
 
-    /// Ignore specified repositories for sync
+    /// Ignore specified repositories for sync, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
 
     /// Labels for sync
     #[arg(long)]
     labels: Option<Vec<String>>,
+
+    /// Act on every repo of the workspace, not just the one holding the current directory
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub all: bool,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for SyncCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let msg = ops::sync_repo(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector)
+            .or_else(|| current_repo_only(&self.path, &self.config, self.all));
+        let options = SyncOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::sync_repo(options, progress).await?;
         print_style_message(&msg);
         Ok(())
     }
@@ -66,18 +106,23 @@ impl CliCommand for SyncCommand {
 
 impl From<SyncCommand> for SyncOptions {
     fn from(value: SyncCommand) -> Self {
-        SyncOptions::new(
-            value.path,
-            value.config,
-            Some(value.thread),
-            Some(value.silent),
-            value.depth,
-            value.ignore,
-            value.labels,
-            Some(value.hard),
-            Some(value.stash),
-            Some(value.no_track),
-            Some(value.no_checkout),
-        )
+        SyncOptions::builder()
+            .path(value.path)
+            .config_path(value.config)
+            .thread_count(Some(value.thread))
+            .net_thread(value.net_thread)
+            .silent(Some(value.silent))
+            .depth(value.depth)
+            .ignore(value.ignore)
+            .labels(value.labels)
+            .hard(Some(value.hard))
+            .stash(Some(value.stash))
+            .no_track(Some(value.no_track))
+            .no_checkout(Some(value.no_checkout))
+            .cache(value.no_cache.then_some(false))
+            .skip_lfs(Some(value.skip_lfs))
+            .no_fast_path(Some(value.no_fast_path))
+            .wait(Some(value.wait))
+            .build()
     }
 }
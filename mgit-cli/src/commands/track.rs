@@ -1,12 +1,14 @@
-use clap::Args;
+use clap::{ArgAction, Args};
 use std::path::PathBuf;
 
 use mgit::error::MgitResult;
 use mgit::ops::{self, TrackOptions};
 
-use crate::commands::CliCommand;
+use crate::commands::{
+    current_repo_only, repo_selectors, CliCommand, RepoSelectorArgs, RepoStateArgs,
+};
 use crate::term::print_style_message;
-use crate::term::progress::MultiProgress;
+use crate::term::progress::CliProgress;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
 /// Track remote branch
@@ -14,19 +16,40 @@ pub(crate) struct TrackCommand {
     /// The work directory
     pub path: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
     /// Use specified config file
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
 
-    /// Ignore specified repositories for track
+    /// Ignore specified repositories for track, by path or glob
     #[arg(long)]
     ignore: Option<Vec<String>>,
+
+    /// Act on every repo of the workspace, not just the one holding the current directory
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub all: bool,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
 }
 
 impl CliCommand for TrackCommand {
-    async fn exec(self) -> MgitResult<()> {
-        let progress = MultiProgress::default();
-        let msg = ops::track(self.into(), progress).await?;
+    async fn exec(mut self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let only = repo_selectors(&mut self.path, &self.config, &self.selector)
+            .or_else(|| current_repo_only(&self.path, &self.config, self.all));
+        let options = TrackOptions {
+            only,
+            states: self.state.states(),
+            ..self.into()
+        };
+        let msg = ops::track(options, progress).await?;
         print_style_message(&msg);
         Ok(())
     }
@@ -34,6 +57,9 @@ impl CliCommand for TrackCommand {
 
 impl From<TrackCommand> for TrackOptions {
     fn from(value: TrackCommand) -> Self {
-        TrackOptions::new(value.path, value.config, value.ignore)
+        TrackOptions {
+            wait: value.wait,
+            ..TrackOptions::new(value.path, value.config, value.ignore)
+        }
     }
 }
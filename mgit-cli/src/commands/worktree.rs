@@ -0,0 +1,134 @@
+use clap::{ArgAction, Args, Subcommand};
+use std::path::PathBuf;
+
+use mgit::error::MgitResult;
+use mgit::ops::{self, WorktreeAddOptions, WorktreeListOptions, WorktreeRemoveOptions};
+
+use crate::commands::{CliCommand, RepoSelectorArgs, RepoStateArgs};
+use crate::term::print_style_message;
+use crate::term::progress::CliProgress;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Args)]
+/// Manage workspaces of git worktrees sharing the clones of a workspace
+pub(crate) struct WorktreeCommand {
+    #[command(subcommand)]
+    pub action: WorktreeAction,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Subcommand)]
+pub(crate) enum WorktreeAction {
+    /// Create a workspace of worktrees of the repos of an existing workspace
+    Add(WorktreeAddCommand),
+
+    /// List the worktrees of a workspace's repos
+    List(WorktreeListCommand),
+
+    /// Remove a workspace created by `worktree add`
+    Remove(WorktreeRemoveCommand),
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct WorktreeAddCommand {
+    /// Directory of the new workspace
+    pub dir: PathBuf,
+
+    #[command(flatten)]
+    pub selector: RepoSelectorArgs,
+
+    #[command(flatten)]
+    pub state: RepoStateArgs,
+
+    /// The existing workspace, default is the current directory
+    #[arg(long, value_name = "PATH")]
+    pub source: Option<PathBuf>,
+
+    /// Config of the new workspace, default is the `.gitrepos` of the existing workspace
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Sets the number of threads to be used
+    #[arg(short, long, default_value_t = 4, value_name = "NUMBER")]
+    pub thread: usize,
+
+    /// Ignore specified repositories, by path or glob
+    #[arg(long)]
+    ignore: Option<Vec<String>>,
+
+    /// Labels for worktree
+    #[arg(long)]
+    labels: Option<Vec<String>>,
+
+    /// Wait for other mgit runs in the new workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct WorktreeListCommand {
+    /// The work directory
+    pub path: Option<PathBuf>,
+
+    /// Use specified config file
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default, Args)]
+pub(crate) struct WorktreeRemoveCommand {
+    /// Directory of the workspace to remove
+    pub dir: PathBuf,
+
+    /// Remove worktrees with local changes too
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub force: bool,
+
+    /// Wait for other mgit runs in the workspace instead of failing
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub wait: bool,
+}
+
+impl CliCommand for WorktreeCommand {
+    async fn exec(self) -> MgitResult<()> {
+        let progress = CliProgress::default();
+        let msg = match self.action {
+            WorktreeAction::Add(cmd) => ops::worktree_add(cmd.into(), progress).await?,
+            WorktreeAction::List(cmd) => ops::worktree_list(cmd.into()).await?,
+            WorktreeAction::Remove(cmd) => ops::worktree_remove(cmd.into(), progress).await?,
+        };
+        print_style_message(&msg);
+        Ok(())
+    }
+}
+
+impl From<WorktreeAddCommand> for WorktreeAddOptions {
+    fn from(value: WorktreeAddCommand) -> Self {
+        WorktreeAddOptions {
+            only: value.selector.selectors(),
+            states: value.state.states(),
+            wait: value.wait,
+            ..WorktreeAddOptions::new(
+                value.dir,
+                value.source,
+                value.config,
+                Some(value.thread),
+                value.ignore,
+                value.labels,
+            )
+        }
+    }
+}
+
+impl From<WorktreeListCommand> for WorktreeListOptions {
+    fn from(value: WorktreeListCommand) -> Self {
+        WorktreeListOptions::new(value.path, value.config)
+    }
+}
+
+impl From<WorktreeRemoveCommand> for WorktreeRemoveOptions {
+    fn from(value: WorktreeRemoveCommand) -> Self {
+        WorktreeRemoveOptions {
+            wait: value.wait,
+            ..WorktreeRemoveOptions::new(value.dir, Some(value.force))
+        }
+    }
+}
@@ -1,10 +1,13 @@
-﻿use clap::Parser;
+﻿use std::sync::Arc;
+
+use clap::Parser;
 use mgit::error::MgitResult;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 use crate::cli::{Cli, Commands};
 use crate::commands::CliCommand;
-use crate::term::{colors_enabled, configure_color};
+use crate::term::progress::configure_progress;
+use crate::term::{colors_enabled, configure_color, TerminalShell};
 
 mod cli;
 mod commands;
@@ -12,13 +15,20 @@ mod term;
 
 #[tokio::main]
 async fn main() {
+    // Started by git or ssh as GIT_ASKPASS / SSH_ASKPASS
+    if let Some(code) = mgit::utils::askpass::run_helper() {
+        std::process::exit(code);
+    }
+
     // Clean up any stale .old binary left by a previous `upgrade` on Windows.
     #[cfg(target_os = "windows")]
     clean_old_binary();
 
     let cli = Cli::parse();
     configure_color(!cli.no_color);
+    configure_progress(cli.progress);
     init_log(cli.verbose);
+    mgit::utils::askpass::set_shell(Arc::new(TerminalShell));
 
     let result: MgitResult = match cli.command {
         Commands::Init(cmd) => cmd.exec().await,
@@ -26,12 +36,17 @@ async fn main() {
         Commands::Fetch(cmd) => cmd.exec().await,
         Commands::Sync(cmd) => cmd.exec().await,
         Commands::Clean(cmd) => cmd.exec().await,
+        Commands::Doctor(cmd) => cmd.exec().await,
+        Commands::Drift(cmd) => cmd.exec().await,
         Commands::ListFiles(cmd) => cmd.exec().await,
         Commands::Track(cmd) => cmd.exec().await,
         Commands::LogRepos(cmd) => cmd.exec().await,
         Commands::NewRemoteBranch(cmd) => cmd.exec().await,
         Commands::DelRemoteBranch(cmd) => cmd.exec().await,
         Commands::NewTag(cmd) => cmd.exec().await,
+        Commands::Cache(cmd) => cmd.exec().await,
+        Commands::Worktree(cmd) => cmd.exec().await,
+        Commands::Stash(cmd) => cmd.exec().await,
         Commands::Upgrade(cmd) => cmd.exec().await,
     };
 
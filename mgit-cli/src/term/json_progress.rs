@@ -0,0 +1,110 @@
+use std::io::Write;
+
+use mgit::utils::progress::{Progress, RepoInfo, TransferProgress};
+use mgit::utils::style_message::StyleMessage;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::term::timestamp;
+
+/// Progress written to stdout as one JSON object per line
+///
+/// Every event has `timestamp` and `event`, repo events add `id`, `index`
+/// and `path`. Messages are plain text, without colors.
+#[derive(Clone, Default)]
+pub(crate) struct JsonProgress;
+
+/// Event line, `timestamp` and `event` lead the fields of the event
+#[derive(Serialize)]
+struct Event<'a> {
+    timestamp: String,
+    event: &'a str,
+    #[serde(flatten)]
+    fields: Value,
+}
+
+/// JSON line of an event
+pub(crate) fn event_line(event: &str, fields: Value) -> Option<String> {
+    let event = Event {
+        timestamp: timestamp(),
+        event,
+        fields,
+    };
+    serde_json::to_string(&event).ok()
+}
+
+/// Fields of a repo event
+pub(crate) fn repo_fields(repo_info: &RepoInfo, message: &StyleMessage) -> Value {
+    json!({
+        "id": repo_info.id,
+        "index": repo_info.index,
+        "path": repo_info.rel_path(),
+        "message": plain_text(message),
+    })
+}
+
+impl JsonProgress {
+    fn emit(&self, event: &str, fields: Value) {
+        let Some(line) = event_line(event, fields) else {
+            return;
+        };
+        // one write per line, so events of parallel repos never interleave
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", line);
+        let _ = stdout.flush();
+    }
+
+    fn emit_repo(&self, event: &str, repo_info: &RepoInfo, message: &StyleMessage) {
+        self.emit(event, repo_fields(repo_info, message));
+    }
+}
+
+/// Message without colors, also for parts rendered into the text upfront
+fn plain_text(message: &StyleMessage) -> String {
+    console::strip_ansi_codes(&message.to_plain_text()).to_string()
+}
+
+impl Progress for JsonProgress {
+    fn on_batch_start(&self, total: usize) {
+        self.emit("batch_start", json!({ "total": total }));
+    }
+
+    fn on_batch_finish(&self) {
+        self.emit("batch_finish", json!({}));
+    }
+
+    fn on_repo_start(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.emit_repo("repo_start", repo_info, &message);
+    }
+
+    fn on_repo_update(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.emit_repo("repo_update", repo_info, &message);
+    }
+
+    fn on_repo_success(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.emit_repo("repo_success", repo_info, &message);
+    }
+
+    fn on_repo_error(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.emit_repo("repo_error", repo_info, &message);
+    }
+
+    fn on_message(&self, message: StyleMessage) {
+        self.emit("message", json!({ "message": plain_text(&message) }));
+    }
+
+    fn on_repo_transfer(&self, repo_info: &RepoInfo, transfer: &TransferProgress) {
+        self.emit(
+            "repo_transfer",
+            json!({
+                "id": repo_info.id,
+                "index": repo_info.index,
+                "path": repo_info.rel_path(),
+                "phase": transfer.phase.to_string(),
+                "percent": transfer.percent,
+                "bytes": transfer.bytes,
+                "rate": transfer.rate,
+            }),
+        );
+    }
+}
@@ -1,10 +1,21 @@
-﻿pub mod progress;
+﻿pub mod json_progress;
+pub mod plain_progress;
+pub mod progress;
+
+#[cfg(test)]
+mod tests;
 
 use std::io::{self, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use mgit::utils::progress::Progress;
 use mgit::utils::shell::{Auth, ShellInteraction};
 use mgit::utils::style_message::StyleMessage;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::term::json_progress::JsonProgress;
+use crate::term::progress::{progress_mode, ProgressMode};
 
 static COLOR_OUTPUT_ENABLED: AtomicBool = AtomicBool::new(true);
 
@@ -32,44 +43,68 @@ pub(crate) fn render_style_message(message: &StyleMessage) -> String {
 }
 
 pub(crate) fn print_style_message(message: &StyleMessage) {
+    // keep stdout parseable, results become `message` events
+    if progress_mode() == ProgressMode::Json {
+        JsonProgress.on_message(message.clone());
+        return;
+    }
     println!("{}", render_style_message(message));
 }
 
+/// Current UTC time in RFC 3339, for progress lines and events
+pub(crate) fn timestamp() -> String {
+    OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
 #[derive(Default)]
 pub struct TerminalShell;
 
 impl TerminalShell {
-    #[allow(dead_code)]
-    fn prompt_line(prompt: &str) -> io::Result<String> {
-        let mut stderr = io::stderr();
-        write!(stderr, "{}", prompt)?;
-        stderr.flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        Ok(input.trim().to_string())
+    fn prompt_line(prompt: &str, secure: bool) -> io::Result<String> {
+        // keep spinners from drawing over the prompt
+        progress::suspend(|| {
+            let term = console::Term::stderr();
+            term.write_str(prompt)?;
+            let input = match secure {
+                true => term.read_secure_line()?,
+                false => term.read_line()?,
+            };
+            Ok(input.trim().to_string())
+        })
     }
 }
 
 impl ShellInteraction for TerminalShell {
     fn warn(&self, msg: &str) {
-        let _ = writeln!(io::stderr(), "{}", msg);
+        progress::suspend(|| {
+            let _ = writeln!(io::stderr(), "{}", msg);
+        })
     }
 
     fn ask_ssh_trust(&self, fingerprint: &str) -> bool {
         let prompt = format!("Trust SSH fingerprint {}? [y/N]: ", fingerprint);
-        match Self::prompt_line(&prompt) {
+        match Self::prompt_line(&prompt, false) {
             Ok(s) => matches!(s.as_str(), "y" | "Y" | "yes" | "YES" | "Yes"),
             Err(_) => false,
         }
     }
 
-    fn ask_http_auth(&self) -> Option<Auth> {
-        let username = Self::prompt_line("HTTP username (empty to cancel): ").ok()?;
+    fn ask_http_auth(&self, host: &str) -> Option<Auth> {
+        let prompt = format!("Username for {} (empty to cancel): ", host);
+        let username = Self::prompt_line(&prompt, false).ok()?;
         if username.is_empty() {
             return None;
         }
-        let password = Self::prompt_line("HTTP password: ").ok()?;
+        let prompt = format!("Password for {}@{}: ", username, host);
+        let password = Self::prompt_line(&prompt, true).ok()?;
         Some(Auth { username, password })
     }
+
+    fn ask_ssh_passphrase(&self, key: &str) -> Option<String> {
+        let prompt = format!("Passphrase for key {} (empty to cancel): ", key);
+        let passphrase = Self::prompt_line(&prompt, true).ok()?;
+        (!passphrase.is_empty()).then_some(passphrase)
+    }
 }
@@ -0,0 +1,79 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use mgit::utils::path::PathExtension;
+use mgit::utils::progress::{Progress, RepoInfo};
+use mgit::utils::style_message::StyleMessage;
+
+use crate::term::timestamp;
+
+/// Progress written to stderr as timestamped lines, for logs and pipes
+///
+/// Nothing is redrawn, so the frequent transfer updates are left out, and
+/// messages are written without colours.
+#[derive(Clone, Default)]
+pub(crate) struct PlainProgress {
+    total_repos: Arc<AtomicUsize>,
+}
+
+impl PlainProgress {
+    fn print(&self, line: String) {
+        let _ = writeln!(std::io::stderr(), "{} {}", timestamp(), line);
+    }
+
+    fn print_repo(&self, repo_info: &RepoInfo, status: &str, message: &StyleMessage) {
+        let total = self.total_repos.load(Ordering::Relaxed);
+        self.print(repo_line(total, repo_info, status, message));
+    }
+}
+
+/// Line of a repo event, before the timestamp
+pub(crate) fn repo_line(
+    total: usize,
+    repo_info: &RepoInfo,
+    status: &str,
+    message: &StyleMessage,
+) -> String {
+    format!(
+        "[{:02}/{:02}] {}{}: {}",
+        repo_info.index,
+        total,
+        status,
+        repo_info.rel_path().display_path(),
+        message.to_plain_text()
+    )
+}
+
+/// Status of a finished repo, before its path
+pub(crate) fn repo_end_status(is_success: bool) -> String {
+    format!("{} ", StyleMessage::repo_end(is_success).to_plain_text())
+}
+
+impl Progress for PlainProgress {
+    fn on_batch_start(&self, total: usize) {
+        self.total_repos.store(total, Ordering::Relaxed);
+    }
+
+    fn on_batch_finish(&self) {}
+
+    fn on_repo_start(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.print_repo(repo_info, "", &message);
+    }
+
+    fn on_repo_update(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.print_repo(repo_info, "", &message);
+    }
+
+    fn on_repo_success(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.print_repo(repo_info, &repo_end_status(true), &message);
+    }
+
+    fn on_repo_error(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        self.print_repo(repo_info, &repo_end_status(false), &message);
+    }
+
+    fn on_message(&self, message: StyleMessage) {
+        self.print(message.to_plain_text());
+    }
+}
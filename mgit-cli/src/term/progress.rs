@@ -1,16 +1,60 @@
 use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 
 use ansi_term::Colour;
 use indicatif::{ProgressBar, ProgressStyle};
 use mgit::utils::path::PathExtension;
 
-use mgit::utils::progress::{Progress, RepoInfo};
+use mgit::utils::progress::{Progress, RepoInfo, TransferProgress};
 use mgit::utils::style_message::StyleMessage;
 
+use crate::term::json_progress::JsonProgress;
+use crate::term::plain_progress::PlainProgress;
 use crate::term::{colors_enabled, render_style_message};
 
+/// How progress is reported, `--progress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum ProgressMode {
+    /// Spinners redrawn on the terminal
+    Tty,
+    /// Timestamped lines on stderr
+    Plain,
+    /// One JSON object per event on stdout
+    Json,
+}
+
+static PROGRESS_MODE: OnceLock<ProgressMode> = OnceLock::new();
+
+/// Select the progress output, spinners unless stderr is not a terminal
+pub(crate) fn configure_progress(mode: Option<ProgressMode>) {
+    let mode = mode.unwrap_or(match std::io::stderr().is_terminal() {
+        true => ProgressMode::Tty,
+        false => ProgressMode::Plain,
+    });
+    let _ = PROGRESS_MODE.set(mode);
+}
+
+pub(crate) fn progress_mode() -> ProgressMode {
+    PROGRESS_MODE.get().copied().unwrap_or(ProgressMode::Tty)
+}
+
+/// Progress bars currently drawn on the terminal, if any
+static ACTIVE_MULTI_PROGRESS: Mutex<Option<indicatif::MultiProgress>> = Mutex::new(None);
+
+/// Hide the active progress bars while `f` writes to the terminal
+pub(crate) fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    let active = ACTIVE_MULTI_PROGRESS
+        .lock()
+        .expect("Failed to lock active multi_progress")
+        .clone();
+    match active {
+        Some(multi_progress) => multi_progress.suspend(f),
+        None => f(),
+    }
+}
+
 #[derive(Clone, Default)]
 pub(crate) struct MultiProgress {
     multi_progress: Arc<Mutex<indicatif::MultiProgress>>,
@@ -106,6 +150,14 @@ impl Progress for MultiProgress {
     fn on_batch_start(&self, total: usize) {
         self.total_repos.store(total, Ordering::Relaxed);
         self.create_total_bar(total);
+        *ACTIVE_MULTI_PROGRESS
+            .lock()
+            .expect("Failed to lock active multi_progress") = Some(
+            self.multi_progress
+                .lock()
+                .expect("Failed to lock multi_progress")
+                .clone(),
+        );
     }
 
     fn on_batch_finish(&self) {
@@ -137,6 +189,10 @@ impl Progress for MultiProgress {
             .set_message(truncate_spinner_msg(self.spinner_info(repo_info, message)));
     }
 
+    fn on_repo_transfer(&self, repo_info: &RepoInfo, transfer: &TransferProgress) {
+        self.on_repo_update(repo_info, transfer.to_string().into());
+    }
+
     fn on_repo_success(&self, repo_info: &RepoInfo, message: StyleMessage) {
         let locked = self
             .spinner_progress_bars
@@ -185,6 +241,68 @@ impl Progress for MultiProgress {
     }
 }
 
+/// Progress of the mode selected by `--progress`
+#[derive(Clone)]
+pub(crate) enum CliProgress {
+    Tty(MultiProgress),
+    Plain(PlainProgress),
+    Json(JsonProgress),
+}
+
+impl Default for CliProgress {
+    fn default() -> Self {
+        match progress_mode() {
+            ProgressMode::Tty => Self::Tty(MultiProgress::default()),
+            ProgressMode::Plain => Self::Plain(PlainProgress::default()),
+            ProgressMode::Json => Self::Json(JsonProgress),
+        }
+    }
+}
+
+macro_rules! delegate {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self {
+            CliProgress::Tty(progress) => progress.$method($($arg),*),
+            CliProgress::Plain(progress) => progress.$method($($arg),*),
+            CliProgress::Json(progress) => progress.$method($($arg),*),
+        }
+    };
+}
+
+impl Progress for CliProgress {
+    fn on_batch_start(&self, total: usize) {
+        delegate!(self.on_batch_start(total))
+    }
+
+    fn on_batch_finish(&self) {
+        delegate!(self.on_batch_finish())
+    }
+
+    fn on_repo_start(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        delegate!(self.on_repo_start(repo_info, message))
+    }
+
+    fn on_repo_update(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        delegate!(self.on_repo_update(repo_info, message))
+    }
+
+    fn on_repo_success(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        delegate!(self.on_repo_success(repo_info, message))
+    }
+
+    fn on_repo_error(&self, repo_info: &RepoInfo, message: StyleMessage) {
+        delegate!(self.on_repo_error(repo_info, message))
+    }
+
+    fn on_message(&self, message: StyleMessage) {
+        delegate!(self.on_message(message))
+    }
+
+    fn on_repo_transfer(&self, repo_info: &RepoInfo, transfer: &TransferProgress) {
+        delegate!(self.on_repo_transfer(repo_info, transfer))
+    }
+}
+
 pub fn get_terminal_width() -> usize {
     match console::Term::stdout().size() {
         (_, width) if width > 10 => width as usize - 10,
@@ -0,0 +1,87 @@
+//! Unit tests for the progress output formats
+
+#[cfg(test)]
+mod json_progress_tests {
+    use mgit::config::RepoConfig;
+    use mgit::utils::progress::RepoInfo;
+    use mgit::utils::style_message::StyleMessage;
+    use serde_json::{json, Value};
+
+    use crate::term::json_progress::{event_line, repo_fields};
+
+    /// Test the fields of a repo event, with colors stripped from the message
+    #[test]
+    fn test_repo_event() {
+        let repo_config = RepoConfig {
+            local: Some("client/engine".to_string()),
+            ..Default::default()
+        };
+        let repo_info = RepoInfo::new(3, 2, &repo_config);
+        let message = StyleMessage::from("\u{1b}[32mdone\u{1b}[0m in 1s");
+
+        let line = event_line("repo_success", repo_fields(&repo_info, &message)).unwrap();
+        let event: Value = serde_json::from_str(&line).unwrap();
+        let timestamp = event["timestamp"].as_str().unwrap();
+        assert!(timestamp.contains('T') && timestamp.ends_with('Z'));
+        assert_eq!(event["event"], "repo_success");
+        assert_eq!(event["id"], 3);
+        assert_eq!(event["index"], 2);
+        assert_eq!(event["path"], "client/engine");
+        assert_eq!(event["message"], "done in 1s");
+
+        // `timestamp` and `event` lead every line
+        assert!(line.starts_with(r#"{"timestamp":"#));
+        assert!(line.contains(r#","event":"repo_success","#));
+    }
+
+    /// Test an event without a repo
+    #[test]
+    fn test_batch_event() {
+        let line = event_line("batch_start", json!({ "total": 4 })).unwrap();
+        let event: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["event"], "batch_start");
+        assert_eq!(event["total"], 4);
+        assert!(event.get("path").is_none());
+    }
+}
+
+#[cfg(test)]
+mod plain_progress_tests {
+    use mgit::config::RepoConfig;
+    use mgit::utils::progress::RepoInfo;
+    use mgit::utils::style_message::StyleMessage;
+
+    use crate::term::plain_progress::{repo_end_status, repo_line};
+
+    /// Test the line of a repo event, without colors
+    #[test]
+    fn test_repo_line() {
+        let repo_config = RepoConfig {
+            local: Some("client/engine".to_string()),
+            ..Default::default()
+        };
+        let repo_info = RepoInfo::new(0, 2, &repo_config);
+        let message = StyleMessage::from("fetch...");
+        assert_eq!(
+            repo_line(12, &repo_info, "", &message),
+            "[02/12] client/engine: fetch..."
+        );
+
+        let message = StyleMessage::repo_end(true);
+        let line = repo_line(12, &repo_info, &repo_end_status(false), &message);
+        assert_eq!(line, "[02/12] x client/engine: √");
+        assert!(!line.contains('\u{1b}'));
+
+        // the root repo has an empty `local`
+        let repo_config = RepoConfig {
+            local: Some(String::new()),
+            ..Default::default()
+        };
+        let repo_info = RepoInfo::new(0, 1, &repo_config);
+        let message = StyleMessage::from("ok");
+        assert_eq!(
+            repo_line(1, &repo_info, &repo_end_status(true), &message),
+            "[01/01] √ .: ok"
+        );
+    }
+}
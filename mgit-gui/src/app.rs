@@ -15,7 +15,7 @@ use tracing::{debug, info, warn};
 
 use crate::app::context::{AppContext, PendingConfigSave, RepoState};
 use crate::app::events::{Action, BackendEvent, CommandType, Event, InputEvent};
-use crate::ui::windows::{ErrorWindow, OptionsWindow, UpgradeState, WindowManager};
+use crate::ui::windows::{AskpassWindow, ErrorWindow, OptionsWindow, UpgradeState, WindowManager};
 use mgit::utils::upgrade_check;
 
 pub mod context;
@@ -24,6 +24,7 @@ pub mod repo_manager;
 pub mod session_manager;
 
 use crate::ui::style::{configure_text_styles, setup_custom_fonts};
+use crate::utils::shell::GuiShell;
 use crate::utils::system::{check_git_valid, GIT_VERSION};
 
 use crate::app::repo_manager::RepoManager;
@@ -99,6 +100,10 @@ impl GuiApp {
             first_frame: true,
             ..GuiApp::default()
         };
+        mgit::utils::askpass::set_shell(Arc::new(GuiShell::new(
+            app.app_context.event_tx.clone(),
+            cc.egui_ctx.clone(),
+        )));
 
         match git_check_result {
             Ok(info) => {
@@ -408,6 +413,13 @@ impl GuiApp {
             InputEvent::UpgradeError { message } => {
                 self.windows.upgrade.state = UpgradeState::Error { message };
             }
+
+            InputEvent::AskpassRequest(request) => {
+                // prompts are serialized, a pending window would only be stale
+                self.windows.askpass.finish();
+                self.windows.askpass = AskpassWindow::new(request);
+                self.windows.askpass_open = true;
+            }
         }
     }
 
@@ -1,6 +1,8 @@
 ﻿use std::path::PathBuf;
+use std::sync::mpsc::Sender;
 
 use mgit::ops::{NewBranchOptions, NewTagOptions};
+use mgit::utils::shell::Auth;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CommandType {
@@ -60,6 +62,25 @@ pub(crate) enum InputEvent {
     UpgradeError {
         message: String,
     },
+    /// git or ssh is waiting for credentials of a running op.
+    AskpassRequest(AskpassRequest),
+}
+
+/// Credential prompt forwarded from the askpass helper, answered through `reply`.
+#[derive(Debug)]
+pub(crate) enum AskpassRequest {
+    HttpAuth {
+        host: String,
+        reply: Sender<Option<Auth>>,
+    },
+    SshTrust {
+        fingerprint: String,
+        reply: Sender<bool>,
+    },
+    SshPassphrase {
+        key: String,
+        reply: Sender<Option<String>>,
+    },
 }
 
 #[derive(Debug)]
@@ -12,7 +12,7 @@ use crate::configs::SyncType;
 use crate::ui::windows::WindowManager;
 use crate::utils::fs;
 use crate::utils::progress::OpsMessageCollector;
-use mgit::config::{cmp_local_remote, MgitConfig, RepoConfig};
+use mgit::config::{cmp_local_remote, LocalConfig, MgitConfig, RepoConfig};
 use mgit::git;
 use mgit::ops;
 use mgit::ops::{
@@ -88,6 +88,10 @@ pub struct PendingConfigSave {
 
 pub struct RepoManager {
     pub mgit_config: MgitConfig,
+    /// The config file alone, edits are saved to it
+    pub shared_config: MgitConfig,
+    /// `.gitrepos.local` of the config file
+    pub local_config: Option<LocalConfig>,
     pub repo_states: Vec<RepoState>,
     pub remote_branches_cache: HashMap<String, RemoteBranchesCacheEntry>,
     pub remote_ref_edit_idx: i32,
@@ -105,6 +109,8 @@ impl RepoManager {
     pub fn new(event_tx: Sender<Event>, progress: Arc<AtomicUsize>) -> Self {
         Self {
             mgit_config: MgitConfig::default(),
+            shared_config: MgitConfig::default(),
+            local_config: None,
             repo_states: Vec::new(),
             remote_branches_cache: HashMap::new(),
             remote_ref_edit_idx: -1,
@@ -123,6 +129,8 @@ impl RepoManager {
             match MgitConfig::load(config_file) {
                 Some(mgit_config) => {
                     self.mgit_config = mgit_config;
+                    self.shared_config = MgitConfig::load_shared(config_file).unwrap_or_default();
+                    self.local_config = LocalConfig::load(config_file);
                 }
                 None => {
                     let _ = self
@@ -140,6 +148,8 @@ impl RepoManager {
 
     pub fn clear_mgit_config(&mut self) {
         self.mgit_config = MgitConfig::default();
+        self.shared_config = MgitConfig::default();
+        self.local_config = None;
         self.repo_states.clear();
         let empty = Vec::new();
         self.ops_message_collector.update(&empty);
@@ -183,6 +193,20 @@ impl RepoManager {
         self.ops_message_collector.update(repo_configs);
     }
 
+    /// Whether the ref of the repo at `local` is set by `.gitrepos.local`
+    pub fn is_ref_set_locally(&self, local: &str) -> bool {
+        self.local_config
+            .as_ref()
+            .is_some_and(|local_config| local_config.sets_ref(local))
+    }
+
+    /// Content of the config file with the edited refs, leaving `.gitrepos.local` out
+    pub fn shared_config_content(&mut self) -> String {
+        self.shared_config
+            .update_refs(&self.mgit_config, self.local_config.as_ref());
+        self.shared_config.serialize()
+    }
+
     pub fn schedule_config_save(&mut self, run_id: u64, config_file: String, content: String) {
         let replaced = self
             .pending_config_save
@@ -507,6 +531,7 @@ impl RepoManager {
                     depth,
                     ignore,
                     labels,
+                    None,
                 );
 
                 self.reset_repo_state(StateType::Updating);
@@ -15,6 +15,11 @@ pub(crate) mod ui;
 pub(crate) mod utils;
 
 fn main() -> eframe::Result {
+    // Started by git or ssh as GIT_ASKPASS / SSH_ASKPASS
+    if let Some(code) = mgit::utils::askpass::run_helper() {
+        std::process::exit(code);
+    }
+
     let _guard = init_log();
 
     let t_start = std::time::Instant::now();
@@ -1,5 +1,5 @@
 ﻿pub(crate) mod progress;
 pub(crate) mod text;
 
-pub(crate) use progress::ProgressBar;
+pub(crate) use progress::{ProgressBar, TransferBar};
 pub(crate) use text::{create_layout_job, create_layout_jobs, create_truncate_layout_job};
@@ -6,6 +6,8 @@ use std::time::Duration;
 use eframe::egui;
 use eframe::egui::{vec2, NumExt, Rect, Response, Sense, Stroke, Ui, Widget};
 
+use mgit::utils::progress::TransferProgress;
+
 use crate::app::context::RepoState;
 
 pub(crate) struct ProgressBar {
@@ -84,3 +86,45 @@ impl Widget for ProgressBar {
         response
     }
 }
+
+/// Transfer progress of a repo fetch, a line like `ProgressBar` under its description
+pub(crate) struct TransferBar<'a> {
+    transfer: &'a TransferProgress,
+}
+
+impl<'a> TransferBar<'a> {
+    pub fn new(transfer: &'a TransferProgress) -> Self {
+        Self { transfer }
+    }
+}
+
+impl Widget for TransferBar<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let progress = self.transfer.percent as f32 / 100.0;
+        ui.vertical(|ui| {
+            ui.label(
+                egui::RichText::new(self.transfer.to_string())
+                    .small()
+                    .color(ui.style().visuals.weak_text_color()),
+            );
+
+            let width = ui.available_size_before_wrap().x.at_least(96.0);
+            let (outer_rect, _) = ui.allocate_exact_size(vec2(width, 2.0), Sense::hover());
+            if ui.is_rect_visible(outer_rect) {
+                let visual = ui.style().visuals.clone();
+                ui.painter().hline(
+                    outer_rect.x_range(),
+                    outer_rect.center().y,
+                    visual.widgets.noninteractive.bg_stroke,
+                );
+                let inner_rect = Rect::from_min_size(
+                    outer_rect.min,
+                    vec2(outer_rect.width() * progress, outer_rect.height()),
+                );
+                ui.painter()
+                    .rect_filled(inner_rect, 0.0, visual.selection.bg_fill);
+            }
+        })
+        .response
+    }
+}
@@ -11,7 +11,9 @@ use tracing::{debug, info, warn};
 use crate::app::context::{RepoState, StateType};
 use crate::app::events::{BackendEvent, Event};
 use crate::app::GuiApp;
-use crate::ui::components::{create_layout_job, create_layout_jobs, create_truncate_layout_job};
+use crate::ui::components::{
+    create_layout_job, create_layout_jobs, create_truncate_layout_job, TransferBar,
+};
 use crate::ui::style::{hex_code, text_color};
 use crate::utils::system::{open_in_file_explorer, open_repo_in_fork};
 
@@ -148,7 +150,7 @@ impl RepositoriesPanel {
 
                 app.app_context.repo_manager.mgit_config.repos = Some(repo_configs);
                 if is_modified {
-                    let toml_string = app.app_context.repo_manager.mgit_config.serialize();
+                    let toml_string = app.app_context.repo_manager.shared_config_content();
                     let config_file = app.app_context.session_manager.config_file.clone();
                     app.app_context.repo_manager.schedule_config_save(
                         app.app_context.next_run_id(),
@@ -262,12 +264,19 @@ impl RepositoriesPanel {
                         egui::vec2(18.0, 18.0),
                     );
 
-                    let toggle_response = ui.put(
-                        widget_rect,
-                        egui::Button::new(hex_code::EDIT).selected(
-                            app.app_context.repo_manager.remote_ref_edit_idx == idx as i32,
-                        ),
-                    );
+                    // refs pinned in `.gitrepos.local` are edited there
+                    let set_locally = app.app_context.repo_manager.is_ref_set_locally(&rel_path);
+                    let toggle_response = ui
+                        .add_enabled_ui(!set_locally, |ui| {
+                            ui.put(
+                                widget_rect,
+                                egui::Button::new(hex_code::EDIT).selected(
+                                    app.app_context.repo_manager.remote_ref_edit_idx == idx as i32,
+                                ),
+                            )
+                        })
+                        .inner
+                        .on_disabled_hover_text("set in .gitrepos.local");
 
                     if toggle_response.clicked() {
                         app.app_context.repo_manager.remote_ref_edit_idx =
@@ -511,13 +520,12 @@ impl RepositoriesPanel {
                             ui.add(egui::widgets::Spinner::new());
                         });
 
-                        let job = create_layout_jobs(
-                            &app.app_context
-                                .repo_manager
-                                .ops_message_collector
-                                .read_ops_message(idx),
-                        );
+                        let collector = &app.app_context.repo_manager.ops_message_collector;
+                        let job = create_layout_jobs(&collector.read_ops_message(idx));
                         ui.label(job);
+                        if let Some(transfer) = collector.read_transfer(idx) {
+                            ui.add(TransferBar::new(&transfer));
+                        }
 
                         ui.add_space(4.0);
                     }
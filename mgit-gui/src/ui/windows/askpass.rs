@@ -0,0 +1,131 @@
+use eframe::egui::{Context, Vec2};
+use eframe::{egui, Frame};
+
+use mgit::utils::shell::Auth;
+
+use crate::app::events::AskpassRequest;
+
+#[derive(Default)]
+pub struct AskpassWindow {
+    request: Option<AskpassRequest>,
+    username: String,
+    password: String,
+    is_ok: Option<bool>,
+}
+
+impl AskpassWindow {
+    pub fn new(request: AskpassRequest) -> Self {
+        Self {
+            request: Some(request),
+            ..Default::default()
+        }
+    }
+
+    /// Send the answer back to the waiting op, closing the window counts as cancel
+    pub(crate) fn finish(&mut self) {
+        let is_ok = self.is_ok.take().unwrap_or_default();
+        match self.request.take() {
+            Some(AskpassRequest::HttpAuth { reply, .. }) => {
+                let auth = match is_ok && !self.username.is_empty() {
+                    true => Some(Auth {
+                        username: std::mem::take(&mut self.username),
+                        password: std::mem::take(&mut self.password),
+                    }),
+                    false => None,
+                };
+                reply.send(auth).ok();
+            }
+            Some(AskpassRequest::SshTrust { reply, .. }) => {
+                reply.send(is_ok).ok();
+            }
+            Some(AskpassRequest::SshPassphrase { reply, .. }) => {
+                let passphrase = match is_ok && !self.password.is_empty() {
+                    true => Some(std::mem::take(&mut self.password)),
+                    false => None,
+                };
+                reply.send(passphrase).ok();
+            }
+            None => {}
+        }
+        self.password.clear();
+    }
+}
+
+impl super::WindowBase for AskpassWindow {
+    fn name(&self) -> String {
+        match self.request {
+            Some(AskpassRequest::SshTrust { .. }) => "SSH Host Verification".to_string(),
+            _ => "Authentication".to_string(),
+        }
+    }
+
+    fn width(&self) -> f32 {
+        360.0
+    }
+
+    fn height(&self) -> f32 {
+        160.0
+    }
+
+    fn default_pos(&self, screen_rect: &Vec2) -> [f32; 2] {
+        [
+            (screen_rect.x - self.width()) * 0.5,
+            (screen_rect.y - self.height()) * 0.5,
+        ]
+    }
+
+    fn after_show(&mut self, _: &Context, _: &mut Frame, open: &mut bool) {
+        if self.is_ok.is_some() {
+            *open = false;
+        }
+    }
+}
+
+impl super::View for AskpassWindow {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.spacing_mut().item_spacing = egui::vec2(0.0, 10.0);
+        ui.add_space(5.0);
+
+        match &self.request {
+            Some(AskpassRequest::HttpAuth { host, .. }) => {
+                ui.label(format!("Credentials for {}", host));
+                egui::Grid::new("askpass_grid")
+                    .num_columns(2)
+                    .min_col_width(80.0)
+                    .show(ui, |ui| {
+                        ui.label("Username");
+                        ui.text_edit_singleline(&mut self.username);
+                        ui.end_row();
+
+                        ui.label("Password");
+                        ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                        ui.end_row();
+                    });
+            }
+            Some(AskpassRequest::SshTrust { fingerprint, .. }) => {
+                ui.label(format!("Trust SSH fingerprint {}?", fingerprint));
+            }
+            Some(AskpassRequest::SshPassphrase { key, .. }) => {
+                ui.label(format!("Passphrase for key {}", key));
+                ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+            }
+            None => {}
+        }
+
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(10.0, 0.0);
+            if ui
+                .add_sized([80.0, 20.0], egui::Button::new("Ok"))
+                .clicked()
+            {
+                self.is_ok = Some(true);
+            }
+            if ui
+                .add_sized([80.0, 20.0], egui::Button::new("Cancel"))
+                .clicked()
+            {
+                self.is_ok = Some(false);
+            }
+        });
+    }
+}
@@ -4,8 +4,8 @@ use crate::app::events::CommandType;
 use crate::app::events::OpsCommand;
 
 use super::{
-    AboutWindow, Dialog, DialogBase, ErrorWindow, NewBranchWindow, NewTagWindow, OptionsWindow,
-    UpgradeAction, UpgradeWindow, WindowBase,
+    AboutWindow, AskpassWindow, Dialog, DialogBase, ErrorWindow, NewBranchWindow, NewTagWindow,
+    OptionsWindow, UpgradeAction, UpgradeWindow, WindowBase,
 };
 
 #[derive(Default)]
@@ -46,6 +46,9 @@ pub(crate) struct WindowManager {
     pub(crate) upgrade: UpgradeWindow,
     pub(crate) upgrade_open: bool,
     pub(crate) upgrade_action: Option<UpgradeAction>,
+
+    pub(crate) askpass: AskpassWindow,
+    pub(crate) askpass_open: bool,
 }
 
 impl Default for WindowManager {
@@ -73,6 +76,8 @@ impl Default for WindowManager {
             upgrade: UpgradeWindow::default(),
             upgrade_open: false,
             upgrade_action: None,
+            askpass: AskpassWindow::default(),
+            askpass_open: false,
         }
     }
 }
@@ -163,6 +168,14 @@ impl WindowManager {
         self.upgrade.show(ctx, eframe, &mut self.upgrade_open);
         self.upgrade_action = self.upgrade.action.take();
 
+        // Credential prompt: answer the waiting op once the window closes.
+        if self.askpass_open {
+            self.askpass.show(ctx, eframe, &mut self.askpass_open);
+            if !self.askpass_open {
+                self.askpass.finish();
+            }
+        }
+
         out
     }
 }
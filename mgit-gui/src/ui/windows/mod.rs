@@ -1,4 +1,5 @@
 ﻿mod about;
+mod askpass;
 mod base;
 mod dialog;
 mod error;
@@ -11,6 +12,7 @@ mod repo_selection;
 mod upgrade;
 
 pub(crate) use about::AboutWindow;
+pub(crate) use askpass::AskpassWindow;
 pub(crate) use base::{View, WindowBase};
 pub(crate) use dialog::{Dialog, DialogBase};
 pub(crate) use error::ErrorWindow;
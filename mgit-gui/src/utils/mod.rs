@@ -2,4 +2,5 @@
 pub(crate) mod logger;
 pub(crate) mod progress;
 pub(crate) mod runtime;
+pub(crate) mod shell;
 pub(crate) mod system;
@@ -10,7 +10,7 @@ use regex::Regex;
 use tracing::{debug, error, info};
 
 use mgit::config::RepoConfig;
-use mgit::utils::progress::{Progress, RepoInfo};
+use mgit::utils::progress::{Progress, RepoInfo, TransferProgress};
 use mgit::utils::style_message::StyleMessage;
 
 use crate::app::events::CommandType;
@@ -21,6 +21,7 @@ use crate::utils::logger::log_dir;
 #[derive(Debug, Clone)]
 pub(crate) struct OpsMessageCollector {
     repo_state_buffers: Vec<Arc<Mutex<StyleMessage>>>,
+    repo_transfers: Vec<Arc<Mutex<Option<TransferProgress>>>>,
     file_loggers: Vec<Arc<Mutex<File>>>,
     repo_names: Vec<String>,
     sender: Arc<Mutex<Sender<Event>>>,
@@ -37,6 +38,7 @@ impl OpsMessageCollector {
     pub(crate) fn new(sender: Sender<Event>, progress: Arc<AtomicUsize>) -> Self {
         Self {
             repo_state_buffers: vec![],
+            repo_transfers: vec![],
             file_loggers: vec![],
             repo_names: vec![],
             sender: Arc::new(Mutex::new(sender)),
@@ -71,6 +73,9 @@ impl OpsMessageCollector {
         let repo_state_buffers = (0..repo_configs.len())
             .map(|_| Arc::new(Mutex::new(StyleMessage::default())))
             .collect();
+        let repo_transfers = (0..repo_configs.len())
+            .map(|_| Arc::new(Mutex::new(None)))
+            .collect();
 
         let repo_names = repo_configs
             .iter()
@@ -94,6 +99,7 @@ impl OpsMessageCollector {
             .collect();
 
         self.repo_state_buffers = repo_state_buffers;
+        self.repo_transfers = repo_transfers;
         self.file_loggers = file_loggers;
         self.repo_names = repo_names;
     }
@@ -106,6 +112,11 @@ impl OpsMessageCollector {
         vec![buffer.clone()]
     }
 
+    /// Transfer progress of the running fetch of a repo, if any
+    pub fn read_transfer(&self, idx: usize) -> Option<TransferProgress> {
+        self.repo_transfers.get(idx)?.lock().unwrap().clone()
+    }
+
     #[rustfmt::skip]
     pub fn generate_repo_name(id: usize, repo_config: &RepoConfig) -> String {
         let regex = Regex::new(r#"[^a-zA-Z0-9]+"#).unwrap();
@@ -171,11 +182,17 @@ impl Progress for OpsMessageCollector {
     fn on_repo_update(&self, repo_info: &RepoInfo, message: StyleMessage) {
         let ptr = self.repo_state_buffers[repo_info.id].clone();
         ptr.lock().unwrap().replace(message.clone());
+        // a new step ends the transfer of the previous one
+        *self.repo_transfers[repo_info.id].lock().unwrap() = None;
 
         let mut file = self.file_loggers[repo_info.id].lock().unwrap();
         writeln!(file, "{}", message.to_plain_text()).unwrap();
     }
 
+    fn on_repo_transfer(&self, repo_info: &RepoInfo, transfer: &TransferProgress) {
+        *self.repo_transfers[repo_info.id].lock().unwrap() = Some(transfer.clone());
+    }
+
     #[allow(unused_variables)]
     fn on_repo_success(&self, repo_info: &RepoInfo, message: StyleMessage) {
         let sender = self.sender.clone();
@@ -0,0 +1,60 @@
+use std::sync::mpsc::{channel, Sender};
+
+use eframe::egui;
+use mgit::utils::shell::{Auth, ShellInteraction};
+
+use crate::app::events::{AskpassRequest, Event, InputEvent};
+
+/// Answers git credential prompts with modal windows
+///
+/// Called from the askpass server thread, blocks until the window is closed.
+pub(crate) struct GuiShell {
+    event_tx: Sender<Event>,
+    context: egui::Context,
+}
+
+impl GuiShell {
+    pub(crate) fn new(event_tx: Sender<Event>, context: egui::Context) -> Self {
+        Self { event_tx, context }
+    }
+
+    fn request(&self, request: AskpassRequest) {
+        self.event_tx
+            .send(Event::Input(InputEvent::AskpassRequest(request)))
+            .ok();
+        self.context.request_repaint();
+    }
+}
+
+impl ShellInteraction for GuiShell {
+    fn warn(&self, msg: &str) {
+        tracing::warn!(message = msg, "askpass_warn");
+    }
+
+    fn ask_ssh_trust(&self, fingerprint: &str) -> bool {
+        let (reply, rx) = channel();
+        self.request(AskpassRequest::SshTrust {
+            fingerprint: fingerprint.to_string(),
+            reply,
+        });
+        rx.recv().unwrap_or_default()
+    }
+
+    fn ask_http_auth(&self, host: &str) -> Option<Auth> {
+        let (reply, rx) = channel();
+        self.request(AskpassRequest::HttpAuth {
+            host: host.to_string(),
+            reply,
+        });
+        rx.recv().ok().flatten()
+    }
+
+    fn ask_ssh_passphrase(&self, key: &str) -> Option<String> {
+        let (reply, rx) = channel();
+        self.request(AskpassRequest::SshPassphrase {
+            key: key.to_string(),
+            reply,
+        });
+        rx.recv().ok().flatten()
+    }
+}
@@ -69,12 +69,11 @@ pub fn check_git_valid() -> Result<GitVersionInfo, String> {
 
         let t = std::time::Instant::now();
         tracing::debug!("git_version_cmd_start");
-        let out = std::process::Command::new("cmd")
+        let out = std::process::Command::new(mgit::git::git_program())
             .creation_flags(CREATE_NO_WINDOW)
-            .arg("/C")
-            .arg("git --version")
+            .arg("--version")
             .output()
-            .expect("command failed to start");
+            .map_err(|_| String::from("git is not found!\n"))?;
         tracing::debug!(
             duration_ms = t.elapsed().as_millis(),
             "git_version_cmd_done"
@@ -86,10 +85,10 @@ pub fn check_git_valid() -> Result<GitVersionInfo, String> {
     let output = {
         let t = std::time::Instant::now();
         tracing::debug!("git_version_cmd_start");
-        let out = std::process::Command::new("git")
+        let out = std::process::Command::new(mgit::git::git_program())
             .arg("--version")
             .output()
-            .expect("command failed to start");
+            .map_err(|_| String::from("git is not found!\n"))?;
         tracing::debug!(
             duration_ms = t.elapsed().as_millis(),
             "git_version_cmd_done"
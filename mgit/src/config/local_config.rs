@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::{MgitConfig, RepoConfig};
+use crate::utils::path::PathExtension;
+
+/// Suffix of the personal overlay of a config file, `.gitrepos.local` for `.gitrepos`
+pub const LOCAL_CONFIG_SUFFIX: &str = ".local";
+
+/// Personal overlay of a config file, kept out of version control
+///
+/// It is merged over the shared config when loading. A repo with the `local`
+/// of a shared repo overrides its `branch`, `tag`, `commit`, `sparse` and
+/// `labels`, any other repo is added, and the repos in `ignore` are left out.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct LocalConfig {
+    /// `local` of the shared repos to leave out
+    pub ignore: Option<Vec<String>>,
+    pub repos: Option<Vec<RepoConfig>>,
+}
+
+impl LocalConfig {
+    /// Overlay file of `config_path`
+    pub fn file_path(config_path: impl AsRef<Path>) -> PathBuf {
+        let mut file_path = config_path.as_ref().as_os_str().to_owned();
+        file_path.push(LOCAL_CONFIG_SUFFIX);
+        PathBuf::from(file_path)
+    }
+
+    /// Load the overlay of `config_path`, `None` when there is none or it can't be parsed
+    pub fn load(config_path: impl AsRef<Path>) -> Option<Self> {
+        let path = Self::file_path(config_path);
+        if !path.is_file() {
+            return None;
+        }
+
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c.replace("\".\"", "\"\""),
+            Err(e) => {
+                warn!(
+                    path = path.to_string_lossy().as_ref(),
+                    error = %e,
+                    "toml_local_config_read_failed"
+                );
+                return None;
+            }
+        };
+
+        toml::from_str::<LocalConfig>(&content)
+            .inspect_err(|e| {
+                warn!(
+                    path = path.to_string_lossy().as_ref(),
+                    error = %e,
+                    "toml_local_config_parse_failed"
+                );
+            })
+            .ok()
+    }
+
+    /// Merge the overlay over `config`, repos only in the overlay are appended
+    pub fn apply(&self, config: &mut MgitConfig) {
+        let repos = config.repos.get_or_insert_with(Vec::new);
+
+        if let Some(ignore) = &self.ignore {
+            let ignore = ignore
+                .iter()
+                .map(|local| local.norm_path())
+                .collect::<HashSet<_>>();
+            let (ignored, kept) = std::mem::take(repos).into_iter().partition(|repo| {
+                repo.local
+                    .as_ref()
+                    .is_some_and(|local| ignore.contains(&local.norm_path()))
+            });
+            *repos = kept;
+            config.ignored_repos = ignored;
+        }
+
+        for local_repo in self.repos.iter().flatten() {
+            let Some(local) = local_repo.local.as_ref() else {
+                continue;
+            };
+            let local = local.norm_path();
+            match repos
+                .iter_mut()
+                .find(|repo| repo.local.as_ref().is_some_and(|l| l.norm_path() == local))
+            {
+                Some(repo) => override_repo(repo, local_repo),
+                None => repos.push(local_repo.clone()),
+            }
+        }
+    }
+
+    /// Whether the overlay sets the ref of the repo at `local`
+    pub fn sets_ref(&self, local: &str) -> bool {
+        let local = local.norm_path();
+        self.repos.iter().flatten().any(|repo| {
+            repo.local.as_ref().is_some_and(|l| l.norm_path() == local) && has_ref(repo)
+        })
+    }
+}
+
+fn has_ref(repo: &RepoConfig) -> bool {
+    repo.branch.is_some() || repo.tag.is_some() || repo.commit.is_some()
+}
+
+fn override_repo(repo: &mut RepoConfig, local_repo: &RepoConfig) {
+    // the ref is replaced as a whole, a shared commit would win over a pinned branch
+    if has_ref(local_repo) {
+        repo.branch = local_repo.branch.clone();
+        repo.tag = local_repo.tag.clone();
+        repo.commit = local_repo.commit.clone();
+    }
+    if local_repo.sparse.is_some() {
+        repo.sparse = local_repo.sparse.clone();
+    }
+    if local_repo.labels.is_some() {
+        repo.labels = local_repo.labels.clone();
+    }
+}
@@ -2,7 +2,9 @@ use serde::{Deserialize, Serialize};
 use std::{fs, path::Path};
 use tracing::warn;
 
+use crate::config::local_config::LocalConfig;
 use crate::config::repo_config::RepoConfig;
+use crate::utils::path::PathExtension;
 
 /// Main configuration structure for MGIT
 ///
@@ -16,12 +18,17 @@ pub struct MgitConfig {
     pub default_branch: Option<String>,
     /// Default remote name to use when not specified in repo config
     pub default_remote: Option<String>,
+    /// Partial clone filter of repos without their own `filter`, e.g. `blob:none`
+    pub default_filter: Option<String>,
     /// List of repository configurations
     pub repos: Option<Vec<RepoConfig>>,
+    /// Repos left out by the `ignore` of `.gitrepos.local`, which `clean` must not remove
+    #[serde(skip)]
+    pub ignored_repos: Vec<RepoConfig>,
 }
 
 impl MgitConfig {
-    /// Load configuration from a TOML file
+    /// Load configuration from a TOML file, with its `.gitrepos.local` overlay merged
     ///
     /// # Arguments
     ///
@@ -31,6 +38,17 @@ impl MgitConfig {
     ///
     /// Returns `Some(MgitConfig)` if the file exists and can be parsed, `None` otherwise.
     pub fn load(path: impl AsRef<Path>) -> Option<Self> {
+        let mut config = Self::load_shared(&path)?;
+        if let Some(local_config) = LocalConfig::load(&path) {
+            local_config.apply(&mut config);
+        }
+        Some(config)
+    }
+
+    /// Load configuration from a TOML file alone, to write it back
+    ///
+    /// Writing a config from `load` would commit the personal overlay.
+    pub fn load_shared(path: impl AsRef<Path>) -> Option<Self> {
         let path_ref = path.as_ref();
         if !path_ref.is_file() {
             return None;
@@ -74,6 +92,35 @@ impl MgitConfig {
     pub fn serialize(&self) -> String {
         serialize_config(self)
     }
+
+    /// Copy the refs of a loaded `config` to the repos of this shared config
+    ///
+    /// Repos whose ref comes from `local_config` keep their shared ref.
+    pub fn update_refs(&mut self, config: &MgitConfig, local_config: Option<&LocalConfig>) {
+        let Some(repos) = self.repos.as_mut() else {
+            return;
+        };
+        for repo in config.repos.iter().flatten() {
+            let Some(local) = repo.local.as_ref() else {
+                continue;
+            };
+            if local_config.is_some_and(|local_config| local_config.sets_ref(local)) {
+                continue;
+            }
+            let local = local.norm_path();
+            let shared_repo = repos.iter_mut().find(|shared| {
+                shared
+                    .local
+                    .as_ref()
+                    .is_some_and(|l| l.norm_path() == local)
+            });
+            if let Some(shared_repo) = shared_repo {
+                shared_repo.branch = repo.branch.clone();
+                shared_repo.tag = repo.tag.clone();
+                shared_repo.commit = repo.commit.clone();
+            }
+        }
+    }
 }
 
 /// Serialize configuration to TOML string format
@@ -110,6 +157,10 @@ pub fn serialize_config(config: &MgitConfig) -> String {
         out.push_str(&format!("default-remote = {}\n", item));
     }
 
+    if let Some(item) = table.get("default-filter") {
+        out.push_str(&format!("default-filter = {}\n", item));
+    }
+
     out.push('\n');
 
     if let Some(repos) = table.get("repos") {
@@ -127,10 +178,18 @@ pub fn serialize_config(config: &MgitConfig) -> String {
                 out.push_str(&format!("local = {}\n", item));
             }
 
+            if let Some(item) = table.get("alias") {
+                out.push_str(&format!("alias = {}\n", item));
+            }
+
             if let Some(item) = table.get("remote") {
                 out.push_str(&format!("remote = {}\n", item));
             }
 
+            if let Some(item) = table.get("fallbacks") {
+                out.push_str(&format!("fallbacks = {}\n", item));
+            }
+
             if let Some(item) = table.get("branch") {
                 out.push_str(&format!("branch = {}\n", item));
             }
@@ -143,10 +202,22 @@ pub fn serialize_config(config: &MgitConfig) -> String {
                 out.push_str(&format!("commit = {}\n", item));
             }
 
+            if let Some(item) = table.get("filter") {
+                out.push_str(&format!("filter = {}\n", item));
+            }
+
             if let Some(item) = table.get("sparse") {
                 out.push_str(&format!("sparse = {}\n", item));
             }
 
+            if let Some(item) = table.get("lfs") {
+                out.push_str(&format!("lfs = {}\n", item));
+            }
+
+            if let Some(item) = table.get("submodules") {
+                out.push_str(&format!("submodules = {}\n", item));
+            }
+
             if let Some(item) = table.get("labels") {
                 out.push_str(&format!("labels = {}\n", item));
             }
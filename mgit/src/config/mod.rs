@@ -1,8 +1,20 @@
+pub mod local_config;
 pub mod mgit_config;
 pub mod repo_config;
+pub mod repo_ignore;
+pub mod repo_selection;
+pub mod repo_status;
+pub mod user_config;
+pub mod workspace;
 
 #[cfg(test)]
 mod tests;
 
+pub use local_config::*;
 pub use mgit_config::*;
 pub use repo_config::*;
+pub use repo_ignore::*;
+pub use repo_selection::*;
+pub use repo_status::*;
+pub use user_config::*;
+pub use workspace::*;
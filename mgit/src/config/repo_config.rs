@@ -1,13 +1,13 @@
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
-use std::{collections::HashSet, path::Path};
+use std::path::Path;
 
+use crate::config::repo_status::{count_changed_files, count_commits, resolve_remote_ref};
+use crate::config::RepoSelection;
 use crate::error::MgitResult;
 use crate::git;
 use crate::git::RemoteRef;
-use crate::utils::label;
 use crate::utils::style_message::StyleMessage;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -16,15 +16,25 @@ pub struct RepoId {
     pub repo: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "kebab-case")]
 pub struct RepoConfig {
     pub local: Option<String>,
+    /// Short name to select the repo by on the command line
+    pub alias: Option<String>,
     pub remote: Option<String>,
+    /// Read-only mirrors fetched from when `remote` is unreachable
+    pub fallbacks: Option<Vec<String>>,
     pub branch: Option<String>,
     pub tag: Option<String>,
     pub commit: Option<String>,
     pub sparse: Option<Vec<String>>,
+    /// Partial clone filter, e.g. `blob:none` or `tree:0`, an empty string disables `default-filter`
+    pub filter: Option<String>,
+    /// Whether the repo uses Git LFS, detected from `.gitattributes` when not set
+    pub lfs: Option<bool>,
+    /// Whether sync checks out the submodules, default true
+    pub submodules: Option<bool>,
     pub labels: Option<Vec<String>>,
 }
 
@@ -38,6 +48,13 @@ impl RepoId {
 }
 
 impl RepoConfig {
+    /// Partial clone filter to apply, `None` for a full clone
+    pub fn partial_clone_filter(&self) -> Option<&str> {
+        self.filter
+            .as_deref()
+            .filter(|filter| !filter.trim().is_empty())
+    }
+
     pub async fn get_remote_name(&self, path: impl AsRef<Path>) -> MgitResult<String> {
         let remote_url = self
             .remote
@@ -68,39 +85,17 @@ impl RepoConfig {
     }
 }
 
+/// Selected repos of the config by their index in it
 pub fn repos_to_map_with_ignore(
     repos: Vec<RepoConfig>,
-    ignore: Option<&Vec<String>>,
-    labels: Option<&Vec<String>>,
-) -> HashMap<usize, RepoConfig> {
-    let mut map = HashMap::new();
-
-    let mut ignore_paths = match ignore {
-        Some(ignore_paths) => ignore_paths.iter().cloned().collect::<HashSet<_>>(),
-        None => HashSet::new(),
-    };
-
-    if ignore_paths.contains(".") {
-        ignore_paths.insert("".to_string());
-    }
-
-    for (idx, repo) in repos.into_iter().enumerate() {
-        let Some(local) = repo.local.as_ref() else {
-            continue;
-        };
-
-        if ignore_paths.contains(local) {
-            continue;
-        }
-
-        if let Some(labels) = labels {
-            if !label::check(&repo, labels) {
-                continue;
-            };
-        }
-        map.insert(idx, repo);
-    }
-    map
+    selection: &RepoSelection,
+) -> MgitResult<HashMap<usize, RepoConfig>> {
+    selection.check(&repos)?;
+    Ok(repos
+        .into_iter()
+        .enumerate()
+        .filter(|(_, repo)| selection.is_selected(repo))
+        .collect())
 }
 
 pub async fn cmp_local_remote(
@@ -117,56 +112,14 @@ pub async fn cmp_local_remote(
         })?;
     let full_path = input_path.as_ref().join(rel_path);
 
-    let mut toml_repo = toml_repo.to_owned();
-    if toml_repo.branch.is_none() {
-        toml_repo.branch = default_branch.to_owned();
-    }
-
-    let (remote_ref_str, remote_desc) = {
-        if use_tracking_remote {
-            let remote_ref_str: String = git::get_tracking_branch(&full_path).await?;
-            (remote_ref_str.clone(), remote_ref_str)
-        } else {
-            let remote_ref = toml_repo.get_remote_ref(&full_path).await?;
-            let remote_ref_str = match remote_ref.clone() {
-                RemoteRef::Commit(r) | RemoteRef::Tag(r) | RemoteRef::Branch(r) => r,
-            };
-            let remote_desc = match remote_ref {
-                RemoteRef::Commit(commit) => commit[..7].to_string(),
-                RemoteRef::Tag(r) | RemoteRef::Branch(r) => r,
-            };
-            (remote_ref_str, remote_desc)
-        }
-    };
+    let (remote_ref_str, remote_desc) =
+        resolve_remote_ref(&full_path, toml_repo, default_branch, use_tracking_remote).await?;
 
     if remote_desc.is_empty() {
         return Ok("not tracking".into());
     }
 
-    let mut changed_files: HashSet<String> = HashSet::new();
-
-    if let Ok(output) = git::get_untrack_files(&full_path).await {
-        for file in output.trim().lines() {
-            changed_files.insert(file.to_string());
-        }
-    }
-
-    if let Ok(output) = git::get_changed_files(&full_path).await {
-        for file in output.trim().lines() {
-            changed_files.insert(file.to_string());
-        }
-    }
-
-    if let Ok(output) = git::get_staged_files(&full_path).await {
-        for file in output.trim().lines() {
-            changed_files.insert(file.to_string());
-        }
-    }
-
-    let mut changes_desc: Option<StyleMessage> = None;
-    if !changed_files.is_empty() {
-        changes_desc = StyleMessage::git_changes(changed_files.len());
-    }
+    let changes_desc = StyleMessage::git_changes(count_changed_files(&full_path).await);
 
     let branch: String = git::get_current_branch(&full_path).await?;
 
@@ -174,18 +127,10 @@ pub async fn cmp_local_remote(
         return Ok("init commit".into());
     }
 
-    let branch_pair = format!("{}...{}", &branch, &remote_ref_str);
-    let mut commit_desc: Option<StyleMessage> = None;
-    if let Ok(output) = git::get_rev_list_count(&full_path, branch_pair).await {
-        let re = Regex::new(r"(\d+)\s*(\d+)").unwrap();
-
-        if let Some(caps) = re.captures(&output) {
-            let (ahead, behind) = (&caps[1], &caps[2]);
-            commit_desc = StyleMessage::git_commits(ahead, behind);
-        }
-    } else {
-        commit_desc = Some(StyleMessage::git_unknown_revision());
-    }
+    let commit_desc = match count_commits(&full_path, &branch, &remote_ref_str).await {
+        Some((ahead, behind)) => StyleMessage::git_commits(ahead.to_string(), behind.to_string()),
+        None => Some(StyleMessage::git_unknown_revision()),
+    };
 
     let desc = match (commit_desc, changes_desc) {
         (None, None) => {
@@ -0,0 +1,157 @@
+use globset::{GlobBuilder, GlobMatcher};
+use snafu::ResultExt;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::error::{InvalidIgnorePatternSnafu, MgitResult};
+use crate::utils::path::PathExtension;
+
+/// Name of the ignore file at the workspace root
+pub const IGNORE_FILE_NAME: &str = ".mgitignore";
+
+/// Repos left alone by an op, from `.mgitignore` and `--ignore`
+///
+/// `.mgitignore` holds gitignore-style globs matched against the `local` of
+/// repos: `*` doesn't cross `/` and `**` does, a pattern without a `/` matches
+/// at any depth, a leading `/` anchors it at the root, the repos under an
+/// ignored directory are ignored too and a later `!pattern` takes repos back.
+/// `--ignore` patterns are globs of the whole `local`, so plain paths keep
+/// matching only that repo. `.` is the repo at the root.
+#[derive(Debug, Default, Clone)]
+pub struct RepoIgnore {
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// Whether the rule matches the repo at the root
+    root: bool,
+    globs: Vec<GlobMatcher>,
+    negated: bool,
+}
+
+impl IgnoreRule {
+    fn is_match(&self, local: &str) -> bool {
+        match local {
+            "" => self.root,
+            _ => self.globs.iter().any(|glob| glob.is_match(local)),
+        }
+    }
+}
+
+impl RepoIgnore {
+    /// `.mgitignore` of the workspace at `path`
+    pub fn file_path(path: impl AsRef<Path>) -> PathBuf {
+        path.as_ref().join(IGNORE_FILE_NAME)
+    }
+
+    /// Patterns of `.mgitignore` in the workspace at `path`, then `ignore`
+    pub fn load(path: impl AsRef<Path>, ignore: Option<&Vec<String>>) -> MgitResult<Self> {
+        let file_path = Self::file_path(path);
+        let mut repo_ignore = match std::fs::read_to_string(&file_path) {
+            Ok(content) => Self::parse(&content)?,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!(path = %file_path.display(), error = %e, "mgitignore_read_failed");
+                }
+                Self::default()
+            }
+        };
+        repo_ignore.add_patterns(ignore.into_iter().flatten())?;
+        Ok(repo_ignore)
+    }
+
+    /// Patterns in the `.mgitignore` format
+    pub fn parse(content: &str) -> MgitResult<Self> {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line),
+            };
+
+            let pattern = pattern.trim_end_matches('/');
+            let (anchored, pattern) = match pattern.strip_prefix('/') {
+                Some(pattern) => (true, pattern),
+                None => (pattern.contains('/'), pattern),
+            };
+            if pattern == "." {
+                rules.push(IgnoreRule {
+                    root: true,
+                    globs: Vec::new(),
+                    negated,
+                });
+                continue;
+            }
+
+            let pattern = match anchored {
+                true => pattern.to_string(),
+                false => format!("**/{}", pattern),
+            };
+            // the repos in an ignored directory
+            let globs = vec![
+                build_glob(&pattern)?,
+                build_glob(&format!("{}/**", pattern))?,
+            ];
+            rules.push(IgnoreRule {
+                root: false,
+                globs,
+                negated,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// Add `--ignore` patterns, matched against the whole `local`
+    pub fn add_patterns<'a>(
+        &mut self,
+        patterns: impl IntoIterator<Item = &'a String>,
+    ) -> MgitResult<()> {
+        for pattern in patterns {
+            let pattern = pattern.norm_path();
+            let rule = match pattern.as_str() {
+                "." | "" => IgnoreRule {
+                    root: true,
+                    globs: Vec::new(),
+                    negated: false,
+                },
+                _ => IgnoreRule {
+                    root: false,
+                    globs: vec![build_glob(&pattern)?],
+                    negated: false,
+                },
+            };
+            self.rules.push(rule);
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether the repo at `local` is ignored, the last matching pattern wins
+    pub fn is_ignored(&self, local: impl AsRef<str>) -> bool {
+        let local = match local.as_ref().norm_path() {
+            local if local == "." => String::new(),
+            local => local,
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.is_match(&local))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+fn build_glob(pattern: &str) -> MgitResult<GlobMatcher> {
+    let glob = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .context(InvalidIgnorePatternSnafu { pattern })?;
+    Ok(glob.compile_matcher())
+}
@@ -0,0 +1,197 @@
+use futures_util::{stream, StreamExt};
+use globset::{GlobBuilder, GlobMatcher};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::{RepoConfig, RepoIgnore, RepoState, RepoStatus};
+use crate::error::{InvalidRepoSelectorSnafu, MgitResult, UnknownRepoSelectorSnafu};
+use crate::utils::label;
+use crate::utils::path::PathExtension;
+use crate::utils::progress::Progress;
+use crate::utils::StyleMessage;
+
+/// Number of repos probed at once for state selectors
+const PROBE_THREAD_COUNT: usize = 8;
+
+/// Repos of the config an op runs on
+///
+/// A repo is selected when it isn't ignored, has one of `labels` and matches
+/// one of the selectors. A selector is the exact `local` of a repo, its
+/// `alias`, or a glob of `local` where `*` doesn't cross `/`. Without
+/// selectors every repo matches. With states, only the repos in one of them
+/// are kept, see [`RepoSelection::retain_states`].
+#[derive(Debug, Default, Clone)]
+pub struct RepoSelection {
+    ignore: RepoIgnore,
+    labels: Option<Vec<String>>,
+    selectors: Vec<RepoSelector>,
+    states: Vec<RepoState>,
+}
+
+#[derive(Debug, Clone)]
+enum RepoSelector {
+    /// `local` or `alias`
+    Name(String),
+    Glob(String, GlobMatcher),
+}
+
+impl RepoSelector {
+    fn new(selector: &str) -> MgitResult<Self> {
+        let selector = match selector.norm_path() {
+            // the root repo is loaded with an empty `local`
+            selector if selector == "." => String::new(),
+            selector => selector,
+        };
+        if !selector.contains(['*', '?', '[', '{']) {
+            return Ok(Self::Name(selector));
+        }
+        let glob = GlobBuilder::new(&selector)
+            .literal_separator(true)
+            .build()
+            .context(InvalidRepoSelectorSnafu {
+                selector: selector.clone(),
+            })?;
+        Ok(Self::Glob(selector, glob.compile_matcher()))
+    }
+
+    fn is_match(&self, repo: &RepoConfig) -> bool {
+        let Some(local) = repo.local.as_ref().map(|local| local.norm_path()) else {
+            return false;
+        };
+        match self {
+            Self::Name(name) => &local == name || repo.alias.as_ref() == Some(name),
+            Self::Glob(_, glob) => !local.is_empty() && glob.is_match(&local),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Name(name) if name.is_empty() => ".",
+            Self::Name(name) | Self::Glob(name, _) => name,
+        }
+    }
+}
+
+impl RepoSelection {
+    /// Every repo of the workspace at `path` not ignored by `.mgitignore` or `ignore`
+    pub fn new(path: impl AsRef<Path>, ignore: Option<&Vec<String>>) -> MgitResult<Self> {
+        Ok(Self {
+            ignore: RepoIgnore::load(path, ignore)?,
+            ..Self::default()
+        })
+    }
+
+    /// Keep the repos with one of `labels`
+    pub fn labels(mut self, labels: Option<&Vec<String>>) -> Self {
+        self.labels = labels.cloned();
+        self
+    }
+
+    /// Keep the repos matching one of `selectors`, all of them when not set
+    pub fn only(mut self, selectors: Option<&Vec<String>>) -> MgitResult<Self> {
+        self.selectors = selectors
+            .into_iter()
+            .flatten()
+            .map(|selector| RepoSelector::new(selector))
+            .collect::<MgitResult<_>>()?;
+        Ok(self)
+    }
+
+    /// Keep the repos in one of `states`, all of them when not set
+    pub fn states(mut self, states: Option<&Vec<RepoState>>) -> Self {
+        self.states = states.cloned().unwrap_or_default();
+        self
+    }
+
+    /// Whether the repo at `local` is ignored
+    pub fn is_ignored(&self, local: impl AsRef<str>) -> bool {
+        self.ignore.is_ignored(local)
+    }
+
+    /// Whether the repo has one of the labels and matches a selector, ignored or not
+    pub fn matches(&self, repo: &RepoConfig) -> bool {
+        let labelled = match &self.labels {
+            Some(labels) => label::check(repo, labels),
+            None => true,
+        };
+        let selected = self.selectors.is_empty() || self.selectors.iter().any(|s| s.is_match(repo));
+        labelled && selected
+    }
+
+    pub fn is_selected(&self, repo: &RepoConfig) -> bool {
+        repo.local
+            .as_ref()
+            .is_some_and(|local| !self.is_ignored(local))
+            && self.matches(repo)
+    }
+
+    /// Repos of the config matching the selection by their index in it, ignored or not
+    pub fn matching(&self, repos: &[RepoConfig]) -> HashMap<usize, RepoConfig> {
+        repos
+            .iter()
+            .enumerate()
+            .filter(|(_, repo)| self.matches(repo))
+            .map(|(id, repo)| (id, repo.clone()))
+            .collect()
+    }
+
+    /// Fail when a selector matches no repo of the config, most likely a typo
+    pub fn check(&self, repos: &[RepoConfig]) -> MgitResult<()> {
+        let unknown = self
+            .selectors
+            .iter()
+            .filter(|selector| !repos.iter().any(|repo| selector.is_match(repo)))
+            .map(|selector| format!("`{}`", selector.as_str()))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(UnknownRepoSelectorSnafu {
+                selectors: unknown.join(", "),
+            }
+            .build());
+        }
+        Ok(())
+    }
+
+    /// Selected repos of the config
+    pub fn filter<'a>(&'a self, repos: &'a [RepoConfig]) -> impl Iterator<Item = &'a RepoConfig> {
+        repos.iter().filter(move |repo| self.is_selected(repo))
+    }
+
+    /// Keep the repos of `repos_map` in one of the states and print them
+    ///
+    /// The repos are probed from local refs only, nothing is fetched first.
+    pub async fn retain_states(
+        &self,
+        path: impl AsRef<Path>,
+        repos_map: &mut HashMap<usize, RepoConfig>,
+        default_branch: &Option<String>,
+        progress: &impl Progress,
+    ) {
+        if self.states.is_empty() {
+            return;
+        }
+
+        let path = path.as_ref();
+        let probes = repos_map.iter().map(|(id, repo)| async move {
+            let status = RepoStatus::probe(path, repo, default_branch).await;
+            (*id, status)
+        });
+        let statuses = stream::iter(probes)
+            .buffer_unordered(PROBE_THREAD_COUNT)
+            .collect::<HashMap<_, _>>()
+            .await;
+        repos_map.retain(|id, _| {
+            statuses
+                .get(id)
+                .is_some_and(|status| self.states.iter().any(|state| status.is(*state)))
+        });
+
+        let mut locals = repos_map
+            .values()
+            .filter_map(|repo| repo.local.as_deref())
+            .collect::<Vec<_>>();
+        locals.sort();
+        progress.on_message(StyleMessage::state_selection(&self.states, &locals));
+    }
+}
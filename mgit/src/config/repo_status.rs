@@ -0,0 +1,167 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use crate::config::RepoConfig;
+use crate::error::MgitResult;
+use crate::git;
+use crate::git::RemoteRef;
+
+/// State of a repo to select it by on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RepoState {
+    /// Staged, unstaged or untracked changes
+    Dirty,
+    /// Commits on the remote ref missing locally
+    Behind,
+    /// Local commits missing on the remote ref
+    Ahead,
+    /// Both ahead and behind
+    Diverged,
+    /// Not cloned
+    Missing,
+    /// HEAD not on a branch
+    Detached,
+}
+
+impl Display for RepoState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            RepoState::Dirty => "dirty",
+            RepoState::Behind => "behind",
+            RepoState::Ahead => "ahead",
+            RepoState::Diverged => "diverged",
+            RepoState::Missing => "missing",
+            RepoState::Detached => "detached",
+        };
+        write!(f, "{}", state)
+    }
+}
+
+/// Local state of a repo against the ref of its config
+///
+/// Only local refs are read, so `behind` counts the commits fetched but not
+/// checked out yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoStatus {
+    pub missing: bool,
+    pub detached: bool,
+    /// Staged, unstaged and untracked files
+    pub changed_files: usize,
+    /// Commits ahead of and behind the remote ref, `None` when they can't be counted
+    pub commits: Option<(usize, usize)>,
+}
+
+impl RepoStatus {
+    /// Probe the repo of `toml_repo` in the workspace at `input_path`
+    pub async fn probe(
+        input_path: impl AsRef<Path>,
+        toml_repo: &RepoConfig,
+        default_branch: &Option<String>,
+    ) -> Self {
+        let Some(rel_path) = toml_repo.local.as_ref() else {
+            return Self::default();
+        };
+        let full_path = input_path.as_ref().join(rel_path);
+        if git::is_repository(&full_path).await.is_err() {
+            return Self {
+                missing: true,
+                ..Self::default()
+            };
+        }
+
+        let detached = git::is_head_detached(&full_path).await.unwrap_or(false);
+        let changed_files = count_changed_files(&full_path).await;
+        let commits = match resolve_remote_ref(&full_path, toml_repo, default_branch, false).await {
+            Ok((remote_ref, _)) => count_commits(&full_path, "HEAD", &remote_ref).await,
+            Err(_) => None,
+        };
+        Self {
+            missing: false,
+            detached,
+            changed_files,
+            commits,
+        }
+    }
+
+    pub fn is(&self, state: RepoState) -> bool {
+        let (ahead, behind) = self.commits.unwrap_or_default();
+        match state {
+            RepoState::Dirty => self.changed_files > 0,
+            RepoState::Behind => behind > 0,
+            RepoState::Ahead => ahead > 0,
+            RepoState::Diverged => ahead > 0 && behind > 0,
+            RepoState::Missing => self.missing,
+            RepoState::Detached => self.detached,
+        }
+    }
+}
+
+/// Remote ref a repo is compared against and its short description
+///
+/// The upstream of the current branch with `use_tracking_remote`, otherwise
+/// the commit, tag or branch of the config.
+pub(crate) async fn resolve_remote_ref(
+    full_path: &Path,
+    toml_repo: &RepoConfig,
+    default_branch: &Option<String>,
+    use_tracking_remote: bool,
+) -> MgitResult<(String, String)> {
+    if use_tracking_remote {
+        let remote_ref_str: String = git::get_tracking_branch(full_path).await?;
+        return Ok((remote_ref_str.clone(), remote_ref_str));
+    }
+
+    let mut toml_repo = toml_repo.to_owned();
+    if toml_repo.branch.is_none() {
+        toml_repo.branch = default_branch.to_owned();
+    }
+    let remote_ref = toml_repo.get_remote_ref(full_path).await?;
+    let remote_ref_str = match remote_ref.clone() {
+        RemoteRef::Commit(r) | RemoteRef::Tag(r) | RemoteRef::Branch(r) => r,
+    };
+    let remote_desc = match remote_ref {
+        RemoteRef::Commit(commit) => commit[..7].to_string(),
+        RemoteRef::Tag(r) | RemoteRef::Branch(r) => r,
+    };
+    Ok((remote_ref_str, remote_desc))
+}
+
+/// Number of staged, unstaged and untracked files
+pub(crate) async fn count_changed_files(full_path: &Path) -> usize {
+    let mut changed_files: HashSet<String> = HashSet::new();
+
+    if let Ok(output) = git::get_untrack_files(full_path).await {
+        for file in output.trim().lines() {
+            changed_files.insert(file.to_string());
+        }
+    }
+
+    if let Ok(output) = git::get_changed_files(full_path).await {
+        for file in output.trim().lines() {
+            changed_files.insert(file.to_string());
+        }
+    }
+
+    if let Ok(output) = git::get_staged_files(full_path).await {
+        for file in output.trim().lines() {
+            changed_files.insert(file.to_string());
+        }
+    }
+
+    changed_files.len()
+}
+
+/// Commits of `local` missing on `remote_ref` and the other way round
+pub(crate) async fn count_commits(
+    full_path: &Path,
+    local: &str,
+    remote_ref: &str,
+) -> Option<(usize, usize)> {
+    let branch_pair = format!("{}...{}", local, remote_ref);
+    let output = git::get_rev_list_count(full_path, branch_pair).await.ok()?;
+    let re = Regex::new(r"(\d+)\s*(\d+)").unwrap();
+    let caps = re.captures(&output)?;
+    Some((caps[1].parse().ok()?, caps[2].parse().ok()?))
+}
@@ -4,7 +4,7 @@
 mod tests {
     use std::collections::{HashMap, HashSet};
 
-    use crate::config::RepoConfig;
+    use crate::config::{RepoConfig, RepoSelection};
 
     /// Test RepoId::new
     #[test]
@@ -27,11 +27,16 @@ mod tests {
     fn test_repo_config_creation() {
         let config = RepoConfig {
             local: None,
+            alias: None,
             remote: None,
+            fallbacks: None,
             branch: None,
             tag: None,
             commit: None,
             sparse: None,
+            filter: None,
+            lfs: None,
+            submodules: None,
             labels: None,
         };
         assert!(config.local.is_none());
@@ -48,11 +53,16 @@ mod tests {
     fn test_repo_config_clone() {
         let config = RepoConfig {
             local: Some("test".to_string()),
+            alias: None,
             remote: Some("https://example.com/repo.git".to_string()),
+            fallbacks: None,
             branch: Some("main".to_string()),
             tag: None,
             commit: None,
             sparse: None,
+            filter: None,
+            lfs: None,
+            submodules: None,
             labels: None,
         };
         let cloned = config.clone();
@@ -65,7 +75,8 @@ mod tests {
     #[test]
     fn test_repos_to_map_empty() {
         let repos = Vec::new();
-        let result = crate::config::repos_to_map_with_ignore(repos, None, None);
+        let result =
+            crate::config::repos_to_map_with_ignore(repos, &RepoSelection::default()).unwrap();
         assert!(result.is_empty());
     }
 
@@ -74,14 +85,20 @@ mod tests {
     fn test_repos_to_map_single() {
         let repos = vec![RepoConfig {
             local: Some("test".to_string()),
+            alias: None,
             remote: Some("https://example.com/repo.git".to_string()),
+            fallbacks: None,
             branch: Some("main".to_string()),
             tag: None,
             commit: None,
             sparse: None,
+            filter: None,
+            lfs: None,
+            submodules: None,
             labels: None,
         }];
-        let result = crate::config::repos_to_map_with_ignore(repos, None, None);
+        let result =
+            crate::config::repos_to_map_with_ignore(repos, &RepoSelection::default()).unwrap();
         assert_eq!(result.len(), 1);
         assert!(result.contains_key(&0));
     }
@@ -92,25 +109,37 @@ mod tests {
         let repos = vec![
             RepoConfig {
                 local: Some("repo1".to_string()),
+                alias: None,
                 remote: Some("https://example.com/repo1.git".to_string()),
+                fallbacks: None,
                 branch: None,
                 tag: None,
                 commit: None,
                 sparse: None,
+                filter: None,
+                lfs: None,
+                submodules: None,
                 labels: None,
             },
             RepoConfig {
                 local: Some("repo2".to_string()),
+                alias: None,
                 remote: Some("https://example.com/repo2.git".to_string()),
+                fallbacks: None,
                 branch: None,
                 tag: None,
                 commit: None,
                 sparse: None,
+                filter: None,
+                lfs: None,
+                submodules: None,
                 labels: None,
             },
         ];
-        let ignore = Some(vec!["repo1".to_string()]);
-        let result = crate::config::repos_to_map_with_ignore(repos, ignore.as_ref(), None);
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let selection =
+            RepoSelection::new(tmp_dir.path(), Some(&vec!["repo1".to_string()])).unwrap();
+        let result = crate::config::repos_to_map_with_ignore(repos, &selection).unwrap();
         assert_eq!(result.len(), 1);
         assert!(result.contains_key(&1));
     }
@@ -121,34 +150,49 @@ mod tests {
         let repos = vec![
             RepoConfig {
                 local: Some("a".to_string()),
+                alias: None,
                 remote: None,
+                fallbacks: None,
                 branch: None,
                 tag: None,
                 commit: None,
                 sparse: None,
+                filter: None,
+                lfs: None,
+                submodules: None,
                 labels: None,
             },
             RepoConfig {
                 local: Some("b".to_string()),
+                alias: None,
                 remote: None,
+                fallbacks: None,
                 branch: None,
                 tag: None,
                 commit: None,
                 sparse: None,
+                filter: None,
+                lfs: None,
+                submodules: None,
                 labels: None,
             },
             RepoConfig {
                 local: Some("c".to_string()),
+                alias: None,
                 remote: None,
+                fallbacks: None,
                 branch: None,
                 tag: None,
                 commit: None,
                 sparse: None,
+                filter: None,
+                lfs: None,
+                submodules: None,
                 labels: None,
             },
         ];
         let result: HashMap<usize, RepoConfig> =
-            crate::config::repos_to_map_with_ignore(repos, None, None);
+            crate::config::repos_to_map_with_ignore(repos, &RepoSelection::default()).unwrap();
         // HashMap doesn't guarantee order, but we should have 3 entries
         assert_eq!(result.len(), 3);
         // All keys should be unique and within range
@@ -156,3 +200,447 @@ mod tests {
         assert_eq!(keys.len(), 3);
     }
 }
+
+#[cfg(test)]
+mod user_config_tests {
+    use crate::config::UserConfig;
+
+    const HOSTS_TOML: &str = r#"
+[[hosts]]
+pattern = "gitea.internal"
+ssh-command = "ssh -i ~/.ssh/id_gitea"
+max-connections = 8
+
+[[hosts]]
+pattern = "*github.com"
+ssh-command = "ssh -i ~/.ssh/id_github"
+env = { GIT_TRACE = "1" }
+
+[[hosts]]
+pattern = "*"
+proxy = "http://127.0.0.1:7890"
+ssh-command = "ssh -i ~/.ssh/id_rsa"
+env = { GIT_TRACE = "0", GIT_HTTP_LOW_SPEED_TIME = "60" }
+"#;
+
+    /// Test that every matching host entry applies, first value wins
+    #[test]
+    fn test_host_settings_merge() {
+        let config: UserConfig = toml::from_str(HOSTS_TOML).unwrap();
+
+        let github = config.host_settings("GitHub.com").unwrap();
+        assert_eq!(
+            github.ssh_command.as_deref(),
+            Some("ssh -i ~/.ssh/id_github")
+        );
+        assert_eq!(github.proxy.as_deref(), Some("http://127.0.0.1:7890"));
+        let env = github.env.unwrap();
+        assert_eq!(env.get("GIT_TRACE").map(String::as_str), Some("1"));
+        assert_eq!(
+            env.get("GIT_HTTP_LOW_SPEED_TIME").map(String::as_str),
+            Some("60")
+        );
+
+        let gitea = config.host_settings("gitea.internal").unwrap();
+        assert_eq!(gitea.ssh_command.as_deref(), Some("ssh -i ~/.ssh/id_gitea"));
+        assert_eq!(gitea.max_connections, Some(8));
+        assert_eq!(github.max_connections, None);
+    }
+
+    /// Test hosts without a matching entry
+    #[test]
+    fn test_host_settings_no_match() {
+        let config: UserConfig = toml::from_str(
+            r#"
+[[hosts]]
+pattern = "*.example.com"
+proxy = "http://proxy"
+"#,
+        )
+        .unwrap();
+        assert!(config.host_settings("example.org").is_none());
+        assert!(config.host_settings("git.example.com").is_some());
+        assert!(UserConfig::default().host_settings("example.com").is_none());
+    }
+}
+
+#[cfg(test)]
+mod workspace_tests {
+    use std::path::PathBuf;
+
+    use crate::config::Workspace;
+
+    /// Test that `.gitrepos` is found in parent directories
+    #[test]
+    fn test_discover() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let root = tmp_dir.path().to_path_buf();
+        std::fs::write(
+            root.join(".gitrepos"),
+            "[[repos]]\nlocal = \".\"\n\n[[repos]]\nlocal = \"client\"\n\n[[repos]]\nlocal = \"client/engine\"\n",
+        )
+        .unwrap();
+        let dir = root.join("client/engine/src");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let workspace = Workspace::discover_with(&dir, None, None).unwrap();
+        assert_eq!(workspace.root, root);
+        assert_eq!(workspace.config_path, root.join(".gitrepos"));
+        assert_eq!(workspace.repo_of(&dir).as_deref(), Some("client/engine"));
+        assert_eq!(
+            workspace.repo_of(root.join("client")).as_deref(),
+            Some("client")
+        );
+        assert_eq!(workspace.repo_of(&root), None);
+
+        assert_eq!(
+            Workspace::discover_with(tmp_dir.path().parent().unwrap(), None, None),
+            None
+        );
+    }
+
+    /// Test `MGIT_ROOT` and `MGIT_CONFIG` overrides
+    #[test]
+    fn test_discover_overrides() {
+        let dir = PathBuf::from("/work/dir");
+        let workspace =
+            Workspace::discover_with(&dir, Some(PathBuf::from("/workspace")), None).unwrap();
+        assert_eq!(workspace.root, PathBuf::from("/workspace"));
+        assert_eq!(workspace.config_path, PathBuf::from("/workspace/.gitrepos"));
+
+        let workspace =
+            Workspace::discover_with(&dir, None, Some(PathBuf::from("configs/release.gitrepos")))
+                .unwrap();
+        assert_eq!(workspace.root, dir.join("configs"));
+        assert_eq!(workspace.config_path, dir.join("configs/release.gitrepos"));
+
+        let workspace = Workspace::discover_with(
+            &dir,
+            Some(PathBuf::from("/workspace")),
+            Some(PathBuf::from("/configs/release.gitrepos")),
+        )
+        .unwrap();
+        assert_eq!(workspace.root, PathBuf::from("/workspace"));
+        assert_eq!(
+            workspace.config_path,
+            PathBuf::from("/configs/release.gitrepos")
+        );
+    }
+}
+
+#[cfg(test)]
+mod local_config_tests {
+    use crate::config::{LocalConfig, MgitConfig};
+
+    const SHARED_CONFIG: &str = r#"default-branch = "develop"
+
+[[repos]]
+local = "."
+remote = "https://example.com/root.git"
+
+[[repos]]
+local = "assets"
+remote = "https://example.com/assets.git"
+
+[[repos]]
+local = "client"
+remote = "https://example.com/client.git"
+commit = "1234567"
+labels = ["client"]
+"#;
+
+    const LOCAL_CONFIG: &str = r#"ignore = ["assets"]
+
+[[repos]]
+local = "client/"
+branch = "feature/login"
+sparse = ["src"]
+
+[[repos]]
+local = "apps"
+remote = "https://example.com/apps.git"
+branch = "main"
+"#;
+
+    /// Test merging `.gitrepos.local` over `.gitrepos`
+    #[test]
+    fn test_load_with_local_config() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let config_path = tmp_dir.path().join(".gitrepos");
+        std::fs::write(&config_path, SHARED_CONFIG).unwrap();
+        assert_eq!(
+            LocalConfig::file_path(&config_path),
+            tmp_dir.path().join(".gitrepos.local")
+        );
+        assert_eq!(
+            MgitConfig::load(&config_path).unwrap().repos.unwrap().len(),
+            3
+        );
+
+        std::fs::write(LocalConfig::file_path(&config_path), LOCAL_CONFIG).unwrap();
+        let config = MgitConfig::load(&config_path).unwrap();
+        let repos = config.repos.unwrap();
+        let locals = repos
+            .iter()
+            .map(|repo| repo.local.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        // config order is kept, repos only in the overlay come last
+        assert_eq!(locals, vec!["", "client", "apps"]);
+        assert_eq!(config.ignored_repos.len(), 1);
+
+        // the pinned branch replaces the shared commit, other fields are kept
+        let client = &repos[1];
+        assert_eq!(client.branch.as_deref(), Some("feature/login"));
+        assert_eq!(client.commit, None);
+        assert_eq!(client.sparse, Some(vec!["src".to_string()]));
+        assert_eq!(client.labels, Some(vec!["client".to_string()]));
+        assert_eq!(
+            client.remote.as_deref(),
+            Some("https://example.com/client.git")
+        );
+
+        let shared = MgitConfig::load_shared(&config_path).unwrap();
+        assert_eq!(shared.repos.unwrap().len(), 3);
+        assert!(shared.ignored_repos.is_empty());
+    }
+
+    /// Test that saving refs leaves what `.gitrepos.local` sets out of `.gitrepos`
+    #[test]
+    fn test_update_refs() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let config_path = tmp_dir.path().join(".gitrepos");
+        std::fs::write(&config_path, SHARED_CONFIG).unwrap();
+        std::fs::write(LocalConfig::file_path(&config_path), LOCAL_CONFIG).unwrap();
+
+        let local_config = LocalConfig::load(&config_path).unwrap();
+        assert!(local_config.sets_ref("client"));
+        assert!(!local_config.sets_ref(""));
+
+        let mut config = MgitConfig::load(&config_path).unwrap();
+        for repo in config.repos.iter_mut().flatten() {
+            repo.branch = Some("release".to_string());
+        }
+        let mut shared = MgitConfig::load_shared(&config_path).unwrap();
+        shared.update_refs(&config, Some(&local_config));
+
+        let repos = shared.repos.unwrap();
+        let locals = repos
+            .iter()
+            .map(|repo| repo.local.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(locals, vec!["", "assets", "client"]);
+        assert_eq!(repos[0].branch.as_deref(), Some("release"));
+        assert_eq!(repos[1].branch, None);
+        assert_eq!(repos[2].branch, None);
+        assert_eq!(repos[2].commit.as_deref(), Some("1234567"));
+    }
+}
+
+#[cfg(test)]
+mod repo_ignore_tests {
+    use crate::config::RepoIgnore;
+
+    /// Test gitignore-style patterns of `.mgitignore`
+    #[test]
+    fn test_parse() {
+        let ignore = RepoIgnore::parse(
+            "# heavy repos\n\nassets\n/tools/*\n!/tools/keep\nthird_party/**/docs/\n",
+        )
+        .unwrap();
+
+        // a name without `/` matches at any depth, with the repos under it
+        assert!(ignore.is_ignored("assets"));
+        assert!(ignore.is_ignored("client/assets"));
+        assert!(ignore.is_ignored("assets/textures"));
+        assert!(!ignore.is_ignored("assets-lite"));
+
+        // anchored patterns, `*` doesn't cross `/`, the last match wins
+        assert!(ignore.is_ignored("tools/gen"));
+        assert!(ignore.is_ignored("tools/gen/plugin"));
+        assert!(!ignore.is_ignored("tools/keep"));
+        assert!(!ignore.is_ignored("client/tools/gen"));
+
+        assert!(ignore.is_ignored("third_party/docs"));
+        assert!(ignore.is_ignored("third_party/a/b/docs"));
+        assert!(!ignore.is_ignored("third_party/a"));
+        assert!(!ignore.is_ignored("."));
+    }
+
+    /// Test `--ignore` patterns, matched against the whole `local`
+    #[test]
+    fn test_add_patterns() {
+        let mut ignore = RepoIgnore::default();
+        assert!(ignore.is_empty());
+        ignore
+            .add_patterns(&vec![
+                ".".to_string(),
+                "client/*".to_string(),
+                "server".to_string(),
+            ])
+            .unwrap();
+
+        assert!(ignore.is_ignored(""));
+        assert!(ignore.is_ignored("."));
+        assert!(ignore.is_ignored("client/engine"));
+        assert!(!ignore.is_ignored("client/engine/plugin"));
+        assert!(ignore.is_ignored("server"));
+        assert!(!ignore.is_ignored("server/api"));
+        assert!(!ignore.is_ignored("game/server"));
+
+        assert!(ignore.add_patterns(&vec!["client/[".to_string()]).is_err());
+    }
+
+    /// Test that `--ignore` adds to `.mgitignore`
+    #[test]
+    fn test_load() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let ignore = RepoIgnore::load(tmp_dir.path(), None).unwrap();
+        assert!(ignore.is_empty());
+
+        std::fs::write(RepoIgnore::file_path(tmp_dir.path()), "assets\n").unwrap();
+        let ignore = RepoIgnore::load(tmp_dir.path(), Some(&vec!["server".to_string()])).unwrap();
+        assert!(ignore.is_ignored("assets"));
+        assert!(ignore.is_ignored("server"));
+        assert!(!ignore.is_ignored("client"));
+    }
+}
+
+#[cfg(test)]
+mod repo_selection_tests {
+    use crate::config::{repos_to_map_with_ignore, RepoConfig, RepoSelection};
+    use crate::error::MgitError;
+
+    fn repos() -> Vec<RepoConfig> {
+        [
+            ("", None, None),
+            ("client", None, Some("ui")),
+            ("client/engine", Some("engine"), Some("core")),
+            ("server", Some("srv"), Some("ui")),
+        ]
+        .into_iter()
+        .map(|(local, alias, label)| RepoConfig {
+            local: Some(local.to_string()),
+            alias: alias.map(str::to_string),
+            labels: label.map(|label| vec![label.to_string()]),
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    fn selected_ids(selection: &RepoSelection) -> Vec<usize> {
+        let mut ids = repos_to_map_with_ignore(repos(), selection)
+            .unwrap()
+            .into_keys()
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn only(selectors: &[&str]) -> Vec<String> {
+        selectors.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Test selecting repos by `local`, glob and alias, with their ids
+    #[test]
+    fn test_only() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let selection = RepoSelection::new(tmp_dir.path(), None).unwrap();
+        assert_eq!(selected_ids(&selection), vec![0, 1, 2, 3]);
+
+        let selection = selection
+            .only(Some(&only(&[".", "client/engine/"])))
+            .unwrap();
+        assert_eq!(selected_ids(&selection), vec![0, 2]);
+
+        let selection = RepoSelection::new(tmp_dir.path(), None)
+            .unwrap()
+            .only(Some(&only(&["client/*", "srv"])))
+            .unwrap();
+        assert_eq!(selected_ids(&selection), vec![2, 3]);
+
+        // `*` matches every repo but the root one
+        let selection = RepoSelection::new(tmp_dir.path(), Some(&only(&["server"])))
+            .unwrap()
+            .only(Some(&only(&["*"])))
+            .unwrap();
+        assert_eq!(selected_ids(&selection), vec![1]);
+    }
+
+    /// Test combining selectors with labels
+    #[test]
+    fn test_only_with_labels() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let labels = vec!["ui".to_string()];
+        let selection = RepoSelection::new(tmp_dir.path(), None)
+            .unwrap()
+            .labels(Some(&labels));
+        // repos without labels are always kept
+        assert_eq!(selected_ids(&selection), vec![0, 1, 3]);
+
+        let selection = selection.only(Some(&only(&["client*"]))).unwrap();
+        assert_eq!(selected_ids(&selection), vec![1]);
+    }
+
+    /// Test that selectors matching no repo fail
+    #[test]
+    fn test_unknown_selector() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let selection = RepoSelection::new(tmp_dir.path(), None)
+            .unwrap()
+            .only(Some(&only(&["client", "clinet", "tools/*"])))
+            .unwrap();
+        match repos_to_map_with_ignore(repos(), &selection) {
+            Err(MgitError::UnknownRepoSelector { selectors }) => {
+                assert_eq!(selectors, "`clinet`, `tools/*`")
+            }
+            res => panic!("expected unknown selectors, got {:?}", res),
+        }
+
+        let selection = RepoSelection::new(tmp_dir.path(), None)
+            .unwrap()
+            .only(Some(&only(&["client/["])));
+        assert!(matches!(
+            selection,
+            Err(MgitError::InvalidRepoSelector { .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod repo_status_tests {
+    use crate::config::{RepoState, RepoStatus};
+
+    /// Test the states of probed repos
+    #[test]
+    fn test_is() {
+        let status = RepoStatus {
+            changed_files: 2,
+            commits: Some((1, 0)),
+            ..Default::default()
+        };
+        assert!(status.is(RepoState::Dirty));
+        assert!(status.is(RepoState::Ahead));
+        assert!(!status.is(RepoState::Behind));
+        assert!(!status.is(RepoState::Diverged));
+        assert!(!status.is(RepoState::Missing));
+
+        let status = RepoStatus {
+            detached: true,
+            commits: Some((1, 3)),
+            ..Default::default()
+        };
+        assert!(!status.is(RepoState::Dirty));
+        assert!(status.is(RepoState::Behind));
+        assert!(status.is(RepoState::Diverged));
+        assert!(status.is(RepoState::Detached));
+
+        // commits can't be counted without the remote ref
+        let status = RepoStatus {
+            missing: true,
+            ..Default::default()
+        };
+        assert!(status.is(RepoState::Missing));
+        assert!(!status.is(RepoState::Behind));
+        assert!(!status.is(RepoState::Ahead));
+    }
+}
@@ -0,0 +1,159 @@
+use globset::GlobBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::{fs, path::Path};
+use tracing::warn;
+
+/// Per-user configuration of MGIT
+///
+/// This structure represents `~/.mgit/config.toml`. It holds machine specific
+/// settings that do not belong in a shared `.gitrepos` file.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct UserConfig {
+    /// Path to the git binary, `git` from PATH is used when not set
+    pub git: Option<String>,
+    /// Settings applied to network git commands, selected by remote host
+    pub hosts: Option<Vec<HostConfig>>,
+    /// Groups of url prefixes that reach the same server
+    pub url_equivalents: Option<Vec<Vec<String>>>,
+    /// Url prefix replacements applied when setting remotes, like git's `insteadOf`
+    pub url_rewrite: Option<BTreeMap<String, String>>,
+    /// Local mirror cache shared by every workspace of this machine
+    pub cache: Option<CacheConfig>,
+}
+
+/// Settings of the local mirror cache
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CacheConfig {
+    /// Refresh the cache and seed new repos from it during `sync`
+    pub enabled: Option<bool>,
+    /// Cache directory, `~/.mgit/cache` when not set
+    pub dir: Option<String>,
+}
+
+/// Settings for remotes whose host matches `pattern`
+///
+/// Like `ssh_config`, every matching entry applies and the first value
+/// found for each setting wins.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct HostConfig {
+    /// Glob matched against the remote host, e.g. `*.github.com`
+    pub pattern: String,
+    /// Value of `GIT_SSH_COMMAND`, e.g. `ssh -i ~/.ssh/id_github`
+    pub ssh_command: Option<String>,
+    /// Value of `http.proxy`, e.g. `http://127.0.0.1:7890`
+    pub proxy: Option<String>,
+    /// Extra environment variables of the git process
+    pub env: Option<BTreeMap<String, String>>,
+    /// Network git commands run at once against the host, on top of the global limit
+    pub max_connections: Option<usize>,
+}
+
+impl HostConfig {
+    /// Check whether `host` matches the pattern, case-insensitively
+    pub fn matches(&self, host: &str) -> bool {
+        match GlobBuilder::new(&self.pattern)
+            .case_insensitive(true)
+            .build()
+        {
+            Ok(glob) => glob.compile_matcher().is_match(host),
+            Err(e) => {
+                warn!(pattern = self.pattern.as_str(), error = %e, "host_pattern_invalid");
+                false
+            }
+        }
+    }
+}
+
+impl UserConfig {
+    /// Default location of the user configuration file
+    pub fn default_path() -> Option<PathBuf> {
+        home::home_dir().map(|home| home.join(".mgit").join("config.toml"))
+    }
+
+    /// Load user configuration from a TOML file
+    ///
+    /// Returns `None` if the file doesn't exist or can't be parsed.
+    pub fn load(path: impl AsRef<Path>) -> Option<Self> {
+        let path_ref = path.as_ref();
+        if !path_ref.is_file() {
+            return None;
+        }
+
+        let content = match fs::read_to_string(path_ref) {
+            Ok(c) => c,
+            Err(e) => {
+                warn!(
+                    path = path_ref.to_string_lossy().as_ref(),
+                    error = %e,
+                    "toml_user_config_read_failed"
+                );
+                return None;
+            }
+        };
+
+        match toml::from_str::<UserConfig>(&content) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                warn!(
+                    path = path_ref.to_string_lossy().as_ref(),
+                    error = %e,
+                    "toml_user_config_parse_failed"
+                );
+                None
+            }
+        }
+    }
+
+    /// Merge the settings of every host entry matching `host`
+    ///
+    /// Returns `None` if no entry matches.
+    pub fn host_settings(&self, host: &str) -> Option<HostConfig> {
+        let mut matched = self
+            .hosts
+            .iter()
+            .flatten()
+            .filter(|h| h.matches(host))
+            .peekable();
+        matched.peek()?;
+
+        let mut settings = HostConfig {
+            pattern: host.to_string(),
+            ..Default::default()
+        };
+        for host_config in matched {
+            if settings.ssh_command.is_none() {
+                settings.ssh_command = host_config.ssh_command.clone();
+            }
+            if settings.proxy.is_none() {
+                settings.proxy = host_config.proxy.clone();
+            }
+            if settings.max_connections.is_none() {
+                settings.max_connections = host_config.max_connections;
+            }
+            for (key, value) in host_config.env.iter().flatten() {
+                settings
+                    .env
+                    .get_or_insert_with(Default::default)
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        Some(settings)
+    }
+
+    /// User configuration of the current process, loaded once from the default path
+    pub fn get() -> &'static UserConfig {
+        static CONFIG: OnceLock<UserConfig> = OnceLock::new();
+        CONFIG.get_or_init(|| {
+            Self::default_path()
+                .and_then(Self::load)
+                .unwrap_or_default()
+        })
+    }
+}
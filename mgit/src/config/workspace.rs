@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::MgitConfig;
+use crate::utils::current_dir;
+use crate::utils::path::PathExtension;
+
+/// Environment variable naming the config file of the workspace
+pub const MGIT_CONFIG_ENV: &str = "MGIT_CONFIG";
+
+/// Environment variable naming the root directory of the workspace
+pub const MGIT_ROOT_ENV: &str = "MGIT_ROOT";
+
+/// Name of the workspace config file
+pub const CONFIG_FILE_NAME: &str = ".gitrepos";
+
+/// A workspace: the root its repos are relative to and its config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config_path: PathBuf,
+}
+
+impl Workspace {
+    /// Find the workspace of `dir`
+    ///
+    /// `MGIT_ROOT` and `MGIT_CONFIG` win when set, either one implies the
+    /// other. Otherwise `dir` and its parents are searched for `.gitrepos`,
+    /// the way git finds `.git`.
+    pub fn discover(dir: impl AsRef<Path>) -> Option<Self> {
+        let env_path = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        Self::discover_with(
+            dir,
+            env_path(MGIT_ROOT_ENV).map(PathBuf::from),
+            env_path(MGIT_CONFIG_ENV).map(PathBuf::from),
+        )
+    }
+
+    pub(crate) fn discover_with(
+        dir: impl AsRef<Path>,
+        root: Option<PathBuf>,
+        config_path: Option<PathBuf>,
+    ) -> Option<Self> {
+        let dir = dir.as_ref();
+        // relative overrides are relative to where mgit runs
+        let root = root.map(|root| dir.join(root));
+        let config_path = config_path.map(|config_path| dir.join(config_path));
+        match (root, config_path) {
+            (Some(root), Some(config_path)) => Some(Self { root, config_path }),
+            (Some(root), None) => Some(Self {
+                config_path: root.join(CONFIG_FILE_NAME),
+                root,
+            }),
+            (None, Some(config_path)) => Some(Self {
+                root: config_path.parent()?.to_path_buf(),
+                config_path,
+            }),
+            (None, None) => dir.ancestors().find_map(|root| {
+                let config_path = root.join(CONFIG_FILE_NAME);
+                config_path.is_file().then(|| Self {
+                    root: root.to_path_buf(),
+                    config_path,
+                })
+            }),
+        }
+    }
+
+    /// Work directory and config file of an op
+    ///
+    /// An explicit `path` is the workspace root as given. Without one, the
+    /// workspace of the current directory is used, or the current directory
+    /// itself when there is none. An explicit `config_path` is always kept.
+    pub fn resolve(
+        path: Option<impl AsRef<Path>>,
+        config_path: Option<impl AsRef<Path>>,
+    ) -> (PathBuf, PathBuf) {
+        let config_path = config_path.map(|p| p.as_ref().to_path_buf());
+        let path = match path {
+            Some(path) => path.as_ref().to_path_buf(),
+            None => match Self::discover(current_dir()) {
+                // the root found for another config file doesn't apply to this one
+                Some(workspace) if config_path.is_none() => return workspace.into_paths(),
+                _ => current_dir(),
+            },
+        };
+        let config_path = config_path.unwrap_or_else(|| path.join(CONFIG_FILE_NAME));
+        (path, config_path)
+    }
+
+    fn into_paths(self) -> (PathBuf, PathBuf) {
+        (self.root, self.config_path)
+    }
+
+    /// `local` of the configured repo holding `dir`, the deepest one for nested repos
+    ///
+    /// A repo at the workspace root holds every directory and never counts.
+    pub fn repo_of(&self, dir: impl AsRef<Path>) -> Option<String> {
+        let rel_dir = dir.as_ref().strip_prefix(&self.root).ok()?;
+        MgitConfig::load(&self.config_path)?
+            .repos?
+            .into_iter()
+            .filter_map(|repo| repo.local)
+            .filter(|local| !local.is_empty() && local != ".")
+            .filter(|local| rel_dir.starts_with(local.norm_path()))
+            .max_by_key(|local| local.len())
+    }
+}
@@ -40,6 +40,27 @@ pub enum MgitError {
     #[snafu(display("Failed to wait for process: {}", source))]
     ProcessWaitFailed { source: std::io::Error },
 
+    #[snafu(display(
+        "git {} does not support {}, git >= {} is required",
+        version,
+        feature,
+        required
+    ))]
+    GitVersionUnsupported {
+        version: String,
+        feature: String,
+        required: String,
+    },
+
+    #[snafu(display("Failed to detect git version: {}", message))]
+    GitVersionUnknown { message: String },
+
+    #[snafu(display(
+        "{} uses Git LFS but git-lfs is not installed, use --skip-lfs to keep pointer files",
+        path.display()
+    ))]
+    LfsNotInstalled { path: PathBuf },
+
     // === Specific Operation Errors (replacing generic OpsError) ===
     #[snafu(display("Semaphore acquisition failed: {}", message))]
     AcquirePermitFailed { message: String },
@@ -62,6 +83,28 @@ pub enum MgitError {
     #[snafu(display("Stash and hard reset cannot be used together"))]
     StashHardConflict,
 
+    #[snafu(display(
+        "{} is locked by {}, wait for it to finish or use --wait",
+        path.display(),
+        holder
+    ))]
+    WorkspaceLocked { path: PathBuf, holder: String },
+
+    #[snafu(display("Invalid ignore pattern `{}`: {}", pattern, source))]
+    InvalidIgnorePattern {
+        pattern: String,
+        source: globset::Error,
+    },
+
+    #[snafu(display("Invalid repo selector `{}`: {}", selector, source))]
+    InvalidRepoSelector {
+        selector: String,
+        source: globset::Error,
+    },
+
+    #[snafu(display("No repo in the config matches {}", selectors))]
+    UnknownRepoSelector { selectors: String },
+
     #[snafu(display("Operation failed: {}", message))]
     OpsError { message: String },
 
@@ -0,0 +1,158 @@
+use std::path::Path;
+use std::process::Stdio;
+
+use tokio::process::Command;
+
+use crate::config::{HostConfig, UserConfig};
+use crate::git::remote::remote_host;
+use crate::git::version::{git_capabilities, git_program, GitCapability};
+
+/// Git subcommands that talk to a remote
+const NETWORK_SUBCOMMANDS: &[&[&str]] = &[
+    &["fetch"],
+    &["pull"],
+    &["push"],
+    &["ls-remote"],
+    &["clone"],
+    &["lfs", "fetch"],
+    &["submodule", "update"],
+];
+
+/// Apply the `hosts` settings of the user config to a network git command
+///
+/// The remote is the first positional argument after the subcommand, either a
+/// url or a remote name of the repository in the working directory.
+pub(crate) async fn apply_host_settings(command: &mut Command) {
+    let user_config = UserConfig::get();
+    if user_config.hosts.as_ref().is_none_or(|h| h.is_empty()) {
+        return;
+    }
+    let Some(subcommand) = network_subcommand(command) else {
+        return;
+    };
+    let Some(host) = command_remote_host(command).await else {
+        return;
+    };
+    let Some(settings) = user_config.host_settings(&host) else {
+        return;
+    };
+    tracing::info!(
+        host = host.as_str(),
+        subcommand = subcommand.as_str(),
+        "host_settings_applied"
+    );
+    apply(command, &settings).await;
+}
+
+/// Subcommand of a git command that talks to a remote, like `lfs fetch`
+pub(crate) fn network_subcommand(command: &Command) -> Option<String> {
+    let std_command = command.as_std();
+    if std_command.get_program() != git_program() {
+        return None;
+    }
+    let args: Vec<String> = std_command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+    parse_network_subcommand(&args)
+}
+
+/// Network subcommand the git arguments `args` start with
+pub(crate) fn parse_network_subcommand(args: &[String]) -> Option<String> {
+    NETWORK_SUBCOMMANDS
+        .iter()
+        .find(|words| {
+            args.len() >= words.len() && args.iter().zip(words.iter()).all(|(a, w)| a == w)
+        })
+        .map(|words| words.join(" "))
+}
+
+/// Host of the remote a network git command talks to
+///
+/// `submodule update` has none, each submodule fetches from its own remote.
+pub(crate) async fn command_remote_host(command: &Command) -> Option<String> {
+    let std_command = command.as_std();
+    let args: Vec<String> = std_command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+    let subcommand = parse_network_subcommand(&args)?;
+    if subcommand == "submodule update" {
+        return None;
+    }
+    let remote = args
+        .get(subcommand.split(' ').count()..)?
+        .iter()
+        .find(|arg| !arg.starts_with('-'))
+        .map(|arg| arg.as_str())
+        .unwrap_or("origin");
+    let url = match remote_host(remote) {
+        Some(_) => remote.to_string(),
+        None => {
+            let dir = std_command
+                .get_current_dir()
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf();
+            // not through `exec_cmd`, which calls back into this function
+            let output = Command::new(git_program())
+                .args(["remote", "get-url", remote])
+                .current_dir(dir)
+                .stdin(Stdio::null())
+                .output()
+                .await
+                .ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
+    remote_host(&url)
+}
+
+async fn apply(command: &mut Command, settings: &HostConfig) {
+    if let Some(ssh_command) = &settings.ssh_command {
+        command.env("GIT_SSH_COMMAND", ssh_command);
+    }
+
+    // before the proxy, which counts the `GIT_CONFIG_COUNT` entries set here
+    for (key, value) in settings.env.iter().flatten() {
+        command.env(key, value);
+    }
+
+    if let Some(proxy) = &settings.proxy {
+        // boxed: detecting the version runs `exec_cmd`, which awaits this function
+        let config_env = Box::pin(git_capabilities())
+            .await
+            .map(|c| c.supports(GitCapability::ConfigEnv))
+            .unwrap_or_default();
+        match config_env {
+            // appended, entries set by the user or a parent process stay in effect
+            true => {
+                let index = config_env_count(command);
+                command
+                    .env("GIT_CONFIG_COUNT", (index + 1).to_string())
+                    .env(format!("GIT_CONFIG_KEY_{}", index), "http.proxy")
+                    .env(format!("GIT_CONFIG_VALUE_{}", index), proxy);
+            }
+            // older git still reads the proxy from curl's environment
+            false => {
+                command.env("http_proxy", proxy).env("https_proxy", proxy);
+            }
+        }
+    }
+}
+
+/// Number of `GIT_CONFIG_COUNT` entries a command passes to git, set on the
+/// command or inherited from this process
+pub(crate) fn config_env_count(command: &Command) -> usize {
+    let value = command
+        .as_std()
+        .get_envs()
+        .find(|(key, _)| *key == "GIT_CONFIG_COUNT")
+        .map(|(_, value)| value.map(|value| value.to_os_string()))
+        .unwrap_or_else(|| std::env::var_os("GIT_CONFIG_COUNT"));
+    value
+        .and_then(|value| value.to_str()?.trim().parse().ok())
+        .unwrap_or(0)
+}
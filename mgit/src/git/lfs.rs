@@ -0,0 +1,128 @@
+use std::future::Future;
+use std::path::Path;
+
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+use crate::error::MgitResult;
+use crate::git::version::git_program;
+use crate::utils::cmd::exec_cmd;
+
+tokio::task_local! {
+    static SKIP_SMUDGE: ();
+}
+
+/// Run `f` with the LFS smudge filter disabled for the git commands it starts
+///
+/// Checkouts and resets then leave pointer files, which are replaced later by
+/// an explicit `git lfs checkout` so the download shows up as its own phase.
+pub async fn without_lfs_smudge<F: Future>(f: F) -> F::Output {
+    SKIP_SMUDGE.scope((), f).await
+}
+
+/// Set `GIT_LFS_SKIP_SMUDGE` on a git command started by `without_lfs_smudge`
+pub(crate) fn apply_env(command: &mut Command) {
+    if SKIP_SMUDGE.try_with(|_| ()).is_ok() {
+        command.env("GIT_LFS_SKIP_SMUDGE", "1");
+    }
+}
+
+/// Whether git-lfs is installed, detected once per process
+pub async fn is_lfs_installed() -> bool {
+    static INSTALLED: OnceCell<bool> = OnceCell::const_new();
+    *INSTALLED
+        .get_or_init(|| async {
+            let installed = exec_cmd(".", git_program(), &["lfs", "version"])
+                .await
+                .is_ok();
+            tracing::info!(installed = installed, "git_lfs_detected");
+            installed
+        })
+        .await
+}
+
+/// Whether any `.gitattributes` in the tree of `reference` routes files through LFS
+pub async fn uses_lfs(path: impl AsRef<Path>, reference: impl AsRef<str>) -> bool {
+    let args = [
+        "grep",
+        "-q",
+        "-e",
+        "filter=lfs",
+        reference.as_ref(),
+        "--",
+        ".gitattributes",
+        "*/.gitattributes",
+    ];
+    exec_cmd(path, git_program(), &args).await.is_ok()
+}
+
+/// `lfs.fetchinclude` and `lfs.fetchexclude` values matching sparse patterns
+///
+/// Negated patterns become excludes. Returns `None` for empty lists.
+pub fn lfs_fetch_filters(sparse: &[String]) -> (Option<String>, Option<String>) {
+    let (excludes, includes): (Vec<&str>, Vec<&str>) = sparse
+        .iter()
+        .map(|pattern| pattern.trim())
+        .filter(|pattern| !pattern.is_empty() && !pattern.starts_with('#'))
+        .partition(|pattern| pattern.starts_with('!'));
+    let excludes: Vec<&str> = excludes
+        .iter()
+        .map(|pattern| &pattern[1..])
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+    let join = |patterns: Vec<&str>| match patterns.is_empty() {
+        true => None,
+        false => Some(patterns.join(",")),
+    };
+    (join(includes), join(excludes))
+}
+
+/// Limit LFS downloads to the sparse patterns, or lift the limit with `None`
+pub async fn lfs_set_fetch_filters(
+    path: impl AsRef<Path>,
+    sparse: Option<&[String]>,
+) -> MgitResult<()> {
+    let path = path.as_ref();
+    let (include, exclude) = lfs_fetch_filters(sparse.unwrap_or_default());
+    for (key, value) in [("lfs.fetchinclude", include), ("lfs.fetchexclude", exclude)] {
+        match value {
+            Some(value) => {
+                exec_cmd(path, git_program(), &["config", key, &value]).await?;
+            }
+            None => {
+                // fails when not set
+                let _ = exec_cmd(path, git_program(), &["config", "--unset", key]).await;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Configure the LFS filters in the repo unless git already knows them
+pub async fn lfs_install(path: impl AsRef<Path>) -> MgitResult<()> {
+    let path = path.as_ref();
+    let args = ["config", "--get", "filter.lfs.process"];
+    if exec_cmd(path, git_program(), &args).await.is_ok() {
+        return Ok(());
+    }
+    exec_cmd(path, git_program(), &["lfs", "install", "--local"])
+        .await
+        .map(|_| ())
+}
+
+/// Download the LFS objects of `reference` from `remote`
+pub async fn lfs_fetch(
+    path: impl AsRef<Path>,
+    remote: impl AsRef<str>,
+    reference: impl AsRef<str>,
+) -> MgitResult<()> {
+    let args = ["lfs", "fetch", remote.as_ref(), reference.as_ref()];
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
+}
+
+/// Replace the pointer files of the worktree with their LFS objects
+pub async fn lfs_checkout(path: impl AsRef<Path>) -> MgitResult<()> {
+    exec_cmd(path, git_program(), &["lfs", "checkout"])
+        .await
+        .map(|_| ())
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use snafu::ResultExt;
+
+use crate::config::UserConfig;
+use crate::error::{CreateDirFailedSnafu, IoSnafu, MgitError, MgitResult};
+use crate::git::url::{canonicalize_url_with, rewrite_url};
+use crate::git::version::git_program;
+use crate::utils::cmd::exec_cmd;
+
+/// Environment variable that overrides the cache directory
+pub const CACHE_DIR_ENV: &str = "MGIT_CACHE_DIR";
+
+/// File touched whenever a mirror is refreshed or used, read by `cache prune`
+pub const LAST_USED_FILE: &str = "mgit-last-used";
+
+/// Directory holding the bare mirrors
+///
+/// Priority: `MGIT_CACHE_DIR` environment variable, `cache.dir` in
+/// `~/.mgit/config.toml`, then `~/.mgit/cache`.
+pub fn cache_dir() -> Option<PathBuf> {
+    std::env::var(CACHE_DIR_ENV)
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| UserConfig::get().cache.as_ref()?.dir.clone())
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".mgit").join("cache")))
+}
+
+/// Whether `sync` should go through the cache, `cache.enabled` of the user config
+pub fn is_cache_enabled() -> bool {
+    UserConfig::get()
+        .cache
+        .as_ref()
+        .and_then(|cache| cache.enabled)
+        .unwrap_or_default()
+}
+
+/// Location of the mirror of `url` relative to the cache directory
+///
+/// Built from the canonical url, so equivalent urls share one mirror.
+/// Characters not allowed in file names are replaced and `.`/`..` dropped.
+pub fn mirror_rel_path(url: &str, equivalents: &[Vec<String>]) -> PathBuf {
+    let canonical = canonicalize_url_with(url, equivalents);
+    // local paths keep their `.git` suffix when canonicalized
+    let canonical = canonical.strip_suffix(".git").unwrap_or(&canonical);
+    let parts: Vec<String> = canonical
+        .split('/')
+        .filter(|part| !matches!(*part, "" | "." | ".."))
+        .map(|part| {
+            part.chars()
+                .map(|c| match c {
+                    ':' | '*' | '?' | '"' | '<' | '>' | '|' | '\\' => '_',
+                    c => c,
+                })
+                .collect()
+        })
+        .collect();
+    PathBuf::from(format!("{}.git", parts.join("/")))
+}
+
+/// Absolute location of the mirror of `url`
+pub fn mirror_path(url: &str) -> Option<PathBuf> {
+    let equivalents = UserConfig::get()
+        .url_equivalents
+        .as_deref()
+        .unwrap_or_default();
+    Some(cache_dir()?.join(mirror_rel_path(url, equivalents)))
+}
+
+/// Whether `path` holds a bare repository
+pub fn is_mirror(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    path.join("HEAD").is_file() && path.join("objects").is_dir()
+}
+
+/// One lock per mirror, so repos sharing a remote don't update it concurrently
+fn mirror_lock(path: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+    LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(path.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+fn touch(mirror: &Path) {
+    if let Err(e) = std::fs::write(mirror.join(LAST_USED_FILE), "") {
+        tracing::warn!(mirror = %mirror.display(), error = %e, "mirror_touch_failed");
+    }
+}
+
+/// Create or refresh the mirror of `url`, returning its path
+///
+/// New mirrors are cloned next to their final location and moved in place
+/// once complete, so an interrupted clone never leaves a broken mirror.
+pub async fn update_mirror(url: &str) -> MgitResult<PathBuf> {
+    let path = mirror_path(url).ok_or_else(|| MgitError::OpsError {
+        message: "cache directory not available".to_string(),
+    })?;
+    let lock = mirror_lock(&path);
+    let _guard = lock.lock().await;
+
+    let fetch_url = rewrite_url(url);
+    if is_mirror(&path) {
+        exec_cmd(
+            &path,
+            git_program(),
+            &["remote", "set-url", "origin", &fetch_url],
+        )
+        .await?;
+        exec_cmd(&path, git_program(), &["fetch", "--prune", "origin"]).await?;
+    } else {
+        let parent = path.parent().unwrap_or(&path).to_path_buf();
+        tokio::fs::create_dir_all(&parent)
+            .await
+            .with_context(|_| CreateDirFailedSnafu {
+                path: parent.clone(),
+            })?;
+
+        let tmp_path = path.with_extension(format!("tmp-{}", std::process::id()));
+        if tmp_path.exists() {
+            tokio::fs::remove_dir_all(&tmp_path).await.ok();
+        }
+        let tmp_str = tmp_path.to_string_lossy().to_string();
+        let args = ["clone", "--mirror", "--quiet", &fetch_url, &tmp_str];
+        if let Err(e) = exec_cmd(&parent, git_program(), &args).await {
+            tokio::fs::remove_dir_all(&tmp_path).await.ok();
+            return Err(e);
+        }
+
+        if let Err(e) = tokio::fs::rename(&tmp_path, &path).await {
+            tokio::fs::remove_dir_all(&tmp_path).await.ok();
+            // another mgit process finished the same mirror first
+            if !is_mirror(&path) {
+                return Err(e).context(IoSnafu);
+            }
+        }
+    }
+
+    touch(&path);
+    tracing::info!(url = url, mirror = %path.display(), "mirror_updated");
+    Ok(path)
+}
+
+/// Fetch branches and tags of `mirror` into a workspace repo
+///
+/// Objects are copied, not borrowed through alternates, so pruning or
+/// collecting the cache never breaks a workspace. Tags the repo already has
+/// are not overwritten, a differing one fails the seed instead.
+pub async fn seed_from_mirror(
+    path: impl AsRef<Path>,
+    remote_name: &str,
+    mirror: impl AsRef<Path>,
+) -> MgitResult<()> {
+    let mirror = mirror.as_ref();
+    let mirror_str = mirror.to_string_lossy();
+    let heads = format!("+refs/heads/*:refs/remotes/{}/*", remote_name);
+    let args = [
+        "fetch",
+        "--quiet",
+        &mirror_str,
+        &heads,
+        "refs/tags/*:refs/tags/*",
+    ];
+    exec_cmd(path, git_program(), &args).await?;
+    touch(mirror);
+    Ok(())
+}
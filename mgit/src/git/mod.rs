@@ -1,11 +1,31 @@
-﻿pub mod remote;
+﻿pub(crate) mod host;
+pub mod lfs;
+pub mod mirror;
+pub mod remote;
 pub mod repo;
+pub mod stash;
 pub mod status;
+pub mod submodule;
+pub mod transfer;
 pub mod types;
+pub mod url;
+pub mod version;
 pub mod workflows;
+pub mod worktree;
 
+#[cfg(test)]
+mod tests;
+
+pub use lfs::*;
+pub use mirror::*;
 pub use remote::*;
 pub use repo::*;
+pub use stash::*;
 pub use status::*;
+pub use submodule::*;
+pub use transfer::*;
 pub use types::*;
+pub use url::*;
+pub use version::*;
 pub use workflows::*;
+pub use worktree::*;
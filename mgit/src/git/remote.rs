@@ -1,12 +1,47 @@
 use std::path::Path;
 
 use crate::error::MgitResult;
+use crate::git::url::{is_same_remote_url, rewrite_url};
+use crate::git::version::git_program;
 use crate::utils::cmd::exec_cmd;
 use crate::utils::style_message::StyleMessage;
 
+/// Host of a remote url, without user info and port
+///
+/// Handles `scheme://[user@]host[:port]/path` and scp-like `[user@]host:path`,
+/// returns `None` for local paths.
+pub fn remote_host(url: &str) -> Option<String> {
+    let authority = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            if scheme.eq_ignore_ascii_case("file") {
+                return None;
+            }
+            rest.split('/').next().unwrap_or(rest)
+        }
+        None => {
+            // scp-like syntax needs a colon before the first slash
+            let (authority, _) = url.split_once(':')?;
+            if authority.contains('/') || authority.contains('\\') || authority.len() == 1 {
+                return None;
+            }
+            authority
+        }
+    };
+    let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host.strip_prefix('[') {
+        // [::1]:22
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    match host.is_empty() {
+        true => None,
+        false => Some(host.to_lowercase()),
+    }
+}
+
 #[allow(dead_code)]
 pub async fn has_authenticity(path: impl AsRef<Path>) -> MgitResult<String> {
-    exec_cmd(path, "git", &["ls-remote"]).await
+    exec_cmd(path, git_program(), &["ls-remote"]).await
 }
 
 pub async fn is_remote_ref_valid(
@@ -15,7 +50,7 @@ pub async fn is_remote_ref_valid(
 ) -> MgitResult<()> {
     let remote_ref = remote_ref.as_ref();
     let args = ["branch", "--contains", remote_ref, "-r"];
-    match exec_cmd(path, "git", &args).await {
+    match exec_cmd(path, git_program(), &args).await {
         Ok(_) => Ok(()),
         Err(_) => {
             let msg = StyleMessage::git_remote_not_found(remote_ref).to_string();
@@ -32,13 +67,16 @@ pub async fn find_remote_name_by_url(
 
     let url = url.as_ref();
     let args = ["remote", "-v"];
-    let output = exec_cmd(&path, "git", &args).await?;
+    let output = exec_cmd(&path, git_program(), &args).await?;
 
+    // <name>\t<url> (fetch|push)
     for line in output.trim().lines() {
-        if line.contains(url) {
-            if let Some(remote_name) = line.split(url).next() {
-                return Ok(remote_name.trim().to_string());
-            }
+        let Some((remote_name, rest)) = line.split_once('\t') else {
+            continue;
+        };
+        let remote_url = rest.rsplit_once(" (").map_or(rest, |(u, _)| u);
+        if is_same_remote_url(remote_url.trim(), url) {
+            return Ok(remote_name.trim().to_string());
         }
     }
 
@@ -54,7 +92,7 @@ pub async fn find_remote_url_by_name(
 
     let name = name.as_ref();
     let args = ["remote", "get-url", name];
-    let output = exec_cmd(&path, "git", &args).await?;
+    let output = exec_cmd(&path, git_program(), &args).await?;
 
     if let Some(remote_url) = output.trim().lines().next() {
         return Ok(remote_url.trim().to_string());
@@ -65,13 +103,70 @@ pub async fn find_remote_url_by_name(
 }
 
 pub async fn add_remote_url(path: impl AsRef<Path>, url: impl AsRef<str>) -> MgitResult<()> {
-    let args = ["remote", "add", "origin", url.as_ref()];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    let url = rewrite_url(url.as_ref());
+    let args = ["remote", "add", "origin", url.as_str()];
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn update_remote_url(path: impl AsRef<Path>, url: impl AsRef<str>) -> MgitResult<String> {
-    let args = ["remote", "set-url", "origin", url.as_ref()];
-    exec_cmd(path, "git", &args).await
+    let url = rewrite_url(url.as_ref());
+    let args = ["remote", "set-url", "origin", url.as_str()];
+    exec_cmd(path, git_program(), &args).await
+}
+
+/// Make `remote` a promisor remote whose fetches apply `filter`
+///
+/// Later plain `git fetch` runs keep the filter and missing objects are
+/// downloaded on demand.
+pub async fn set_partial_clone_filter(
+    path: impl AsRef<Path>,
+    remote: &str,
+    filter: &str,
+) -> MgitResult<()> {
+    let path = path.as_ref();
+    let promisor = format!("remote.{}.promisor", remote);
+    let filter_key = format!("remote.{}.partialclonefilter", remote);
+    exec_cmd(path, git_program(), &["config", &promisor, "true"]).await?;
+    exec_cmd(path, git_program(), &["config", &filter_key, filter])
+        .await
+        .map(|_| ())
+}
+
+/// Partial clone filter of `remote`, `None` if it is not a promisor remote
+pub async fn get_partial_clone_filter(path: impl AsRef<Path>, remote: &str) -> Option<String> {
+    let key = format!("remote.{}.partialclonefilter", remote);
+    let output = exec_cmd(path, git_program(), &["config", "--get", &key])
+        .await
+        .ok()?;
+    let filter = output.trim();
+    match filter.is_empty() {
+        true => None,
+        false => Some(filter.to_string()),
+    }
+}
+
+/// Commit of `refname` in `git ls-remote` output, peeled for annotated tags
+pub fn parse_ls_remote(output: &str, refname: &str) -> Option<String> {
+    let peeled = format!("{}^{{}}", refname);
+    let find = |name: &str| {
+        output.lines().find_map(|line| {
+            let (oid, line_ref) = line.split_once('\t')?;
+            (line_ref.trim() == name).then(|| oid.trim().to_string())
+        })
+    };
+    find(&peeled).or_else(|| find(refname))
+}
+
+/// Commit `refname` points to on `remote`, `None` if the remote lacks the ref
+pub async fn ls_remote_ref(
+    path: impl AsRef<Path>,
+    remote: &str,
+    refname: &str,
+) -> MgitResult<Option<String>> {
+    let peeled = format!("{}^{{}}", refname);
+    let args = ["ls-remote", remote, refname, peeled.as_str()];
+    let output = exec_cmd(path, git_program(), &args).await?;
+    Ok(parse_ls_remote(&output, refname))
 }
 
 pub async fn get_remote_branches(path: impl AsRef<Path>) -> MgitResult<Vec<String>> {
@@ -79,7 +174,7 @@ pub async fn get_remote_branches(path: impl AsRef<Path>) -> MgitResult<Vec<Strin
     crate::git::repo::is_repository(path).await?;
 
     let args = ["branch", "-r"];
-    let output = exec_cmd(path, "git", &args).await?;
+    let output = exec_cmd(path, git_program(), &args).await?;
 
     let mut branches = Vec::new();
     for file in output.trim().lines() {
@@ -105,18 +200,18 @@ pub async fn new_remote_branch(
 ) -> MgitResult<()> {
     let arg = format!("origin/{}:refs/heads/{}", base_branch, new_branch);
     let args = vec!["push", "origin", arg.as_str(), "--force"];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn del_remote_branch(path: impl AsRef<Path>, branch: &str) -> MgitResult<()> {
     let args = vec!["push", "origin", "--delete", branch];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn check_remote_branch_exist(path: impl AsRef<Path>, branch: &str) -> MgitResult<bool> {
     let head = format!("refs/heads/{}", branch);
     let args = vec!["ls-remote", "--heads", "origin", head.as_str()];
-    let output = exec_cmd(path, "git", &args).await?;
+    let output = exec_cmd(path, git_program(), &args).await?;
     Ok(output.contains(&head))
 }
 
@@ -126,10 +221,10 @@ pub async fn new_local_tag(path: impl AsRef<Path>, local_ref: &str, tag: &str) -
         args.push(local_ref);
     }
 
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn push_tag(path: impl AsRef<Path>, tag: &str) -> MgitResult<()> {
     let args = vec!["push", "origin", tag, "--force"];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
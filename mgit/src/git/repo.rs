@@ -1,14 +1,48 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::error::MgitResult;
+use crate::git::version::{git_capabilities, git_program, GitCapability};
 use crate::utils::cmd::exec_cmd;
 use crate::utils::style_message::StyleMessage;
 
+/// Git directory of a work tree
+///
+/// `.git` is the git directory itself, or for linked worktrees and
+/// submodules a file holding `gitdir: <path>`.
+pub fn git_dir(path: impl AsRef<Path>) -> Option<PathBuf> {
+    let path = path.as_ref();
+    let dot_git = path.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = std::fs::read_to_string(&dot_git).ok()?;
+    let gitdir = content
+        .lines()
+        .find_map(|line| line.strip_prefix("gitdir:"))?
+        .trim();
+    let gitdir = path.join(gitdir);
+    gitdir.is_dir().then_some(gitdir)
+}
+
+/// Git directory shared by all worktrees of a repository, holding objects and refs
+pub fn git_common_dir(path: impl AsRef<Path>) -> Option<PathBuf> {
+    let git_dir = git_dir(path)?;
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => Some(git_dir.join(common_dir.trim())),
+        Err(_) => Some(git_dir),
+    }
+}
+
+/// Whether the work tree at `path` was created by `git worktree add`
+pub fn is_linked_worktree(path: impl AsRef<Path>) -> bool {
+    git_dir(path).is_some_and(|git_dir| git_dir.join("commondir").is_file())
+}
+
 #[must_use]
 pub async fn is_repository(path: impl AsRef<Path>) -> MgitResult<()> {
-    if path.as_ref().join(".git").is_dir() {
+    if git_dir(&path).is_some() {
         let args = ["rev-parse", "--show-cdup"];
-        if let Ok(output) = exec_cmd(path, "git", &args).await {
+        if let Ok(output) = exec_cmd(path, git_program(), &args).await {
             if output.trim().is_empty() {
                 return Ok(());
             }
@@ -24,7 +58,7 @@ pub async fn is_repository(path: impl AsRef<Path>) -> MgitResult<()> {
 pub async fn get_current_commit(path: impl AsRef<Path>) -> MgitResult<String> {
     is_repository(&path).await?;
     let args = ["rev-parse", "HEAD"];
-    let output = exec_cmd(path, "git", &args).await?;
+    let output = exec_cmd(path, git_program(), &args).await?;
 
     if let Some(oid) = output.trim().lines().next() {
         return Ok(oid.to_string());
@@ -39,7 +73,7 @@ pub async fn get_tracking_branch(path: impl AsRef<Path>) -> MgitResult<String> {
     is_repository(&path).await?;
     let args = ["rev-parse", "--symbolic-full-name", "--abbrev-ref", "@{u}"];
 
-    let output = exec_cmd(path, "git", &args).await?;
+    let output = exec_cmd(path, git_program(), &args).await?;
     if !output.trim().is_empty() {
         return Ok(output.trim().to_string());
     }
@@ -53,7 +87,7 @@ pub async fn get_head_tags(path: impl AsRef<Path>) -> MgitResult<Vec<String>> {
     is_repository(&path).await?;
     let args = ["tag", "--points-at", "HEAD"];
 
-    let output = exec_cmd(path, "git", &args).await?;
+    let output = exec_cmd(path, git_program(), &args).await?;
 
     if output.contains("fatal:") {
         return Err(crate::error::MgitError::OpsError { message: output });
@@ -65,11 +99,11 @@ pub async fn get_head_tags(path: impl AsRef<Path>) -> MgitResult<Vec<String>> {
 pub async fn get_current_branch(path: impl AsRef<Path>) -> MgitResult<String> {
     is_repository(&path).await?;
     let args = ["branch", "--show-current"];
-    let output = exec_cmd(&path, "git", &args).await?;
+    let output = exec_cmd(&path, git_program(), &args).await?;
 
     for line in output.trim().lines() {
         let branch = line.to_string();
-        let branch_output = exec_cmd(&path, "git", &["branch", "-l", &branch]).await?;
+        let branch_output = exec_cmd(&path, git_program(), &["branch", "-l", &branch]).await?;
         if branch_output.contains(&branch) {
             return Ok(branch);
         }
@@ -81,13 +115,25 @@ pub async fn get_current_branch(path: impl AsRef<Path>) -> MgitResult<String> {
 
 pub async fn get_branch_log(path: impl AsRef<Path>, branch: String) -> String {
     let args = ["show-branch", "--sha1-name", &branch];
-    let output = exec_cmd(path, "git", &args).await.unwrap_or_default();
+    let output = exec_cmd(path, git_program(), &args)
+        .await
+        .unwrap_or_default();
     output.trim().to_string()
 }
 
 pub async fn init(path: impl AsRef<Path>) -> MgitResult<()> {
-    let args = ["init", "-b", "master"];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    if git_capabilities()
+        .await?
+        .supports(GitCapability::InitBranch)
+    {
+        let args = ["init", "-b", "master"];
+        return exec_cmd(path, git_program(), &args).await.map(|_| ());
+    }
+
+    // `init -b` is missing before git 2.28, point HEAD to master by hand
+    exec_cmd(&path, git_program(), &["init"]).await?;
+    let args = ["symbolic-ref", "HEAD", "refs/heads/master"];
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn local_branch_already_exist(
@@ -96,13 +142,13 @@ pub async fn local_branch_already_exist(
 ) -> MgitResult<bool> {
     let args = ["branch", "-l", branch.as_ref()];
 
-    let output = exec_cmd(path, "git", &args).await?;
+    let output = exec_cmd(path, git_program(), &args).await?;
     let exist = output.trim().contains(branch.as_ref());
     Ok(exist)
 }
 
 pub async fn checkout(path: impl AsRef<Path>, args: &[&str]) -> MgitResult<()> {
-    exec_cmd(path, "git", args).await.map(|_| ())
+    exec_cmd(path, git_program(), args).await.map(|_| ())
 }
 
 pub async fn set_tracking_remote_branch(
@@ -114,7 +160,7 @@ pub async fn set_tracking_remote_branch(
 ) -> MgitResult<StyleMessage> {
     let args = ["branch", "--set-upstream-to", remote_ref.as_ref()];
 
-    let msg = match exec_cmd(full_path, "git", &args).await {
+    let msg = match exec_cmd(full_path, git_program(), &args).await {
         Ok(_) => StyleMessage::git_tracking_succ(rel_path, local_branch, remote_desc),
         Err(_) => StyleMessage::git_tracking_failed(rel_path, remote_desc),
     };
@@ -123,7 +169,7 @@ pub async fn set_tracking_remote_branch(
 
 pub async fn ls_files(path: impl AsRef<Path>) -> MgitResult<String> {
     let args = ["ls-files", "-s"];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn log_current(path: impl AsRef<Path>) -> MgitResult<String> {
@@ -133,5 +179,21 @@ pub async fn log_current(path: impl AsRef<Path>) -> MgitResult<String> {
         "--pretty=format:\"%H%n%an <%ae>%n%ad%n%s%n\"",
         "--date=format-local:\"%Y-%m-%d %H:%M:%S\"",
     ];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
+}
+
+pub async fn is_head_detached(path: impl AsRef<Path>) -> MgitResult<bool> {
+    is_repository(&path).await?;
+    let args = ["symbolic-ref", "-q", "HEAD"];
+    Ok(exec_cmd(path, git_program(), &args).await.is_err())
+}
+
+pub async fn get_ref_commit(
+    path: impl AsRef<Path>,
+    refname: impl AsRef<str>,
+) -> MgitResult<String> {
+    let rev = format!("{}^{{commit}}", refname.as_ref());
+    let args = ["rev-parse", "--verify", "-q", rev.as_str()];
+    let output = exec_cmd(path, git_program(), &args).await?;
+    Ok(output.trim().to_string())
 }
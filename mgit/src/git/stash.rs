@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use crate::error::MgitResult;
+use crate::git::version::git_program;
+use crate::utils::cmd::exec_cmd;
+
+/// Start of the message of every stash mgit creates, to tell them from the user's own
+pub const STASH_MESSAGE_PREFIX: &str = "mgit:";
+
+/// A stash of a repository, as reported by `git stash list`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stash {
+    /// Reference of the stash, like `stash@{0}`
+    pub reference: String,
+    /// Branch the stash was created on, `None` when detached
+    pub branch: Option<String>,
+    pub message: String,
+    /// Relative date of creation, like `2 hours ago`
+    pub date: String,
+}
+
+impl Stash {
+    /// Whether the stash was created by mgit
+    pub fn is_mgit(&self) -> bool {
+        self.message.starts_with(STASH_MESSAGE_PREFIX)
+    }
+}
+
+/// Parse the output of `git stash list --format=%gd%x09%cr%x09%gs`
+pub fn parse_stash_list(output: &str) -> Vec<Stash> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let reference = fields.next()?.to_string();
+            let date = fields.next()?.to_string();
+            // `On <branch>: <message>`, or `WIP on <branch>: <commit>` without a message
+            let (place, message) = fields.next()?.split_once(": ")?;
+            let branch = place
+                .strip_prefix("WIP on ")
+                .or_else(|| place.strip_prefix("On "))
+                .filter(|branch| *branch != "(no branch)")
+                .map(|branch| branch.to_string());
+            Some(Stash {
+                reference,
+                branch,
+                message: message.to_string(),
+                date,
+            })
+        })
+        .collect()
+}
+
+/// Stashes of the repository created by mgit, newest first
+pub async fn list_mgit_stashes(path: impl AsRef<Path>) -> MgitResult<Vec<Stash>> {
+    let args = ["stash", "list", "--format=%gd%x09%cr%x09%gs"];
+    let output = exec_cmd(path, git_program(), &args).await?;
+    Ok(parse_stash_list(&output)
+        .into_iter()
+        .filter(|stash| stash.is_mgit())
+        .collect())
+}
+
+/// Restore a stash, dropping it too with `pop` unless it conflicts
+pub async fn stash_apply(
+    path: impl AsRef<Path>,
+    reference: impl AsRef<str>,
+    pop: bool,
+) -> MgitResult<String> {
+    let action = match pop {
+        true => "pop",
+        false => "apply",
+    };
+    let args = ["stash", action, reference.as_ref()];
+    exec_cmd(path, git_program(), &args).await
+}
+
+pub async fn stash_drop(path: impl AsRef<Path>, reference: impl AsRef<str>) -> MgitResult<String> {
+    let args = ["stash", "drop", reference.as_ref()];
+    exec_cmd(path, git_program(), &args).await
+}
+
+/// Files changed by a stash, as `git diff --stat`
+pub async fn stash_show(path: impl AsRef<Path>, reference: impl AsRef<str>) -> MgitResult<String> {
+    let args = ["stash", "show", "--stat", reference.as_ref()];
+    exec_cmd(path, git_program(), &args).await
+}
+
+/// Files left with merge conflicts in the work tree
+pub async fn get_conflicted_files(path: impl AsRef<Path>) -> MgitResult<Vec<String>> {
+    let args = ["diff", "--name-only", "--diff-filter=U"];
+    let output = exec_cmd(path, git_program(), &args).await?;
+    Ok(output.lines().map(|line| line.to_string()).collect())
+}
@@ -1,21 +1,22 @@
 use std::path::Path;
 
 use crate::error::MgitResult;
+use crate::git::version::git_program;
 use crate::utils::cmd::exec_cmd;
 
 pub async fn get_untrack_files(path: impl AsRef<Path>) -> MgitResult<String> {
     let args = ["ls-files", ".", "--exclude-standard", "--others"];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn get_changed_files(path: impl AsRef<Path>) -> MgitResult<String> {
     let args = ["diff", "--name-only"];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn get_staged_files(path: impl AsRef<Path>) -> MgitResult<String> {
     let args = ["diff", "--cached", "--name-only"];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn get_rev_list_count(
@@ -23,5 +24,12 @@ pub async fn get_rev_list_count(
     branch_pair: impl AsRef<str>,
 ) -> MgitResult<String> {
     let args = ["rev-list", "--count", "--left-right", branch_pair.as_ref()];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
+}
+
+/// Whether the worktree has no staged, unstaged or untracked changes
+pub async fn is_worktree_clean(path: impl AsRef<Path>) -> MgitResult<bool> {
+    let args = ["status", "--porcelain"];
+    let output = exec_cmd(path, git_program(), &args).await?;
+    Ok(output.trim().is_empty())
 }
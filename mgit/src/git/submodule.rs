@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::MgitResult;
+use crate::git::repo::{git_common_dir, git_dir};
+use crate::git::version::git_program;
+use crate::utils::cmd::exec_cmd;
+
+/// Parse `git config --file .gitmodules --get-regexp` output into submodule paths
+pub fn parse_submodule_paths(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(key, _)| key.starts_with("submodule.") && key.ends_with(".path"))
+        .map(|(_, path)| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Paths of the submodules listed in `.gitmodules` of the worktree
+pub async fn list_submodules(path: impl AsRef<Path>) -> Vec<String> {
+    let path = path.as_ref();
+    if !path.join(".gitmodules").is_file() {
+        return Vec::new();
+    }
+    let args = [
+        "config",
+        "--file",
+        ".gitmodules",
+        "--get-regexp",
+        r"^submodule\..*\.path$",
+    ];
+    match exec_cmd(path, git_program(), &args).await {
+        Ok(output) => parse_submodule_paths(&output),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Register the submodules of `.gitmodules` in the repo config
+pub async fn submodule_init(path: impl AsRef<Path>) -> MgitResult<()> {
+    exec_cmd(path, git_program(), &["submodule", "init"])
+        .await
+        .map(|_| ())
+}
+
+/// Copy changed submodule urls of `.gitmodules` into the repo config
+pub async fn submodule_sync(path: impl AsRef<Path>) -> MgitResult<()> {
+    let args = ["submodule", "sync", "--recursive", "--quiet"];
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
+}
+
+/// Check out the commit recorded for a submodule, cloning it when missing
+///
+/// `force` discards local changes of the submodule.
+pub async fn submodule_update(
+    path: impl AsRef<Path>,
+    submodule: &str,
+    depth: Option<&usize>,
+    force: bool,
+) -> MgitResult<()> {
+    let depth = depth.map(|depth| depth.to_string());
+    let mut args = vec!["submodule", "update", "--init", "--recursive"];
+    if let Some(depth) = depth.as_deref() {
+        args.push("--depth");
+        args.push(depth);
+    }
+    if force {
+        args.push("--force");
+    }
+    args.push("--");
+    args.push(submodule);
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
+}
+
+/// Remove untracked files of a submodule and its nested submodules
+pub async fn submodule_clean(path: impl AsRef<Path>, submodule: &str) -> MgitResult<()> {
+    let path = path.as_ref().join(submodule);
+    exec_cmd(&path, git_program(), &["clean", "-fd"]).await?;
+    let args = [
+        "submodule",
+        "foreach",
+        "--recursive",
+        "--quiet",
+        "git clean -fd",
+    ];
+    exec_cmd(&path, git_program(), &args).await.map(|_| ())
+}
+
+/// Whether `path` is the checkout of a submodule of the repo at `parent`
+///
+/// Submodule git dirs live below `modules` of the git dir of the parent.
+pub fn is_submodule_of(path: impl AsRef<Path>, parent: impl AsRef<Path>) -> bool {
+    let (Some(dir), Some(parent_dir)) = (git_dir(path), git_common_dir(parent)) else {
+        return false;
+    };
+    let canonical = |p: PathBuf| p.canonicalize().unwrap_or(p);
+    canonical(dir).starts_with(canonical(parent_dir).join("modules"))
+}
+
+/// Whether every submodule is initialized and at its recorded commit
+pub async fn are_submodules_up_to_date(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    if !path.join(".gitmodules").is_file() {
+        return true;
+    }
+    let args = ["submodule", "status", "--recursive"];
+    match exec_cmd(path, git_program(), &args).await {
+        // ` ` up to date, `-` not initialized, `+` other commit, `U` conflicts
+        Ok(output) => output.lines().all(|line| line.starts_with(' ')),
+        Err(_) => false,
+    }
+}
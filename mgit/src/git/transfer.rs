@@ -0,0 +1,61 @@
+use crate::utils::progress::{TransferPhase, TransferProgress};
+
+/// Parse one progress line of `git fetch --progress`
+///
+/// Lines look like `Receiving objects:  45% (450/1000), 120.00 MiB | 5.00 MiB/s`,
+/// the server side phases are prefixed with `remote: `. Lines without a
+/// percentage, like `Enumerating objects: 302, done.`, return `None`.
+pub fn parse_transfer_progress(line: &str) -> Option<TransferProgress> {
+    let line = line.trim();
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (name, rest) = line.split_once(':')?;
+    let phase = match name.trim() {
+        "Counting objects" => TransferPhase::Counting,
+        "Compressing objects" => TransferPhase::Compressing,
+        "Receiving objects" => TransferPhase::Receiving,
+        "Unpacking objects" => TransferPhase::Unpacking,
+        "Resolving deltas" => TransferPhase::Resolving,
+        _ => return None,
+    };
+
+    let (percent, rest) = rest.trim_start().split_once('%')?;
+    let percent = percent.trim().parse::<u8>().ok()?.min(100);
+
+    // `, 120.00 MiB | 5.00 MiB/s` follows the object count while receiving or unpacking
+    let (bytes, rate) = match rest.split_once("), ") {
+        Some((_, transfer)) => {
+            let transfer = transfer.trim_end_matches(", done.").trim();
+            let (bytes, rate) = match transfer.split_once('|') {
+                Some((bytes, rate)) => (bytes, Some(rate)),
+                None => (transfer, None),
+            };
+            (
+                parse_size(bytes),
+                rate.and_then(|rate| parse_size(rate.trim().trim_end_matches("/s"))),
+            )
+        }
+        None => (None, None),
+    };
+
+    Some(TransferProgress {
+        phase,
+        percent,
+        bytes,
+        rate,
+    })
+}
+
+/// Parse sizes printed by git, like `120.00 MiB` or `512 bytes`
+fn parse_size(size: &str) -> Option<u64> {
+    let (value, unit) = size.trim().split_once(' ')?;
+    let value = value.parse::<f64>().ok()?;
+    let scale: u64 = match unit.trim() {
+        "bytes" | "byte" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        _ => return None,
+    };
+    Some((value * scale as f64) as u64)
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use crate::config::UserConfig;
+
+/// Canonical form of a remote url, used to tell whether two urls name the same remote
+///
+/// Network urls become `host[:port]/path`: scheme, user info and default ports
+/// are dropped, host and path are lowercased and a trailing `.git` is removed, so
+/// `git@Host:a/b.git`, `ssh://git@host:22/a/b` and `https://host/a/b/` are equal.
+/// Local paths only get their separators normalized.
+pub fn canonicalize_url(url: &str) -> String {
+    let url = url.trim();
+    let (scheme, authority, path) = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let scheme = scheme.to_lowercase();
+            if scheme == "file" {
+                return canonicalize_local(rest);
+            }
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            (scheme, authority, path)
+        }
+        None => match url.split_once(':') {
+            // scp-like syntax needs a colon before the first slash
+            Some((authority, path))
+                if !authority.contains('/') && !authority.contains('\\') && authority.len() > 1 =>
+            {
+                ("ssh".to_string(), authority, path)
+            }
+            _ => return canonicalize_local(url),
+        },
+    };
+
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let (host, port) = match host_port.strip_prefix('[') {
+        // [::1]:22
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((host, rest)) => (host, rest.strip_prefix(':')),
+            None => (bracketed, None),
+        },
+        None => match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        },
+    };
+    let port = port.filter(|port| !port.is_empty() && Some(*port) != default_port(&scheme));
+
+    let path = path.trim_matches('/');
+    let path = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+
+    let mut canonical = host.to_lowercase();
+    if let Some(port) = port {
+        canonical.push(':');
+        canonical.push_str(port);
+    }
+    if !path.is_empty() {
+        canonical.push('/');
+        canonical.push_str(&path.to_lowercase());
+    }
+    canonical
+}
+
+fn canonicalize_local(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    match path.trim_end_matches('/') {
+        "" => path,
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn default_port(scheme: &str) -> Option<&'static str> {
+    match scheme {
+        "ssh" | "git+ssh" | "ssh+git" => Some("22"),
+        "https" => Some("443"),
+        "http" => Some("80"),
+        "git" => Some("9418"),
+        _ => None,
+    }
+}
+
+/// Canonical form of `url`, with `equivalents` groups folded to their first entry
+///
+/// Each group lists url prefixes that reach the same server, e.g.
+/// `["https://gitea.internal/", "ssh://git@gitea.internal:2222/"]`.
+pub fn canonicalize_url_with(url: &str, equivalents: &[Vec<String>]) -> String {
+    let canonical = canonicalize_url(url);
+    for group in equivalents {
+        let Some((preferred, others)) = group.split_first() else {
+            continue;
+        };
+        for other in others {
+            let prefix = canonicalize_url(other);
+            let matched = canonical == prefix
+                || canonical
+                    .strip_prefix(&prefix)
+                    .is_some_and(|rest| rest.starts_with('/'));
+            if matched {
+                return format!(
+                    "{}{}",
+                    canonicalize_url(preferred),
+                    &canonical[prefix.len()..]
+                );
+            }
+        }
+    }
+    canonical
+}
+
+/// Replace the longest matching prefix of `url` by its rewrite rule
+pub fn rewrite_url_with(url: &str, rules: &BTreeMap<String, String>) -> String {
+    rules
+        .iter()
+        .filter(|(from, _)| !from.is_empty() && url.starts_with(from.as_str()))
+        .max_by_key(|(from, _)| from.len())
+        .map(|(from, to)| format!("{}{}", to, &url[from.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Url actually set on the workspace repo for a config url, honoring
+/// `url-rewrite` of the user config
+pub fn rewrite_url(url: &str) -> String {
+    match &UserConfig::get().url_rewrite {
+        Some(rules) => rewrite_url_with(url, rules),
+        None => url.to_string(),
+    }
+}
+
+/// Check whether two urls name the same remote, honoring `url-equivalents`
+/// and `url-rewrite` of the user config
+pub fn is_same_remote_url(a: &str, b: &str) -> bool {
+    let equivalents = UserConfig::get()
+        .url_equivalents
+        .as_deref()
+        .unwrap_or_default();
+    let same = |a: &str, b: &str| {
+        a == b || canonicalize_url_with(a, equivalents) == canonicalize_url_with(b, equivalents)
+    };
+    same(a, b) || same(&rewrite_url(a), &rewrite_url(b))
+}
@@ -0,0 +1,162 @@
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+
+use tokio::sync::OnceCell;
+
+use crate::config::UserConfig;
+use crate::error::MgitResult;
+use crate::utils::cmd::exec_cmd;
+
+/// Environment variable that overrides the git binary
+pub const GIT_ENV: &str = "MGIT_GIT";
+
+/// Git binary used for every git command
+///
+/// Priority: `MGIT_GIT` environment variable, `git` in `~/.mgit/config.toml`,
+/// then `git` from PATH. Resolved once per process.
+pub fn git_program() -> &'static str {
+    static PROGRAM: OnceLock<String> = OnceLock::new();
+    PROGRAM.get_or_init(|| {
+        std::env::var(GIT_ENV)
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .or_else(|| UserConfig::get().git.clone())
+            .unwrap_or_else(|| "git".to_string())
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GitVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl GitVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse the output of `git --version`, e.g. `git version 2.39.3 (Apple Git-145)`
+    pub fn parse(desc: impl AsRef<str>) -> Option<Self> {
+        static VERSION_RE: OnceLock<regex::Regex> = OnceLock::new();
+        let re = VERSION_RE.get_or_init(|| {
+            regex::Regex::new(r"(\d+)\.(\d+)(?:\.(\d+))?").expect("valid git version pattern")
+        });
+        let caps = re.captures(desc.as_ref())?;
+        let parse = |i: usize| {
+            caps.get(i)
+                .and_then(|m| m.as_str().parse::<u32>().ok())
+                .unwrap_or_default()
+        };
+        Some(Self::new(parse(1), parse(2), parse(3)))
+    }
+}
+
+impl Display for GitVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Git features mgit relies on that are missing in older releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GitCapability {
+    /// `git init -b <branch>`
+    InitBranch,
+    /// `git branch --show-current`
+    ShowCurrentBranch,
+    /// `git sparse-checkout set --no-cone`
+    SparseNoCone,
+    /// `git fetch --recurse-submodules=on-demand`
+    FetchRecurseSubmodules,
+    /// `GIT_CONFIG_COUNT` / `GIT_CONFIG_KEY_<n>` / `GIT_CONFIG_VALUE_<n>`
+    ConfigEnv,
+    /// `git fetch --filter` into a promisor remote
+    PartialClone,
+}
+
+impl GitCapability {
+    pub fn min_version(&self) -> GitVersion {
+        match self {
+            GitCapability::InitBranch => GitVersion::new(2, 28, 0),
+            GitCapability::ShowCurrentBranch => GitVersion::new(2, 22, 0),
+            GitCapability::SparseNoCone => GitVersion::new(2, 35, 0),
+            GitCapability::FetchRecurseSubmodules => GitVersion::new(1, 7, 3),
+            GitCapability::ConfigEnv => GitVersion::new(2, 31, 0),
+            GitCapability::PartialClone => GitVersion::new(2, 27, 0),
+        }
+    }
+
+    pub fn desc(&self) -> &'static str {
+        match self {
+            GitCapability::InitBranch => "`init -b`",
+            GitCapability::ShowCurrentBranch => "`branch --show-current`",
+            GitCapability::SparseNoCone => "`sparse-checkout set --no-cone`",
+            GitCapability::FetchRecurseSubmodules => "`fetch --recurse-submodules=on-demand`",
+            GitCapability::ConfigEnv => "`GIT_CONFIG_COUNT`",
+            GitCapability::PartialClone => "`fetch --filter`",
+        }
+    }
+}
+
+/// Capability set of the git binary in use
+#[derive(Debug, Clone)]
+pub struct GitCapabilities {
+    pub version: GitVersion,
+}
+
+impl GitCapabilities {
+    pub fn new(version: GitVersion) -> Self {
+        Self { version }
+    }
+
+    pub fn supports(&self, capability: GitCapability) -> bool {
+        self.version >= capability.min_version()
+    }
+
+    pub fn require(&self, capability: GitCapability) -> MgitResult<()> {
+        if self.supports(capability) {
+            return Ok(());
+        }
+        Err(crate::error::MgitError::GitVersionUnsupported {
+            version: self.version.to_string(),
+            feature: capability.desc().to_string(),
+            required: capability.min_version().to_string(),
+        })
+    }
+}
+
+/// Detect the git version once per process
+pub async fn git_capabilities() -> MgitResult<&'static GitCapabilities> {
+    static CAPABILITIES: OnceCell<GitCapabilities> = OnceCell::const_new();
+    CAPABILITIES
+        .get_or_try_init(|| async {
+            let output = exec_cmd(".", git_program(), &["--version"]).await?;
+            let version = GitVersion::parse(&output).ok_or_else(|| {
+                crate::error::MgitError::GitVersionUnknown {
+                    message: output.trim().to_string(),
+                }
+            })?;
+            tracing::info!(
+                program = git_program(),
+                version = %version,
+                "git_version_detected"
+            );
+            Ok(GitCapabilities::new(version))
+        })
+        .await
+}
+
+/// Fail early with a clear error when the git in use lacks a required feature
+pub async fn require_capabilities(capabilities: &[GitCapability]) -> MgitResult<()> {
+    let detected = git_capabilities().await?;
+    for capability in capabilities {
+        detected.require(*capability)?;
+    }
+    Ok(())
+}
@@ -1,11 +1,13 @@
 use std::path::Path;
 
 use crate::error::MgitResult;
+use crate::git::stash::STASH_MESSAGE_PREFIX;
+use crate::git::version::git_program;
 use crate::utils::cmd::exec_cmd;
 
 pub async fn clean(path: impl AsRef<Path>) -> MgitResult<()> {
     let args = ["clean", "-fd"];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn reset(
@@ -15,7 +17,7 @@ pub async fn reset(
 ) -> MgitResult<()> {
     let args = ["reset", reset_type.as_ref(), remote_ref.as_ref()];
 
-    match exec_cmd(path, "git", &args).await {
+    match exec_cmd(path, git_program(), &args).await {
         Ok(_) => Ok(()),
         Err(e) => Err(crate::error::MgitError::OpsError {
             message: format!("Error: {}", e),
@@ -26,7 +28,7 @@ pub async fn reset(
 pub async fn add_untracked_files(path: impl AsRef<Path>) -> MgitResult<String> {
     let path = path.as_ref();
     let args = ["ls-files", "-o", "--exclude-standard"];
-    let paths_desc = exec_cmd(path, "git", &args).await?;
+    let paths_desc = exec_cmd(path, git_program(), &args).await?;
     if paths_desc.is_empty() {
         return Ok("not found any unchecked file to add".to_string());
     }
@@ -37,7 +39,7 @@ pub async fn add_untracked_files(path: impl AsRef<Path>) -> MgitResult<String> {
         args.push(file);
     }
 
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn stash(path: impl AsRef<Path>) -> MgitResult<String> {
@@ -45,13 +47,14 @@ pub async fn stash(path: impl AsRef<Path>) -> MgitResult<String> {
 
     add_untracked_files(path).await?;
 
-    let args = ["stash", "-u"];
-    exec_cmd(path, "git", &args).await
+    let message = format!("{} sync", STASH_MESSAGE_PREFIX);
+    let args = ["stash", "push", "-u", "-m", &message];
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn stash_pop(path: impl AsRef<Path>) -> MgitResult<String> {
     let args = ["stash", "pop"];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
 }
 
 pub async fn sparse_checkout_set(path: impl AsRef<Path>, dirs: &Vec<String>) -> MgitResult<()> {
@@ -60,15 +63,29 @@ pub async fn sparse_checkout_set(path: impl AsRef<Path>, dirs: &Vec<String>) ->
         args.push(dir);
     }
 
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn sparse_checkout_disable(path: impl AsRef<Path>) -> MgitResult<()> {
     let args = vec!["sparse-checkout", "disable"];
-    exec_cmd(path, "git", &args).await.map(|_| ())
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
 }
 
 pub async fn sparse_checkout_list(path: impl AsRef<Path>) -> MgitResult<String> {
     let args = vec!["sparse-checkout", "list"];
-    exec_cmd(path, "git", &args).await
+    exec_cmd(path, git_program(), &args).await
+}
+
+/// Sparse patterns of the worktree, `None` if sparse checkout is not enabled
+pub async fn get_sparse_patterns(path: impl AsRef<Path>) -> Option<Vec<String>> {
+    let output = sparse_checkout_list(path).await.ok()?;
+    let patterns: Vec<String> = output
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    match patterns.is_empty() {
+        true => None,
+        false => Some(patterns),
+    }
 }
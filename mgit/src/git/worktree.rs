@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::MgitResult;
+use crate::git::repo::git_common_dir;
+use crate::git::version::git_program;
+use crate::utils::cmd::exec_cmd;
+
+/// A work tree of a repository, as reported by `git worktree list`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Worktree {
+    pub path: PathBuf,
+    /// Checked out branch, `None` when detached
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    /// Whether this is the work tree of the main clone
+    pub is_main: bool,
+}
+
+/// Parse the output of `git worktree list --porcelain`
+pub fn parse_worktree_list(output: &str) -> Vec<Worktree> {
+    let mut worktrees: Vec<Worktree> = Vec::new();
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push(Worktree {
+                path: PathBuf::from(path),
+                is_main: worktrees.is_empty(),
+                ..Default::default()
+            });
+        } else if let Some(worktree) = worktrees.last_mut() {
+            if let Some(head) = line.strip_prefix("HEAD ") {
+                worktree.head = Some(head.to_string());
+            } else if let Some(branch) = line.strip_prefix("branch ") {
+                let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+                worktree.branch = Some(branch.to_string());
+            }
+        }
+    }
+    worktrees
+}
+
+pub async fn list_worktrees(path: impl AsRef<Path>) -> MgitResult<Vec<Worktree>> {
+    let args = ["worktree", "list", "--porcelain"];
+    let output = exec_cmd(path, git_program(), &args).await?;
+    Ok(parse_worktree_list(&output))
+}
+
+/// Other work tree of the same repository that has `branch` checked out
+///
+/// Old git lets `checkout -B` reset a branch checked out elsewhere, which
+/// silently changes the other work tree, so callers check first.
+pub async fn find_branch_worktree(
+    path: impl AsRef<Path>,
+    branch: impl AsRef<str>,
+) -> MgitResult<Option<PathBuf>> {
+    let path = path.as_ref();
+    // only repositories with linked work trees can conflict
+    if !git_common_dir(path).is_some_and(|dir| dir.join("worktrees").is_dir()) {
+        return Ok(None);
+    }
+    let current = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let worktree = list_worktrees(path).await?.into_iter().find(|worktree| {
+        worktree.branch.as_deref() == Some(branch.as_ref())
+            && std::fs::canonicalize(&worktree.path).unwrap_or_else(|_| worktree.path.clone())
+                != current
+    });
+    Ok(worktree.map(|worktree| worktree.path))
+}
+
+/// Create a detached work tree of the repository at `path` in `target`
+pub async fn add_worktree(
+    path: impl AsRef<Path>,
+    target: impl AsRef<Path>,
+    reference: impl AsRef<str>,
+) -> MgitResult<()> {
+    let target = target.as_ref().to_string_lossy();
+    let args = ["worktree", "add", "--detach", &target, reference.as_ref()];
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
+}
+
+pub async fn remove_worktree(
+    path: impl AsRef<Path>,
+    target: impl AsRef<Path>,
+    force: bool,
+) -> MgitResult<()> {
+    let target = target.as_ref().to_string_lossy();
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(&target);
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
+}
+
+/// Drop the bookkeeping of work trees whose directory is gone
+pub async fn prune_worktrees(path: impl AsRef<Path>) -> MgitResult<()> {
+    let args = ["worktree", "prune"];
+    exec_cmd(path, git_program(), &args).await.map(|_| ())
+}
@@ -0,0 +1,340 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use walkdir::WalkDir;
+
+use crate::config::{
+    repos_to_map_with_ignore, MgitConfig, RepoConfig, RepoSelection, RepoState, Workspace,
+};
+use crate::error::{AcquirePermitFailedSnafu, MgitError, MgitResult};
+use crate::git;
+use crate::utils::askpass::AskpassSession;
+use crate::utils::cmd::exec_cmd;
+use crate::utils::path::PathExtension;
+use crate::utils::progress::{format_size, Progress, RepoInfo};
+use crate::utils::style_message::StyleMessage;
+
+/// Mirrors unused for longer than this are removed by `cache prune`
+const DEFAULT_PRUNE_DAYS: u64 = 30;
+
+pub struct CacheOptions {
+    pub path: PathBuf,
+    pub config_path: PathBuf,
+    pub thread_count: usize,
+    pub ignore: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+}
+
+impl CacheOptions {
+    pub fn new(
+        path: Option<impl AsRef<Path>>,
+        config_path: Option<impl AsRef<Path>>,
+        thread: Option<usize>,
+        ignore: Option<Vec<String>>,
+        labels: Option<Vec<String>>,
+    ) -> Self {
+        let (path, config_path) = Workspace::resolve(path, config_path);
+        Self {
+            path,
+            config_path,
+            thread_count: thread.unwrap_or(4),
+            ignore,
+            labels,
+            only: None,
+            states: None,
+        }
+    }
+}
+
+pub struct CachePruneOptions {
+    pub older_than: Duration,
+}
+
+impl CachePruneOptions {
+    pub fn new(older_than_days: Option<u64>) -> Self {
+        let days = older_than_days.unwrap_or(DEFAULT_PRUNE_DAYS);
+        Self {
+            older_than: Duration::from_secs(days * 24 * 60 * 60),
+        }
+    }
+}
+
+/// Create or refresh the mirror of every repo of a workspace
+pub async fn cache_update(
+    options: CacheOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let path = &options.path;
+    let config_path = &options.config_path;
+    let _askpass = AskpassSession::begin();
+
+    progress.on_message(StyleMessage::ops_start("update cache", path));
+
+    if !config_path.is_file() {
+        return Err(MgitError::ConfigFileNotFound {
+            path: config_path.clone(),
+        });
+    }
+
+    let mgit_config = MgitConfig::load(config_path).ok_or(MgitError::LoadConfigFailed {
+        source: std::io::Error::other("Failed to load config"),
+    })?;
+
+    let repo_configs = if let Some(repos) = mgit_config.repos {
+        repos
+    } else {
+        return Ok(StyleMessage::new().plain_text("No repos to cache"));
+    };
+
+    let selection = RepoSelection::new(path, options.ignore.as_ref())?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &mgit_config.default_branch, &progress)
+        .await;
+    progress.on_batch_start(repos_map.len());
+
+    let semaphore = Arc::new(Semaphore::new(options.thread_count));
+    let mut join_set = JoinSet::new();
+    let counter = Arc::new(std::sync::atomic::AtomicUsize::new(1));
+
+    for (id, repo_config) in repos_map {
+        let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
+            AcquirePermitFailedSnafu {
+                message: "Failed to acquire semaphore permit for parallel execution".to_string(),
+            }
+            .build()
+        })?;
+        let counter = Arc::clone(&counter);
+        let progress = progress.clone();
+
+        join_set.spawn(async move {
+            let _permit = permit;
+            let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let repo_info = RepoInfo::new(id, index, &repo_config);
+            let rel_path = repo_info.rel_path().display_path();
+
+            progress.on_repo_start(&repo_info, "update cache...".into());
+            let Some(url) = repo_config.remote.as_ref() else {
+                progress.on_repo_success(&repo_info, "no remote, skipped".into());
+                return Ok(());
+            };
+            match git::update_mirror(url).await {
+                Ok(mirror) => {
+                    let msg = StyleMessage::new().plain_text(mirror.display_path());
+                    progress.on_repo_success(&repo_info, msg);
+                    Ok(())
+                }
+                Err(e) => {
+                    progress.on_repo_error(&repo_info, StyleMessage::new());
+                    Err(StyleMessage::git_error(rel_path, &e))
+                }
+            }
+        });
+    }
+
+    let mut errors = Vec::new();
+    while let Some(res) = join_set.join_next().await {
+        match res {
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(StyleMessage::new().plain_text(format!("Task failed: {}", e))),
+            _ => {}
+        }
+    }
+
+    progress.on_batch_finish();
+
+    if errors.is_empty() {
+        Ok(StyleMessage::ops_success("update cache"))
+    } else {
+        let msg = StyleMessage::ops_failed("update cache", errors.len());
+        Err(MgitError::OpsError {
+            message: format!(
+                "{}\nErrors:\n{}",
+                msg,
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+    }
+}
+
+/// Run `git gc` in every mirror of the cache
+pub async fn cache_gc(progress: impl Progress + 'static) -> MgitResult<StyleMessage> {
+    let cache_dir = require_cache_dir()?;
+    progress.on_message(StyleMessage::ops_start("gc cache", &cache_dir));
+
+    let mirrors = list_mirrors(&cache_dir);
+    let repo_configs = mirror_repo_configs(&cache_dir, &mirrors);
+    progress.on_batch_start(mirrors.len());
+
+    let mut errors = Vec::new();
+    for (index, (mirror, repo_config)) in mirrors.iter().zip(&repo_configs).enumerate() {
+        let repo_info = RepoInfo::new(index, index + 1, repo_config);
+        progress.on_repo_start(&repo_info, "gc...".into());
+        match exec_cmd(mirror, git::git_program(), &["gc", "--quiet"]).await {
+            Ok(_) => progress.on_repo_success(&repo_info, StyleMessage::new()),
+            Err(e) => {
+                progress.on_repo_error(&repo_info, StyleMessage::new());
+                errors.push(StyleMessage::git_error(repo_info.rel_path(), &e));
+            }
+        }
+    }
+
+    progress.on_batch_finish();
+
+    if errors.is_empty() {
+        Ok(StyleMessage::ops_success("gc cache"))
+    } else {
+        let msg = StyleMessage::ops_failed("gc cache", errors.len());
+        Err(MgitError::OpsError {
+            message: format!(
+                "{}\nErrors:\n{}",
+                msg,
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+    }
+}
+
+/// Remove mirrors that no sync or `cache update` used for a while
+pub async fn cache_prune(
+    options: CachePruneOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let cache_dir = require_cache_dir()?;
+    progress.on_message(StyleMessage::ops_start("prune cache", &cache_dir));
+
+    let now = SystemTime::now();
+    let mut result = StyleMessage::new();
+    let mut removed = 0;
+    for mirror in list_mirrors(&cache_dir) {
+        let idle = last_used(&mirror)
+            .and_then(|time| now.duration_since(time).ok())
+            .unwrap_or_default();
+        if idle < options.older_than {
+            continue;
+        }
+
+        let rel_path = mirror.strip_prefix(&cache_dir).unwrap_or(&mirror);
+        match tokio::fs::remove_dir_all(&mirror).await {
+            Ok(_) => {
+                tracing::info!(mirror = %mirror.display(), "mirror_pruned");
+                result = result.join(StyleMessage::remove_file_succ(rel_path));
+                result = result.join("\n".into());
+                removed += 1;
+                remove_empty_parents(&mirror, &cache_dir);
+            }
+            Err(e) => {
+                result = result.join(StyleMessage::remove_file_failed(rel_path, e));
+                result = result.join("\n".into());
+            }
+        }
+    }
+
+    Ok(result.join(StyleMessage::cache_pruned(removed)))
+}
+
+/// Report the disk usage of every mirror of the cache
+pub async fn cache_du(progress: impl Progress + 'static) -> MgitResult<StyleMessage> {
+    let cache_dir = require_cache_dir()?;
+    progress.on_message(StyleMessage::ops_start("cache usage", &cache_dir));
+
+    let mut result = StyleMessage::new();
+    let mut total = 0;
+    for mirror in list_mirrors(&cache_dir) {
+        let size = dir_size(&mirror);
+        total += size;
+        let rel_path = mirror.strip_prefix(&cache_dir).unwrap_or(&mirror);
+        result = result.join(StyleMessage::cache_usage(rel_path, format_size(size)));
+        result = result.join("\n".into());
+    }
+
+    Ok(result.join(StyleMessage::cache_usage_total(format_size(total))))
+}
+
+fn require_cache_dir() -> MgitResult<PathBuf> {
+    git::cache_dir().ok_or_else(|| MgitError::OpsError {
+        message: "cache directory not available".to_string(),
+    })
+}
+
+/// Mirrors below `cache_dir`, sorted by path
+fn list_mirrors(cache_dir: &Path) -> Vec<PathBuf> {
+    let mut mirrors = Vec::new();
+    let mut walker = WalkDir::new(cache_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        if git::is_mirror(entry.path()) {
+            mirrors.push(entry.path().to_path_buf());
+            walker.skip_current_dir();
+        }
+    }
+    mirrors
+}
+
+/// Progress entries for mirrors, named by their path in the cache
+fn mirror_repo_configs(cache_dir: &Path, mirrors: &[PathBuf]) -> Vec<RepoConfig> {
+    mirrors
+        .iter()
+        .map(|mirror| RepoConfig {
+            local: Some(mirror.strip_prefix(cache_dir).unwrap_or(mirror).norm_path()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn last_used(mirror: &Path) -> Option<SystemTime> {
+    std::fs::metadata(mirror.join(git::LAST_USED_FILE))
+        .or_else(|_| std::fs::metadata(mirror))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn remove_empty_parents(mirror: &Path, cache_dir: &Path) {
+    let mut dir = mirror.parent();
+    while let Some(parent) = dir {
+        if parent == cache_dir || !parent.starts_with(cache_dir) {
+            break;
+        }
+        // fails when not empty
+        if std::fs::remove_dir(parent).is_err() {
+            break;
+        }
+        dir = parent.parent();
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
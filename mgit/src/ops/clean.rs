@@ -4,10 +4,14 @@ use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 use crate::config::MgitConfig;
+use crate::config::RepoIgnore;
+use crate::config::Workspace;
 use crate::error::MgitError;
 use crate::error::MgitResult;
-use crate::utils::current_dir;
+use crate::git;
 use crate::utils::label;
+use crate::utils::lock::WorkspaceLock;
+use crate::utils::path::PathExtension;
 use crate::utils::progress::Progress;
 use crate::utils::style_message::StyleMessage;
 
@@ -15,6 +19,10 @@ pub struct CleanOptions {
     pub path: PathBuf,
     pub config_path: PathBuf,
     pub labels: Option<Vec<String>>,
+    /// Repos to keep even though they are not in the config, on top of `.mgitignore`
+    pub ignore: Option<Vec<String>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl CleanOptions {
@@ -23,15 +31,13 @@ impl CleanOptions {
         config_path: Option<impl AsRef<Path>>,
         labels: Option<Vec<String>>,
     ) -> Self {
-        let path = match path {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => current_dir(),
-        };
-        let config_path = config_path.map_or(path.join(".gitrepos"), |p| p.as_ref().to_path_buf());
+        let (path, config_path) = Workspace::resolve(path, config_path);
         Self {
             path,
             config_path,
             labels,
+            ignore: None,
+            wait: false,
         }
     }
 }
@@ -40,6 +46,21 @@ impl CleanOptions {
 pub async fn clean_repo(
     options: CleanOptions,
     progress: impl Progress,
+) -> MgitResult<StyleMessage> {
+    // a missing directory has nothing to lock, the clean reports it
+    let _lock = match options.path.is_dir() {
+        true => {
+            Some(WorkspaceLock::acquire(&options.path, "clean", options.wait, &progress).await?)
+        }
+        false => None,
+    };
+    exec_clean_repo(options, progress).await
+}
+
+/// Clean the workspace, for callers already holding its lock
+pub(crate) async fn exec_clean_repo(
+    options: CleanOptions,
+    progress: impl Progress,
 ) -> MgitResult<StyleMessage> {
     let path = &options.path;
     let config_path = &options.config_path;
@@ -72,13 +93,77 @@ pub async fn clean_repo(
         repo_configs = label::filter(&repo_configs, &labels).cloned().collect();
     }
 
-    let config_repo_paths: Vec<PathBuf> = repo_configs
+    // repos ignored by `.gitrepos.local` are skipped, not unused
+    let mut config_repo_paths: Vec<PathBuf> = repo_configs
         .iter()
+        .chain(&mgit_config.ignored_repos)
         .filter_map(|item| item.local.as_ref())
         .map(PathBuf::from)
         .collect();
 
-    // search for git repos and create .gitrepos file
+    let input_path = path.to_owned();
+    let unused_paths = find_unused_repos(&input_path, &config_repo_paths).await?;
+
+    // ignored repos are kept like the ones in the config
+    let ignore = RepoIgnore::load(path, options.ignore.as_ref())?;
+    let (ignored_paths, mut unused_paths): (Vec<_>, Vec<_>) = unused_paths
+        .into_iter()
+        .partition(|unused_path| ignore.is_ignored(unused_path.norm_path()));
+    config_repo_paths.extend(ignored_paths);
+
+    unused_paths.sort_by_key(|b| std::cmp::Reverse(b.components().count()));
+
+    // remvoe unused repositories
+    let mut count: u32 = 0;
+    for unused_path in unused_paths {
+        // find contianed repo path
+        let contained_paths = find_contained_paths(&unused_path, &config_repo_paths);
+
+        // remove unused directory
+        if !contained_paths.is_empty() {
+            if let Err(e) = remove_unused_files(&input_path, &unused_path, &contained_paths).await {
+                tracing::error!(message = %StyleMessage::remove_file_failed(&unused_path, &e).to_plain_text());
+            };
+        } else {
+            let full_path = input_path.join(&unused_path);
+            // a linked worktree also leaves bookkeeping in the repository it belongs to
+            let common_dir = match git::is_linked_worktree(&full_path) {
+                true => git::git_common_dir(&full_path),
+                false => None,
+            };
+            match tokio::fs::remove_dir_all(&full_path).await {
+                Ok(_) => {
+                    if let Some(common_dir) = common_dir {
+                        if let Err(e) = git::prune_worktrees(&common_dir).await {
+                            tracing::warn!(repo = %unused_path.display(), error = %e, "worktree_prune_failed");
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(crate::error::MgitError::OpsError {
+                        message: format!("Failed to remove dir {}: {}", unused_path.display(), e),
+                    });
+                }
+            }
+        }
+        count += 1;
+
+        progress.on_message(StyleMessage::remove_file_succ(&unused_path));
+    }
+
+    // show statistics info
+    Ok(StyleMessage::remove_repo_succ(count))
+}
+
+/// Search `input_path` for git repositories that are not listed in `config_repo_paths`
+///
+/// Returns paths relative to `input_path`. These are the repositories `clean` removes.
+pub(crate) async fn find_unused_repos(
+    input_path: &Path,
+    config_repo_paths: &[PathBuf],
+) -> MgitResult<Vec<PathBuf>> {
+    // search for git repos, `.git` is a file in linked worktrees
     let glob = GlobBuilder::new("**/.git")
         .literal_separator(true)
         .build()
@@ -87,13 +172,11 @@ pub async fn clean_repo(
         })?
         .compile_matcher();
 
-    let input_path = path.to_owned();
-
     // WalkDir is blocking, so we wrap it in spawn_blocking
-    let input_path_clone = input_path.clone();
-    let config_repo_paths_clone = config_repo_paths.clone();
+    let input_path_clone = input_path.to_path_buf();
+    let config_repo_paths_clone = config_repo_paths.to_vec();
 
-    let mut unused_paths = tokio::task::spawn_blocking(move || {
+    tokio::task::spawn_blocking(move || {
         let mut unused = Vec::new();
         let mut it = WalkDir::new(&input_path_clone).into_iter();
 
@@ -120,7 +203,13 @@ pub async fn clean_repo(
                     }
                 };
 
-                if !config_repo_paths_clone.contains(&rel_path) {
+                // submodules of a configured repo are checked out by the repo itself
+                let is_submodule = config_repo_paths_clone.iter().any(|repo_path| {
+                    rel_path.starts_with(repo_path)
+                        && &rel_path != repo_path
+                        && git::is_submodule_of(&pb, input_path_clone.join(repo_path))
+                });
+                if !config_repo_paths_clone.contains(&rel_path) && !is_submodule {
                     unused.push(rel_path);
                 }
 
@@ -134,39 +223,7 @@ pub async fn clean_repo(
     .await
     .map_err(|e| crate::error::MgitError::OpsError {
         message: format!("Failed to walk directory: {}", e),
-    })?;
-
-    unused_paths.sort_by_key(|b| std::cmp::Reverse(b.components().count()));
-
-    // remvoe unused repositories
-    let mut count: u32 = 0;
-    for unused_path in unused_paths {
-        // find contianed repo path
-        let contained_paths = find_contained_paths(&unused_path, &config_repo_paths);
-
-        // remove unused directory
-        if !contained_paths.is_empty() {
-            if let Err(e) = remove_unused_files(&input_path, &unused_path, &contained_paths).await {
-                tracing::error!(message = %StyleMessage::remove_file_failed(&unused_path, &e).to_plain_text());
-            };
-        } else {
-            match tokio::fs::remove_dir_all(input_path.join(&unused_path)).await {
-                Ok(_) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
-                Err(e) => {
-                    return Err(crate::error::MgitError::OpsError {
-                        message: format!("Failed to remove dir {}: {}", unused_path.display(), e),
-                    });
-                }
-            }
-        }
-        count += 1;
-
-        progress.on_message(StyleMessage::remove_file_succ(&unused_path));
-    }
-
-    // show statistics info
-    Ok(StyleMessage::remove_repo_succ(count))
+    })
 }
 
 fn find_contained_paths(unused_path: &Path, config_repo_paths: &[PathBuf]) -> Vec<PathBuf> {
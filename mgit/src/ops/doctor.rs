@@ -0,0 +1,452 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::{
+    repos_to_map_with_ignore, MgitConfig, RepoConfig, RepoSelection, RepoState, Workspace,
+};
+use crate::error::{AcquirePermitFailedSnafu, MgitError, MgitResult};
+use crate::git;
+use crate::git::GitCapability;
+use crate::utils::lock::WorkspaceLock;
+use crate::utils::progress::{Progress, RepoInfo};
+use crate::utils::StyleMessage;
+
+/// Lock files left behind by interrupted git processes
+const LOCK_FILES: [&str; 2] = ["index.lock", "shallow.lock"];
+
+/// Marker files of an operation that stopped half way
+const IN_PROGRESS_MARKERS: [(&str, &str); 4] = [
+    ("rebase-merge", "rebase"),
+    ("rebase-apply", "rebase"),
+    ("MERGE_HEAD", "merge"),
+    ("CHERRY_PICK_HEAD", "cherry-pick"),
+];
+
+pub struct DoctorOptions {
+    pub path: PathBuf,
+    pub config_path: PathBuf,
+    pub thread_count: usize,
+    pub ignore: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    pub fix: bool,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
+}
+
+impl DoctorOptions {
+    pub fn new(
+        path: Option<impl AsRef<Path>>,
+        config_path: Option<impl AsRef<Path>>,
+        thread: Option<usize>,
+        ignore: Option<Vec<String>>,
+        labels: Option<Vec<String>>,
+        fix: Option<bool>,
+    ) -> Self {
+        let (path, config_path) = Workspace::resolve(path, config_path);
+        Self {
+            path,
+            config_path,
+            thread_count: thread.unwrap_or(4),
+            ignore,
+            labels,
+            only: None,
+            states: None,
+            fix: fix.unwrap_or(false),
+            wait: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Finding {
+    severity: Severity,
+    /// empty for workspace-wide findings
+    rel_path: String,
+    desc: String,
+    fixed: bool,
+}
+
+impl Finding {
+    fn new(severity: Severity, rel_path: impl AsRef<str>, desc: impl AsRef<str>) -> Self {
+        Self {
+            severity,
+            rel_path: rel_path.as_ref().to_string(),
+            desc: desc.as_ref().to_string(),
+            fixed: false,
+        }
+    }
+
+    fn to_message(&self) -> StyleMessage {
+        StyleMessage::doctor_finding(
+            self.severity.as_str(),
+            &self.rel_path,
+            &self.desc,
+            self.fixed,
+        )
+    }
+}
+
+/// Check the workspace for broken local state
+///
+/// Runs per-repo checks (lock files, remote url, detached HEAD, unfinished
+/// rebase/merge, sparse patterns) and global checks (git version). With
+/// `fix` enabled the safe cases are repaired in place.
+pub async fn doctor(
+    options: DoctorOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let path = &options.path;
+    let config_path = &options.config_path;
+    let ignore = options.ignore.as_ref();
+    let fix = options.fix;
+
+    progress.on_message(StyleMessage::ops_start("doctor", path));
+
+    // if directory doesn't exist, finsh doctor
+    if !path.is_dir() {
+        return Err(MgitError::DirNotFound { path: path.clone() });
+    }
+    // check if .gitrepos exists
+    if !config_path.is_file() {
+        return Err(MgitError::ConfigFileNotFound {
+            path: config_path.clone(),
+        });
+    }
+    // checking reads the repos only, repairing them must not race a sync
+    let _lock = match fix {
+        true => Some(WorkspaceLock::acquire(path, "doctor", options.wait, &progress).await?),
+        false => None,
+    };
+    // load config file(like .gitrepos)
+    let mgit_config = MgitConfig::load(config_path).ok_or(MgitError::LoadConfigFailed {
+        source: std::io::Error::other("Failed to load config"),
+    })?;
+
+    let repo_configs = mgit_config.repos.unwrap_or_default();
+    let default_branch = mgit_config.default_branch;
+
+    // global checks
+    let mut findings = Vec::new();
+    let mut sparse_supported = true;
+    match git::git_capabilities().await {
+        Ok(capabilities) => {
+            let version = capabilities.version;
+            if !capabilities.supports(GitCapability::ShowCurrentBranch) {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    "",
+                    format!(
+                        "git {} is too old, mgit requires git >= {}",
+                        version,
+                        GitCapability::ShowCurrentBranch.min_version()
+                    ),
+                ));
+            }
+            if !capabilities.supports(GitCapability::SparseNoCone) {
+                sparse_supported = false;
+                if repo_configs.iter().any(|repo| repo.sparse.is_some()) {
+                    findings.push(Finding::new(
+                        Severity::Error,
+                        "",
+                        format!(
+                            "git {} does not support {}, sparse repos require git >= {}",
+                            version,
+                            GitCapability::SparseNoCone.desc(),
+                            GitCapability::SparseNoCone.min_version()
+                        ),
+                    ));
+                }
+            }
+        }
+        Err(e) => {
+            sparse_supported = false;
+            findings.push(Finding::new(
+                Severity::Error,
+                "",
+                format!("git is not available: {}", e),
+            ));
+        }
+    }
+
+    // per-repo checks
+    let selection = RepoSelection::new(path, ignore)?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &default_branch, &progress)
+        .await;
+    progress.on_batch_start(repos_map.len());
+
+    let semaphore = Arc::new(Semaphore::new(options.thread_count));
+    let mut join_set = JoinSet::new();
+    let counter = std::sync::atomic::AtomicUsize::new(1);
+    let counter = Arc::new(counter);
+    let default_branch = Arc::new(default_branch);
+
+    for (id, repo_config) in repos_map {
+        let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
+            AcquirePermitFailedSnafu {
+                message: "Failed to acquire semaphore permit for doctor operation".to_string(),
+            }
+            .build()
+        })?;
+        let counter = Arc::clone(&counter);
+        let progress = progress.clone();
+        let base_path = path.clone();
+        let default_branch = Arc::clone(&default_branch);
+
+        join_set.spawn(async move {
+            let _permit = permit;
+            let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let repo_info = RepoInfo::new(id, index, &repo_config);
+
+            progress.on_repo_start(&repo_info, "checking...".into());
+
+            let findings = check_repo(
+                &base_path,
+                &repo_config,
+                &default_branch,
+                fix,
+                sparse_supported,
+            )
+            .await;
+
+            let unresolved = findings.iter().filter(|f| !f.fixed).count();
+            let msg = match unresolved {
+                0 => StyleMessage::new().plain_text("ok"),
+                n => StyleMessage::new().plain_text(format!("{} problem(s)", n)),
+            };
+            if findings
+                .iter()
+                .any(|f| f.severity == Severity::Error && !f.fixed)
+            {
+                progress.on_repo_error(&repo_info, msg);
+            } else {
+                progress.on_repo_success(&repo_info, msg);
+            }
+            findings
+        });
+    }
+
+    while let Some(res) = join_set.join_next().await {
+        match res {
+            Ok(repo_findings) => findings.extend(repo_findings),
+            Err(e) => {
+                tracing::error!("Task panicked or cancelled: {}", e);
+                findings.push(Finding::new(
+                    Severity::Error,
+                    "",
+                    format!("Task failed: {}", e),
+                ));
+            }
+        }
+    }
+
+    progress.on_batch_finish();
+
+    // global findings first, then by repo path
+    findings.sort_by(|a, b| {
+        a.rel_path
+            .cmp(&b.rel_path)
+            .then(b.severity.cmp(&a.severity))
+    });
+    for finding in &findings {
+        progress.on_message(finding.to_message());
+    }
+
+    let unresolved = |severity: Severity| {
+        findings
+            .iter()
+            .filter(|f| f.severity == severity && !f.fixed)
+            .count()
+    };
+    let errors = unresolved(Severity::Error);
+    let warnings = unresolved(Severity::Warning);
+    let fixed = findings.iter().filter(|f| f.fixed).count();
+
+    let summary = StyleMessage::doctor_summary(errors, warnings, fixed);
+    if errors == 0 {
+        Ok(summary)
+    } else {
+        Err(MgitError::OpsError {
+            message: summary.to_string(),
+        })
+    }
+}
+
+async fn check_repo(
+    base_path: &Path,
+    repo_config: &RepoConfig,
+    default_branch: &Option<String>,
+    fix: bool,
+    sparse_supported: bool,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(rel_path) = repo_config.local.as_ref() else {
+        return findings;
+    };
+    let full_path = base_path.join(rel_path);
+
+    if !full_path.exists() {
+        findings.push(Finding::new(
+            Severity::Info,
+            rel_path,
+            "not cloned yet, run sync to create it",
+        ));
+        return findings;
+    }
+
+    let Some(git_dir) = git::git_dir(&full_path) else {
+        findings.push(Finding::new(
+            Severity::Error,
+            rel_path,
+            "directory exists but is not a git repository",
+        ));
+        return findings;
+    };
+    let common_dir = git::git_common_dir(&full_path).unwrap_or_else(|| git_dir.clone());
+
+    // stale lock files, shallow.lock lives in the git dir shared by all worktrees
+    for lock_file in LOCK_FILES {
+        let lock_path = match lock_file {
+            "shallow.lock" => common_dir.join(lock_file),
+            _ => git_dir.join(lock_file),
+        };
+        if !lock_path.exists() {
+            continue;
+        }
+        let mut finding = Finding::new(
+            Severity::Error,
+            rel_path,
+            format!("stale .git/{} found", lock_file),
+        );
+        if fix {
+            match tokio::fs::remove_file(&lock_path).await {
+                Ok(_) => {
+                    tracing::info!(repo = %rel_path, lock_file = lock_file, "doctor_lock_file_removed");
+                    finding.fixed = true;
+                }
+                Err(e) => {
+                    finding.desc = format!("{}, remove failed: {}", finding.desc, e);
+                }
+            }
+        }
+        findings.push(finding);
+    }
+
+    if git::is_repository(&full_path).await.is_err() {
+        findings.push(Finding::new(
+            Severity::Error,
+            rel_path,
+            "git repository is broken",
+        ));
+        return findings;
+    }
+
+    // unfinished rebase/merge
+    for (marker, ops) in IN_PROGRESS_MARKERS {
+        if git_dir.join(marker).exists() {
+            findings.push(Finding::new(
+                Severity::Error,
+                rel_path,
+                format!("{} in progress, finish or abort it first", ops),
+            ));
+            break;
+        }
+    }
+
+    // remote url
+    if let Some(remote) = repo_config.remote.as_ref() {
+        let current = git::find_remote_url_by_name(&full_path, "origin").await;
+        let mismatch = match &current {
+            Ok(url) if git::is_same_remote_url(url, remote) => None,
+            Ok(url) => Some(format!(
+                "remote url {} does not match config {}",
+                url, remote
+            )),
+            Err(_) => Some("remote origin not found".to_string()),
+        };
+        if let Some(desc) = mismatch {
+            let mut finding = Finding::new(Severity::Error, rel_path, desc);
+            if fix {
+                let res = match current {
+                    Ok(_) => git::update_remote_url(&full_path, remote).await.map(|_| ()),
+                    Err(_) => git::add_remote_url(&full_path, remote).await,
+                };
+                match res {
+                    Ok(_) => finding.fixed = true,
+                    Err(e) => finding.desc = format!("{}, fix failed: {}", finding.desc, e),
+                }
+            }
+            findings.push(finding);
+        }
+    }
+
+    // detached HEAD when a branch is expected
+    let expected_branch = match (&repo_config.commit, &repo_config.tag) {
+        (None, None) => repo_config.branch.as_ref().or(default_branch.as_ref()),
+        _ => None,
+    };
+    if let Some(branch) = expected_branch {
+        if let Ok(true) = git::is_head_detached(&full_path).await {
+            findings.push(Finding::new(
+                Severity::Warning,
+                rel_path,
+                format!("HEAD is detached, expected branch {}", branch),
+            ));
+        }
+    }
+
+    // sparse patterns
+    let current_sparse = git::get_sparse_patterns(&full_path).await;
+    let sparse_desc = match (&repo_config.sparse, &current_sparse) {
+        (Some(dirs), Some(current)) if dirs != current => {
+            Some("sparse patterns differ from config")
+        }
+        (Some(_), None) => Some("sparse checkout is not enabled"),
+        (None, Some(_)) => Some("sparse checkout is enabled but not configured"),
+        _ => None,
+    };
+    if let Some(desc) = sparse_desc {
+        let mut finding = Finding::new(Severity::Warning, rel_path, desc);
+        if fix && sparse_supported {
+            let res = match &repo_config.sparse {
+                Some(dirs) => git::sparse_checkout_set(&full_path, dirs).await,
+                None => git::sparse_checkout_disable(&full_path).await,
+            };
+            match res {
+                Ok(_) => finding.fixed = true,
+                Err(e) => finding.desc = format!("{}, fix failed: {}", finding.desc, e),
+            }
+        }
+        findings.push(finding);
+    }
+
+    findings
+}
@@ -0,0 +1,517 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table, Value};
+
+use crate::config::{
+    repos_to_map_with_ignore, MgitConfig, RepoConfig, RepoSelection, RepoState, Workspace,
+};
+use crate::error::{AcquirePermitFailedSnafu, MgitError, MgitResult};
+use crate::git;
+use crate::ops::clean::find_unused_repos;
+use crate::utils::lock::WorkspaceLock;
+use crate::utils::path::PathExtension;
+use crate::utils::progress::{Progress, RepoInfo};
+use crate::utils::StyleMessage;
+
+pub struct DriftOptions {
+    pub path: PathBuf,
+    pub config_path: PathBuf,
+    pub thread_count: usize,
+    pub ignore: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Update the config from the workspace for these repos, empty for all drifted repos
+    pub write: Option<Vec<String>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
+}
+
+impl DriftOptions {
+    pub fn new(
+        path: Option<impl AsRef<Path>>,
+        config_path: Option<impl AsRef<Path>>,
+        thread: Option<usize>,
+        ignore: Option<Vec<String>>,
+        labels: Option<Vec<String>>,
+        write: Option<Vec<String>>,
+    ) -> Self {
+        let (path, config_path) = Workspace::resolve(path, config_path);
+        Self {
+            path,
+            config_path,
+            thread_count: thread.unwrap_or(4),
+            ignore,
+            labels,
+            only: None,
+            states: None,
+            write,
+            wait: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DriftState {
+    /// configured repo present on disk
+    Present,
+    /// configured repo missing on disk
+    Missing,
+    /// repo on disk but not in config
+    Extra,
+}
+
+#[derive(Debug)]
+struct RepoDrift {
+    rel_path: String,
+    state: DriftState,
+    items: Vec<(&'static str, String)>,
+}
+
+impl RepoDrift {
+    fn new(rel_path: impl AsRef<str>, state: DriftState) -> Self {
+        Self {
+            rel_path: rel_path.as_ref().to_string(),
+            state,
+            items: Vec::new(),
+        }
+    }
+
+    fn is_drifted(&self) -> bool {
+        self.state != DriftState::Present || !self.items.is_empty()
+    }
+}
+
+/// Report where the workspace deviates from the config without syncing
+///
+/// For each configured repo: remote url, checked-out ref and sparse patterns
+/// are compared with the config, and missing repos are reported. Repos on disk
+/// that are not in the config (what `clean` would delete) are reported as extra.
+/// With `write` the config is updated to match the workspace.
+pub async fn drift(
+    options: DriftOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let path = &options.path;
+    let config_path = &options.config_path;
+    let ignore = options.ignore.as_ref();
+
+    progress.on_message(StyleMessage::ops_start("drift", path));
+
+    // if directory doesn't exist, finsh drift
+    if !path.is_dir() {
+        return Err(MgitError::DirNotFound { path: path.clone() });
+    }
+    // check if .gitrepos exists
+    if !config_path.is_file() {
+        return Err(MgitError::ConfigFileNotFound {
+            path: config_path.clone(),
+        });
+    }
+    // reporting reads the repos only, writing the config must not race a sync
+    let _lock = match options.write.is_some() {
+        true => Some(WorkspaceLock::acquire(path, "drift", options.wait, &progress).await?),
+        false => None,
+    };
+    // load config file(like .gitrepos)
+    let mgit_config = MgitConfig::load(config_path).ok_or(MgitError::LoadConfigFailed {
+        source: std::io::Error::other("Failed to load config"),
+    })?;
+
+    let repo_configs = mgit_config.repos.clone().unwrap_or_default();
+    let default_branch = mgit_config.default_branch.clone();
+
+    // retain repos exclude ignore repositories
+    let selection = RepoSelection::new(path, ignore)?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs.clone(), &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &default_branch, &progress)
+        .await;
+    progress.on_batch_start(repos_map.len());
+
+    let semaphore = Arc::new(Semaphore::new(options.thread_count));
+    let mut join_set = JoinSet::new();
+    let counter = std::sync::atomic::AtomicUsize::new(1);
+    let counter = Arc::new(counter);
+    let default_branch_arc = Arc::new(default_branch.clone());
+
+    for (id, repo_config) in repos_map {
+        let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
+            AcquirePermitFailedSnafu {
+                message: "Failed to acquire semaphore permit for drift operation".to_string(),
+            }
+            .build()
+        })?;
+        let counter = Arc::clone(&counter);
+        let progress = progress.clone();
+        let base_path = path.clone();
+        let default_branch = Arc::clone(&default_branch_arc);
+
+        join_set.spawn(async move {
+            let _permit = permit;
+            let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let repo_info = RepoInfo::new(id, index, &repo_config);
+
+            progress.on_repo_start(&repo_info, "checking...".into());
+            let repo_drift = check_repo(&base_path, &repo_config, &default_branch).await;
+            let msg = match repo_drift.is_drifted() {
+                true => "drifted",
+                false => "ok",
+            };
+            progress.on_repo_success(&repo_info, msg.into());
+            repo_drift
+        });
+    }
+
+    let mut drifts = Vec::new();
+    while let Some(res) = join_set.join_next().await {
+        match res {
+            Ok(repo_drift) => drifts.push(repo_drift),
+            Err(e) => {
+                tracing::error!("Task panicked or cancelled: {}", e);
+            }
+        }
+    }
+
+    progress.on_batch_finish();
+
+    // repos on disk but not in config, compared against the whole config like `clean`
+    let config_repo_paths: Vec<PathBuf> = repo_configs
+        .iter()
+        .chain(&mgit_config.ignored_repos)
+        .filter_map(|item| item.local.as_ref())
+        .map(PathBuf::from)
+        .collect();
+    for extra_path in find_unused_repos(path, &config_repo_paths).await? {
+        let rel_path = extra_path.norm_path().display_path();
+        let extra_repo = RepoConfig {
+            local: Some(rel_path.clone()),
+            ..RepoConfig::default()
+        };
+        if !selection.is_selected(&extra_repo) {
+            continue;
+        }
+        drifts.push(RepoDrift::new(rel_path, DriftState::Extra));
+    }
+
+    drifts.retain(|d| d.is_drifted());
+    drifts.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+
+    for repo_drift in &drifts {
+        match repo_drift.state {
+            DriftState::Missing => progress.on_message(StyleMessage::drift_item(
+                &repo_drift.rel_path,
+                "missing on disk",
+                "",
+            )),
+            DriftState::Extra => progress.on_message(StyleMessage::drift_item(
+                &repo_drift.rel_path,
+                "not in config",
+                "",
+            )),
+            DriftState::Present => {
+                for (kind, detail) in &repo_drift.items {
+                    progress.on_message(StyleMessage::drift_item(
+                        &repo_drift.rel_path,
+                        kind,
+                        detail,
+                    ));
+                }
+            }
+        }
+    }
+
+    let summary = StyleMessage::drift_summary(drifts.len());
+
+    let Some(selection) = options.write.as_ref() else {
+        return Ok(summary);
+    };
+
+    // update config to match the workspace
+    let selection: Vec<String> = selection
+        .iter()
+        .map(|s| Path::new(s).norm_path().display_path())
+        .collect();
+    // write the shared config, what `.gitrepos.local` sets stays there
+    let mgit_config = MgitConfig::load_shared(config_path).ok_or(MgitError::LoadConfigFailed {
+        source: std::io::Error::other("Failed to load config"),
+    })?;
+    let shared_repos = mgit_config.repos.unwrap_or_default();
+    // edit the entries in place, so order, comments and untouched keys are kept
+    let content =
+        tokio::fs::read_to_string(config_path)
+            .await
+            .map_err(|e| MgitError::OpsError {
+                message: format!("Failed to read file .gitrepos: {}", e),
+            })?;
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .map_err(|e| MgitError::OpsError {
+            message: format!("Failed to parse file .gitrepos: {}", e),
+        })?;
+    let repo_tables = doc
+        .entry("repos")
+        .or_insert(Item::ArrayOfTables(ArrayOfTables::new()))
+        .as_array_of_tables_mut()
+        .ok_or_else(|| MgitError::OpsError {
+            message: "repos of .gitrepos must be a list of [[repos]] tables".to_string(),
+        })?;
+
+    let mut changed = 0;
+    for repo_drift in &drifts {
+        let explicit = selection.contains(&repo_drift.rel_path);
+        let selected = explicit || selection.is_empty();
+        let is_repo = |local: Option<&str>| {
+            local.is_some_and(|local| {
+                Path::new(local).norm_path().display_path() == repo_drift.rel_path
+            })
+        };
+        let is_repo_table =
+            |table: &Table| is_repo(table.get("local").and_then(|item| item.as_str()));
+
+        match repo_drift.state {
+            DriftState::Present if selected => {
+                let full_path = path.join(&repo_drift.rel_path);
+                let repo = shared_repos
+                    .iter()
+                    .find(|repo| is_repo(repo.local.as_deref()));
+                let table = repo_tables.iter_mut().find(|table| is_repo_table(table));
+                if let (Some(repo), Some(table)) = (repo, table) {
+                    let workspace_repo =
+                        workspace_repo_config(&full_path, repo, &default_branch).await;
+                    update_repo_table(table, repo, &workspace_repo);
+                    changed += 1;
+                }
+            }
+            // removing or adding repos only happens when they are named explicitly
+            DriftState::Missing if explicit => {
+                repo_tables.retain(|table| !is_repo_table(table));
+                changed += 1;
+            }
+            DriftState::Extra if explicit => {
+                let full_path = path.join(&repo_drift.rel_path);
+                let repo = RepoConfig {
+                    local: Some(repo_drift.rel_path.clone()),
+                    ..RepoConfig::default()
+                };
+                let workspace_repo = workspace_repo_config(&full_path, &repo, &None).await;
+                let mut table = Table::new();
+                update_repo_table(&mut table, &RepoConfig::default(), &workspace_repo);
+                repo_tables.push(table);
+                changed += 1;
+            }
+            _ => {}
+        }
+    }
+
+    if changed > 0 {
+        tokio::fs::write(config_path, doc.to_string())
+            .await
+            .map_err(|e| MgitError::OpsError {
+                message: format!("Failed to write file .gitrepos: {}", e),
+            })?;
+        progress.on_message(StyleMessage::update_config_succ());
+    }
+
+    Ok(summary)
+}
+
+async fn check_repo(
+    base_path: &Path,
+    repo_config: &RepoConfig,
+    default_branch: &Option<String>,
+) -> RepoDrift {
+    let rel_path = repo_config.local.clone().unwrap_or_default();
+    let full_path = base_path.join(&rel_path);
+    let rel_path = Path::new(&rel_path).norm_path().display_path();
+
+    if git::is_repository(&full_path).await.is_err() {
+        return RepoDrift::new(rel_path, DriftState::Missing);
+    }
+
+    let mut repo_drift = RepoDrift::new(rel_path, DriftState::Present);
+
+    // remote url
+    if let Some(remote) = repo_config.remote.as_ref() {
+        let current = git::find_remote_url_by_name(&full_path, "origin")
+            .await
+            .ok();
+        if !current
+            .as_deref()
+            .is_some_and(|current| git::is_same_remote_url(current, remote))
+        {
+            repo_drift.items.push((
+                "remote differs",
+                format!(
+                    "(workspace: {}, config: {})",
+                    current.as_deref().unwrap_or("none"),
+                    remote
+                ),
+            ));
+        }
+    }
+
+    // checked-out ref
+    if let Some(detail) = ref_drift(&full_path, repo_config, default_branch).await {
+        repo_drift.items.push(("ref differs", detail));
+    }
+
+    // sparse patterns
+    let current_sparse = git::get_sparse_patterns(&full_path).await;
+    let config_sparse = repo_config.sparse.clone().filter(|dirs| !dirs.is_empty());
+    if current_sparse != config_sparse {
+        let desc = |sparse: &Option<Vec<String>>| match sparse {
+            Some(dirs) => format!("[{}]", dirs.join(", ")),
+            None => "none".to_string(),
+        };
+        repo_drift.items.push((
+            "sparse differs",
+            format!(
+                "(workspace: {}, config: {})",
+                desc(&current_sparse),
+                desc(&config_sparse)
+            ),
+        ));
+    }
+
+    repo_drift
+}
+
+async fn ref_drift(
+    full_path: &Path,
+    repo_config: &RepoConfig,
+    default_branch: &Option<String>,
+) -> Option<String> {
+    let head = git::get_current_commit(full_path).await.ok();
+    let head_desc = head
+        .as_ref()
+        .map(|oid| oid[..7.min(oid.len())].to_string())
+        .unwrap_or_else(|| "none".to_string());
+
+    // priority: commit/tag/branch(default-branch)
+    if let Some(commit) = &repo_config.commit {
+        return match &head {
+            Some(oid) if oid.starts_with(commit.as_str()) => None,
+            _ => Some(format!(
+                "(workspace: {}, config commit: {})",
+                head_desc, commit
+            )),
+        };
+    }
+
+    if let Some(tag) = &repo_config.tag {
+        let tag_commit = git::get_ref_commit(full_path, format!("refs/tags/{}", tag))
+            .await
+            .ok();
+        return match (&head, &tag_commit) {
+            (Some(oid), Some(tag_oid)) if oid == tag_oid => None,
+            _ => Some(format!("(workspace: {}, config tag: {})", head_desc, tag)),
+        };
+    }
+
+    let branch = repo_config.branch.as_ref().or(default_branch.as_ref())?;
+    match git::get_current_branch(full_path).await {
+        Ok(current) if &current == branch => None,
+        Ok(current) => Some(format!(
+            "(workspace: {}, config branch: {})",
+            current, branch
+        )),
+        Err(_) => Some(format!(
+            "(workspace: detached at {}, config branch: {})",
+            head_desc, branch
+        )),
+    }
+}
+
+/// Update the keys of a `[[repos]]` table that differ between `old` and `new`
+fn update_repo_table(table: &mut Table, old: &RepoConfig, new: &RepoConfig) {
+    let strings = [
+        ("local", &old.local, &new.local),
+        ("remote", &old.remote, &new.remote),
+        ("branch", &old.branch, &new.branch),
+        ("tag", &old.tag, &new.tag),
+        ("commit", &old.commit, &new.commit),
+    ];
+    for (key, old, new) in strings {
+        if old != new {
+            set_repo_key(table, key, new.as_deref().map(Value::from));
+        }
+    }
+    if old.sparse != new.sparse {
+        let sparse = new
+            .sparse
+            .as_ref()
+            .map(|dirs| Value::Array(Array::from_iter(dirs)));
+        set_repo_key(table, "sparse", sparse);
+    }
+}
+
+fn set_repo_key(table: &mut Table, key: &str, value: Option<Value>) {
+    match value {
+        Some(value) => {
+            table.insert(key, Item::Value(value));
+        }
+        None => {
+            table.remove(key);
+        }
+    }
+}
+
+/// Describe the workspace state of a repo as a repo config, keeping `local` and `labels`
+async fn workspace_repo_config(
+    full_path: &Path,
+    repo_config: &RepoConfig,
+    default_branch: &Option<String>,
+) -> RepoConfig {
+    let mut repo_config = repo_config.to_owned();
+
+    // an equivalent url form is not a drift, keep the one in the config
+    if let Ok(remote) = git::find_remote_url_by_name(full_path, "origin").await {
+        let same = matches!(&repo_config.remote, Some(r) if git::is_same_remote_url(r, &remote));
+        if !same {
+            repo_config.remote = Some(remote);
+        }
+    }
+
+    // mgit checks out commits and tags as `commits/<sha>` and `tags/<tag>` branches
+    let branch = git::get_current_branch(full_path)
+        .await
+        .ok()
+        .filter(|b| !b.starts_with("commits/") && !b.starts_with("tags/"));
+
+    match branch {
+        Some(branch) => {
+            repo_config.branch = match default_branch {
+                Some(default) if default == &branch && repo_config.branch.is_none() => None,
+                _ => Some(branch),
+            };
+            repo_config.tag = None;
+            repo_config.commit = None;
+        }
+        None => {
+            let tags = git::get_head_tags(full_path).await.unwrap_or_default();
+            let tag = match &repo_config.tag {
+                Some(tag) if tags.contains(tag) => Some(tag.clone()),
+                Some(_) => tags.first().cloned(),
+                None => None,
+            };
+            repo_config.branch = None;
+            repo_config.commit = match tag {
+                Some(_) => None,
+                None => git::get_current_commit(full_path).await.ok(),
+            };
+            repo_config.tag = tag;
+        }
+    }
+
+    repo_config.sparse = git::get_sparse_patterns(full_path).await;
+    repo_config
+}
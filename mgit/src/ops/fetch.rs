@@ -1,22 +1,28 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use tokio::process::Command;
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
-use crate::config::{cmp_local_remote, repos_to_map_with_ignore, MgitConfig};
+use crate::config::{
+    cmp_local_remote, repos_to_map_with_ignore, MgitConfig, RepoSelection, RepoState, Workspace,
+};
 use crate::error::{
-    AcquirePermitFailedSnafu, BranchReferenceRequiredSnafu, MgitResult, NoRemoteConfiguredSnafu,
+    AcquirePermitFailedSnafu, BranchReferenceRequiredSnafu, LfsNotInstalledSnafu, MgitError,
+    MgitResult, NoRemoteConfiguredSnafu,
 };
 use crate::git;
-use crate::git::RemoteRef;
+use crate::git::{GitCapability, RemoteRef};
+use crate::utils::askpass::AskpassSession;
+use crate::utils::cmd;
 use crate::utils::cmd::{retry, DEFAULT_RETRY_COUNT, DEFAULT_RETRY_DELAY_MS};
+use crate::utils::lock::WorkspaceLock;
 use crate::utils::path::PathExtension;
 use crate::utils::progress::{Progress, RepoInfo};
+use crate::utils::stats::{PhaseTimer, RepoTiming, TimingSummary, WorkspaceStats};
 use crate::utils::style_message::StyleMessage;
-use crate::utils::{cmd, current_dir};
 
 pub struct FetchOptions {
     pub path: PathBuf,
@@ -26,9 +32,18 @@ pub struct FetchOptions {
     pub depth: Option<usize>,
     pub ignore: Option<Vec<String>>,
     pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Whether to leave LFS objects undownloaded
+    pub skip_lfs: bool,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl FetchOptions {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         path: Option<impl AsRef<Path>>,
         config_path: Option<impl AsRef<Path>>,
@@ -37,12 +52,9 @@ impl FetchOptions {
         depth: Option<usize>,
         ignore: Option<Vec<String>>,
         labels: Option<Vec<String>>,
+        skip_lfs: Option<bool>,
     ) -> Self {
-        let path = match path {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => current_dir(),
-        };
-        let config_path = config_path.map_or(path.join(".gitrepos"), |p| p.as_ref().to_path_buf());
+        let (path, config_path) = Workspace::resolve(path, config_path);
         Self {
             path,
             config_path,
@@ -51,6 +63,10 @@ impl FetchOptions {
             depth,
             ignore,
             labels,
+            skip_lfs: skip_lfs.unwrap_or(false),
+            only: None,
+            states: None,
+            wait: false,
         }
     }
 }
@@ -65,7 +81,9 @@ pub async fn fetch_repos(
     let thread_count = options.thread_count;
     let silent = options.silent;
     let depth = options.depth;
+    let skip_lfs = options.skip_lfs;
     let ignore = options.ignore.as_ref();
+    let _askpass = AskpassSession::begin();
 
     progress.on_message(StyleMessage::ops_start("fetch repos", path));
 
@@ -75,11 +93,23 @@ pub async fn fetch_repos(
         });
     }
 
+    let _lock = WorkspaceLock::acquire(path, "fetch", options.wait, &progress).await?;
+
     let mgit_config =
         MgitConfig::load(config_path).ok_or(crate::error::MgitError::LoadConfigFailed {
             source: std::io::Error::other("Failed to load config"),
         })?;
 
+    // fail early if the git in use is too old
+    let mut capabilities = vec![
+        GitCapability::ShowCurrentBranch,
+        GitCapability::FetchRecurseSubmodules,
+    ];
+    if uses_partial_clone(&mgit_config) {
+        capabilities.push(GitCapability::PartialClone);
+    }
+    git::require_capabilities(&capabilities).await?;
+
     let repo_configs = if let Some(repos) = mgit_config.repos {
         repos
     } else {
@@ -87,9 +117,19 @@ pub async fn fetch_repos(
     };
 
     let default_branch = mgit_config.default_branch;
-    let repos_map = repos_to_map_with_ignore(repo_configs, ignore, options.labels.as_ref());
+    let default_filter = mgit_config.default_filter;
+    let selection = RepoSelection::new(path, ignore)?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &default_branch, &progress)
+        .await;
+    let mut stats = WorkspaceStats::load(path);
+    let repos = stats.slowest_first("fetch", repos_map);
 
-    progress.on_batch_start(repos_map.len());
+    progress.on_batch_start(repos.len());
 
     let semaphore = Arc::new(Semaphore::new(thread_count));
     let mut join_set = JoinSet::new();
@@ -100,7 +140,8 @@ pub async fn fetch_repos(
     let base_path = path.clone();
     let default_branch = Arc::new(default_branch);
 
-    for (id, repo_config) in repos_map {
+    let batch_start = Instant::now();
+    for (id, repo_config) in repos {
         let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
             AcquirePermitFailedSnafu {
                 message: "Failed to acquire semaphore permit for parallel execution".to_string(),
@@ -111,27 +152,44 @@ pub async fn fetch_repos(
         let progress = progress.clone();
         let base_path = base_path.clone();
         let default_branch = Arc::clone(&default_branch);
-        let repo_config = repo_config.clone();
+        let mut repo_config = repo_config.clone();
+        if repo_config.filter.is_none() {
+            repo_config.filter.clone_from(&default_filter);
+        }
 
         join_set.spawn(async move {
             let _permit = permit; // Hold permit until task finishes
             let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
             let on_repo_update = RepoInfo::new(id, index, &repo_config);
+            let repo_start = Instant::now();
+            let timer = PhaseTimer::default();
 
             progress.on_repo_start(&on_repo_update, "waiting...".into());
 
             // execute fetch command
-            let exec_res = inner_exec(&base_path, &on_repo_update, depth.as_ref(), &progress).await;
+            let exec_res = inner_exec(
+                &base_path,
+                &on_repo_update,
+                depth.as_ref(),
+                skip_lfs,
+                &default_branch,
+                &timer,
+                &progress,
+            )
+            .await;
 
-            match exec_res {
-                Ok(_) => {
-                    let msg = if silent {
+            let res = match exec_res {
+                Ok(fallback_url) => {
+                    let mut msg = if silent {
                         StyleMessage::new()
                     } else {
                         cmp_local_remote(&base_path, &repo_config, &default_branch, false)
                             .await
                             .unwrap_or_else(|_| StyleMessage::new())
                     };
+                    if let Some(url) = fallback_url {
+                        msg = msg.join(StyleMessage::git_fetch_fallback(url));
+                    }
                     progress.on_repo_success(&on_repo_update, msg);
                     Ok(())
                 }
@@ -146,19 +204,35 @@ pub async fn fetch_repos(
                         &e,
                     ))
                 }
-            }
+            };
+            let timing = RepoTiming::new(on_repo_update.rel_path(), repo_start.elapsed(), &timer);
+            (timing, res)
         });
     }
 
     let mut errors = Vec::new();
+    let mut timings = Vec::new();
     while let Some(res) = join_set.join_next().await {
-        if let Ok(Err(e)) = res {
-            errors.push(e);
+        if let Ok((timing, res)) = res {
+            timings.push(timing);
+            if let Err(e) = res {
+                errors.push(e);
+            }
         }
     }
 
     progress.on_batch_finish();
 
+    if let Some(summary) = TimingSummary::new(batch_start.elapsed(), &timings) {
+        if !silent {
+            progress.on_message(StyleMessage::timing_summary(&summary));
+        }
+        stats.record("fetch", &summary, &timings);
+        if let Err(e) = stats.save(path) {
+            tracing::warn!(error = %e, "stats_save_failed");
+        }
+    }
+
     if errors.is_empty() {
         Ok(StyleMessage::ops_success("fetch"))
     } else {
@@ -181,8 +255,11 @@ async fn inner_exec(
     input_path: impl AsRef<Path>,
     on_repo_update: &RepoInfo<'_>,
     depth: Option<&usize>,
+    skip_lfs: bool,
+    default_branch: &Option<String>,
+    timer: &PhaseTimer,
     progress: &impl Progress,
-) -> MgitResult<()> {
+) -> MgitResult<Option<String>> {
     let full_path = input_path.as_ref().join(on_repo_update.rel_path());
     let remote_url = on_repo_update.repo_config.remote.as_ref().ok_or_else(|| {
         NoRemoteConfiguredSnafu {
@@ -192,15 +269,39 @@ async fn inner_exec(
     })?;
 
     git::update_remote_url(&full_path, remote_url).await?;
-    exec_fetch(input_path, on_repo_update, depth, progress).await
+    let fallback_url = timer
+        .time(
+            "fetch",
+            exec_fetch(&input_path, on_repo_update, depth, progress),
+        )
+        .await?;
+
+    if !skip_lfs {
+        let mut repo_config = on_repo_update.repo_config.clone();
+        if repo_config.branch.is_none() {
+            repo_config.branch.clone_from(default_branch);
+        }
+        let repo_info = RepoInfo::new(on_repo_update.id, on_repo_update.index, &repo_config);
+        timer
+            .time("lfs", exec_lfs_fetch(input_path, &repo_info, progress))
+            .await?;
+    }
+    Ok(fallback_url)
 }
 
+/// Fetch a repo from its remote, falling back to the `fallbacks` mirrors of the
+/// repo config when the remote is unreachable
+///
+/// Mirrors are fetched into the same remote-tracking refs and the remote url is
+/// left untouched, so pushes still go to the remote. Returns the mirror url when
+/// one was used. Retries are for the remote alone: with mirrors, a remote that
+/// can't be reached is not retried and each mirror is tried once.
 pub async fn exec_fetch(
     input_path: impl AsRef<Path>,
     on_repo_update: &RepoInfo<'_>,
     depth: Option<&usize>,
     progress: &impl Progress,
-) -> MgitResult<()> {
+) -> MgitResult<Option<String>> {
     let full_path = input_path.as_ref().join(on_repo_update.rel_path());
 
     let remote_name: String = on_repo_update
@@ -208,8 +309,9 @@ pub async fn exec_fetch(
         .get_remote_name(full_path.as_path())
         .await?;
 
-    // Build args without using String -> &str conversions where possible
-    let mut args: Vec<String> = vec!["fetch".to_string(), remote_name];
+    // refs to fetch from the remote, and the same refs fetched from a mirror url
+    let mut refs: Vec<String> = Vec::new();
+    let mut fallback_refs: Vec<String> = Vec::new();
 
     if let Some(depth) = depth {
         let remote_ref = on_repo_update
@@ -218,12 +320,14 @@ pub async fn exec_fetch(
             .await?;
         match remote_ref {
             RemoteRef::Commit(commit) => {
-                args.push(commit);
+                refs.push(commit);
+                fallback_refs.clone_from(&refs);
             }
             RemoteRef::Tag(tag) => {
-                args.push("tag".to_string());
-                args.push(tag);
-                args.push("--no-tags".to_string());
+                refs.push("tag".to_string());
+                refs.push(tag);
+                refs.push("--no-tags".to_string());
+                fallback_refs.clone_from(&refs);
             }
             RemoteRef::Branch(_) => {
                 let branch = on_repo_update.repo_config.branch.as_ref().ok_or_else(|| {
@@ -232,26 +336,181 @@ pub async fn exec_fetch(
                     }
                     .build()
                 })?;
-                args.push(branch.clone());
+                refs.push(branch.clone());
+                fallback_refs.push(format!(
+                    "+refs/heads/{}:refs/remotes/{}/{}",
+                    branch, remote_name, branch
+                ));
             }
         };
 
-        args.push("--depth".to_string());
-        args.push(depth.to_string());
+        for args in [&mut refs, &mut fallback_refs] {
+            args.push("--depth".to_string());
+            args.push(depth.to_string());
+        }
+    } else {
+        fallback_refs.push(format!("+refs/heads/*:refs/remotes/{}/*", remote_name));
+    }
+
+    // the remote turns into a promisor remote, so later fetches keep the filter and
+    // blobs are downloaded on demand; mirrors are fetched in full
+    if let Some(filter) = on_repo_update.repo_config.partial_clone_filter() {
+        git::set_partial_clone_filter(&full_path, &remote_name, filter).await?;
+        refs.push(format!("--filter={}", filter));
+    }
+
+    let fetch = |source: String, refs: &Vec<String>, attempts: usize| {
+        // Build args without using String -> &str conversions where possible
+        let mut args: Vec<String> = vec!["fetch".to_string(), source];
+        args.extend(refs.iter().cloned());
+        args.push("--prune".to_string());
+        args.push("--recurse-submodules=on-demand".to_string());
+        args.push("--progress".to_string());
+
+        let full_path = full_path.clone();
+        retry(
+            attempts,
+            Duration::from_millis(DEFAULT_RETRY_DELAY_MS),
+            move || {
+                let args = args.clone();
+                let full_path = full_path.clone();
+                async move {
+                    let mut command = Command::new(git::git_program());
+                    command.args(&args).current_dir(&full_path);
+                    cmd::exec_cmd_with_progress(on_repo_update, &mut command, progress).await
+                }
+            },
+        )
+    };
+
+    let fallbacks = on_repo_update
+        .repo_config
+        .fallbacks
+        .as_deref()
+        .unwrap_or_default();
+    if fallbacks.is_empty() {
+        return fetch(remote_name, &refs, DEFAULT_RETRY_COUNT)
+            .await
+            .map(|_| None);
+    }
+
+    // an unreachable remote goes to the mirrors right away instead of after every retry
+    let err = match fetch(remote_name.clone(), &refs, 1).await {
+        Ok(_) => return Ok(None),
+        Err(e) => e,
+    };
+    if !is_network_error(&err) {
+        tokio::time::sleep(Duration::from_millis(DEFAULT_RETRY_DELAY_MS)).await;
+        return fetch(remote_name, &refs, DEFAULT_RETRY_COUNT - 1)
+            .await
+            .map(|_| None);
+    }
+
+    for url in fallbacks {
+        progress.on_repo_update(
+            on_repo_update,
+            format!("fetch from fallback {}...", url).into(),
+        );
+        match fetch(git::rewrite_url(url), &fallback_refs, 1).await {
+            Ok(_) => {
+                tracing::warn!(
+                    repo = on_repo_update.rel_path(),
+                    url = url.as_str(),
+                    error = %err,
+                    "fetch_fallback_used"
+                );
+                return Ok(Some(url.clone()));
+            }
+            Err(e) => {
+                tracing::warn!(
+                    repo = on_repo_update.rel_path(),
+                    url = url.as_str(),
+                    error = %e,
+                    "fetch_fallback_failed"
+                );
+            }
+        }
     }
 
-    args.push("--prune".to_string());
-    args.push("--recurse-submodules=on-demand".to_string());
-    args.push("--progress".to_string());
-
-    retry(
-        DEFAULT_RETRY_COUNT,
-        Duration::from_millis(DEFAULT_RETRY_DELAY_MS),
-        || async {
-            let mut command = Command::new("git");
-            command.args(&args).current_dir(&full_path);
-            cmd::exec_cmd_with_progress(on_repo_update, &mut command, progress).await
-        },
-    )
-    .await
+    Err(err)
+}
+
+/// Download the LFS objects of the configured ref, limited to the sparse patterns
+///
+/// Returns whether the repo uses LFS, set by the `lfs` key of the repo config or
+/// detected from the `.gitattributes` of the ref. Without git-lfs a detected
+/// repo keeps its pointer files with a warning, only `lfs = true` fails.
+pub async fn exec_lfs_fetch(
+    input_path: impl AsRef<Path>,
+    repo_info: &RepoInfo<'_>,
+    progress: &impl Progress,
+) -> MgitResult<bool> {
+    let full_path = input_path.as_ref().join(repo_info.rel_path());
+    let repo_config = repo_info.repo_config;
+    if repo_config.lfs == Some(false) {
+        return Ok(false);
+    }
+
+    let remote_ref = repo_config.get_remote_ref(&full_path).await?;
+    let remote_ref_str = match remote_ref {
+        RemoteRef::Commit(r) | RemoteRef::Tag(r) | RemoteRef::Branch(r) => r,
+    };
+    if repo_config.lfs != Some(true) && !git::uses_lfs(&full_path, &remote_ref_str).await {
+        return Ok(false);
+    }
+    if !git::is_lfs_installed().await {
+        // detected repos keep working without LFS, only a repo asking for it fails
+        if repo_config.lfs == Some(true) {
+            return Err(LfsNotInstalledSnafu { path: full_path }.build());
+        }
+        tracing::warn!(repo = repo_info.rel_path(), "lfs_not_installed");
+        progress.on_message(StyleMessage::lfs_not_installed(repo_info.rel_path()));
+        return Ok(false);
+    }
+
+    progress.on_repo_update(repo_info, "lfs fetch...".into());
+    git::lfs_install(&full_path).await?;
+    git::lfs_set_fetch_filters(&full_path, repo_config.sparse.as_deref()).await?;
+    let remote_name = repo_config.get_remote_name(&full_path).await?;
+    git::lfs_fetch(&full_path, remote_name, remote_ref_str).await?;
+    Ok(true)
+}
+
+/// Whether any repo of the config is a partial clone
+pub(crate) fn uses_partial_clone(mgit_config: &MgitConfig) -> bool {
+    let has_filter =
+        |filter: &Option<String>| filter.as_ref().is_some_and(|f| !f.trim().is_empty());
+    has_filter(&mgit_config.default_filter)
+        || mgit_config
+            .repos
+            .iter()
+            .flatten()
+            .any(|repo| has_filter(&repo.filter))
+}
+
+/// Whether a failed git command could not reach the remote at all
+fn is_network_error(err: &MgitError) -> bool {
+    const PATTERNS: &[&str] = &[
+        "could not resolve host",
+        "could not resolve hostname",
+        "failed to connect",
+        "connection refused",
+        "connection timed out",
+        "operation timed out",
+        "network is unreachable",
+        "no route to host",
+        "connection reset",
+        "could not read from remote repository",
+        "the remote end hung up unexpectedly",
+        "early eof",
+        "ssl_connect",
+        "gnutls_handshake",
+    ];
+    match err {
+        MgitError::GitCommandError { output, .. } => {
+            let output = output.to_lowercase();
+            PATTERNS.iter().any(|pattern| output.contains(pattern))
+        }
+        _ => false,
+    }
 }
@@ -12,6 +12,9 @@ use crate::utils::style_message::StyleMessage;
 pub struct InitOptions {
     pub path: PathBuf,
     pub force: bool,
+    pub ignore: Option<Vec<String>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl InitOptions {
@@ -23,6 +26,8 @@ impl InitOptions {
         Self {
             path,
             force: force.unwrap_or(true),
+            ignore: None,
+            wait: false,
         }
     }
 }
@@ -36,15 +41,16 @@ pub async fn init_repo(options: InitOptions, progress: impl Progress) -> MgitRes
 
     progress.on_message(StyleMessage::ops_start("init", path));
 
-    snapshot_repo(
-        SnapshotOptions::new(
+    // the snapshot locks the workspace
+    let snapshot_options = SnapshotOptions {
+        wait: options.wait,
+        ..SnapshotOptions::new(
             Some(path.to_path_buf()),
             Some(config_file),
             Some(force),
             Some(snapshot_type),
-            None,
-        ),
-        progress,
-    )
-    .await
+            options.ignore,
+        )
+    };
+    snapshot_repo(snapshot_options, progress).await
 }
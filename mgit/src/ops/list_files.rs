@@ -1,16 +1,17 @@
 use std::path::{Path, PathBuf};
 
-use crate::config::MgitConfig;
+use crate::config::{MgitConfig, RepoSelection};
 use crate::error::MgitResult;
 use crate::git;
 use crate::ops::CleanOptions;
-use crate::utils::label;
 use crate::utils::path::PathExtension;
 
 pub struct ListFilesOptions {
     pub path: PathBuf,
     pub config_path: PathBuf,
     pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
 }
 
 impl ListFilesOptions {
@@ -24,6 +25,7 @@ impl ListFilesOptions {
             path: clean_options.path,
             config_path: clean_options.config_path,
             labels: clean_options.labels,
+            only: None,
         }
     }
 }
@@ -52,13 +54,15 @@ pub async fn list_files(options: ListFilesOptions) -> MgitResult<Vec<String>> {
         });
     };
 
-    let Some(mut repo_configs) = mgit_config.repos else {
+    let Some(repo_configs) = mgit_config.repos else {
         return Ok(vec![]);
     };
 
-    if let Some(labels) = options.labels {
-        repo_configs = label::filter(&repo_configs, &labels).cloned().collect();
-    }
+    let selection = RepoSelection::new(path, None)?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?;
+    selection.check(&repo_configs)?;
+    let repo_configs = selection.filter(&repo_configs).cloned().collect::<Vec<_>>();
 
     let mut files = Vec::new();
 
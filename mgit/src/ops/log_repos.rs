@@ -8,17 +8,23 @@ use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
 use crate::config::MgitConfig;
+use crate::config::Workspace;
+use crate::config::{repos_to_map_with_ignore, RepoSelection, RepoState};
 use crate::error::{MgitError, MgitResult};
 use crate::git::log_current;
 use crate::utils::path::PathExtension;
 use crate::utils::progress::Progress;
-use crate::utils::{current_dir, label, StyleMessage};
+use crate::utils::StyleMessage;
 
 pub struct LogReposOptions {
     pub path: PathBuf,
     pub config_path: PathBuf,
     pub thread_count: usize,
     pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
 }
 
 impl LogReposOptions {
@@ -28,25 +34,25 @@ impl LogReposOptions {
         thread_count: Option<usize>,
         labels: Option<Vec<String>>,
     ) -> Self {
-        let path = match path {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => current_dir(),
-        };
-        let config_path = config_path.map_or(path.join(".gitrepos"), |p| p.as_ref().to_path_buf());
+        let (path, config_path) = Workspace::resolve(path, config_path);
         Self {
             path,
             config_path,
             thread_count: thread_count.unwrap_or(4),
             labels,
+            only: None,
+            states: None,
         }
     }
 
-    pub fn validate(self) -> MgitResult<(PathBuf, MgitConfig, usize, Option<Vec<String>>)> {
+    pub fn validate(self) -> MgitResult<(PathBuf, MgitConfig, usize, RepoSelection)> {
         let LogReposOptions {
             path,
             config_path,
             thread_count,
             labels,
+            only,
+            states,
         } = self;
 
         // if directory doesn't exist, return
@@ -68,7 +74,11 @@ impl LogReposOptions {
             });
         };
 
-        Ok((path, mgit_config, thread_count, labels))
+        let selection = RepoSelection::new(&path, None)?
+            .labels(labels.as_ref())
+            .only(only.as_ref())?
+            .states(states.as_ref());
+        Ok((path, mgit_config, thread_count, selection))
     }
 }
 
@@ -98,21 +108,28 @@ pub async fn log_repos(
     options: LogReposOptions,
     progress: impl Progress,
 ) -> MgitResult<Vec<MgitResult<RepoLog>>> {
-    let (path, mgit_config, thread_count, labels) = options.validate()?;
+    let (path, mgit_config, thread_count, selection) = options.validate()?;
 
     progress.on_message(StyleMessage::ops_start("log repos", &path));
 
-    let mut repo_configs = mgit_config.repos.unwrap_or_default();
-
-    if let Some(labels) = labels {
-        repo_configs = label::filter(&repo_configs, &labels).cloned().collect();
-    }
+    let repo_configs = mgit_config.repos.unwrap_or_default();
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(
+            &path,
+            &mut repos_map,
+            &mgit_config.default_branch,
+            &progress,
+        )
+        .await;
+    let mut repo_configs = repos_map.into_iter().collect::<Vec<_>>();
+    repo_configs.sort_by_key(|(id, _)| *id);
 
     let semaphore = Arc::new(Semaphore::new(thread_count));
     let mut join_set = JoinSet::new();
     let base_path = path.clone();
 
-    for repo_config in repo_configs {
+    for (_, repo_config) in repo_configs {
         let permit =
             Arc::clone(&semaphore)
                 .acquire_owned()
@@ -1,19 +1,30 @@
+pub use cache::{cache_du, cache_gc, cache_prune, cache_update, CacheOptions, CachePruneOptions};
 pub use clean::{clean_repo, CleanOptions};
 pub use del_branch::{del_remote_branch, DelBranchOptions};
-pub use fetch::{exec_fetch, fetch_repos, FetchOptions};
+pub use doctor::{doctor, DoctorOptions};
+pub use drift::{drift, DriftOptions};
+pub use fetch::{exec_fetch, exec_lfs_fetch, fetch_repos, FetchOptions};
 pub use init::{init_repo, InitOptions};
 pub use list_files::{list_files, ListFilesOptions};
 pub use log_repos::*;
 pub use new_branch::{new_remote_branch, NewBranchOptions};
 pub use new_tag::{new_tag, NewTagOptions};
 pub use snapshot::{snapshot_repo, SnapshotOptions, SnapshotType};
+pub use stash::{stash_repos, StashAction, StashOptions};
 pub use sync::{sync_repo, SyncOptions};
 pub use track::{set_tracking_remote_branch, track, TrackOptions};
+pub use worktree::{
+    worktree_add, worktree_list, worktree_remove, WorktreeAddOptions, WorktreeListOptions,
+    WorktreeRemoveOptions,
+};
 
 pub use crate::utils::current_dir;
 
+mod cache;
 mod clean;
 mod del_branch;
+mod doctor;
+mod drift;
 mod fetch;
 mod init;
 mod list_files;
@@ -21,5 +32,7 @@ mod log_repos;
 mod new_branch;
 mod new_tag;
 mod snapshot;
+mod stash;
 mod sync;
 mod track;
+mod worktree;
@@ -1,9 +1,12 @@
 use std::path::{Path, PathBuf};
 
 use crate::config::MgitConfig;
+use crate::config::Workspace;
+use crate::config::{RepoSelection, RepoState};
 use crate::error::MgitResult;
 use crate::git;
-use crate::utils::current_dir;
+use crate::utils::askpass::AskpassSession;
+use crate::utils::lock::WorkspaceLock;
 use crate::utils::path::PathExtension;
 use crate::utils::progress::Progress;
 use crate::utils::StyleMessage;
@@ -15,6 +18,12 @@ pub struct NewBranchOptions {
     pub new_branch: String,
     pub force: bool,
     pub ignore: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl NewBranchOptions {
@@ -26,11 +35,7 @@ impl NewBranchOptions {
         force: bool,
         ignore: Option<Vec<String>>,
     ) -> Self {
-        let path = match path {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => current_dir(),
-        };
-        let config_path = config_path.map_or(path.join(".gitrepos"), |p| p.as_ref().to_path_buf());
+        let (path, config_path) = Workspace::resolve(path, config_path);
         Self {
             path,
             config_path,
@@ -38,6 +43,9 @@ impl NewBranchOptions {
             new_branch,
             force,
             ignore,
+            only: None,
+            states: None,
+            wait: false,
         }
     }
 }
@@ -52,7 +60,7 @@ pub async fn new_remote_branch(
     let new_branch = options.new_branch;
     let new_config_path = options.new_config_path;
     let force = options.force;
-    let mut ignore = options.ignore.unwrap_or_default();
+    let _askpass = AskpassSession::begin();
 
     progress.on_message(StyleMessage::new().plain_text("New remote branch:"));
     // if directory doesn't exist, finsh clean
@@ -66,29 +74,39 @@ pub async fn new_remote_branch(
             path: config_path.clone(),
         });
     }
+    let _lock = WorkspaceLock::acquire(path, "new-remote-branch", options.wait, &progress).await?;
 
     // load config file(like .gitrepos)
-    let Some(mut mgit_config) = MgitConfig::load(config_path) else {
+    let Some(mgit_config) = MgitConfig::load(config_path) else {
         return Err(crate::error::MgitError::LoadConfigFailed {
             source: std::io::Error::other("Failed to load config"),
         });
     };
 
-    let repo_configs = if let Some(repos) = mgit_config.repos.as_mut() {
+    let repo_configs = if let Some(repos) = mgit_config.repos.as_ref() {
         repos
     } else {
         return Ok(StyleMessage::new().plain_text("No repos to create new remote branch"));
     };
 
-    if ignore.contains(&".".to_string()) {
-        ignore.push("".to_string());
-    }
+    let selection = RepoSelection::new(path, options.ignore.as_ref())?
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    selection.check(repo_configs)?;
+    let mut matched = selection.matching(repo_configs);
+    selection
+        .retain_states(path, &mut matched, &mgit_config.default_branch, &progress)
+        .await;
 
     let mut errors = Vec::new();
-    for repo_config in repo_configs.iter_mut() {
+    let mut branched = Vec::new();
+    for (id, repo_config) in repo_configs.iter().enumerate() {
         let Some(local) = repo_config.local.as_ref() else {
             continue;
         };
+        if !matched.contains_key(&id) {
+            continue;
+        }
 
         // only support new branch from exist branch
         if repo_config.branch.is_none() {
@@ -100,7 +118,7 @@ pub async fn new_remote_branch(
             continue;
         }
 
-        if ignore.contains(local) {
+        if selection.is_ignored(local) {
             let rel_path_display = Path::new(local).display_path();
             progress.on_message(
                 StyleMessage::new().plain_text(format!("{}: ignored", rel_path_display)),
@@ -138,7 +156,7 @@ pub async fn new_remote_branch(
             continue;
         }
 
-        repo_config.branch = Some(new_branch.clone());
+        branched.push(rel_path.norm_path());
         let rel_path_display = Path::new(rel_path).display_path();
 
         let msg = StyleMessage::git_new_branch(rel_path_display, &new_branch);
@@ -161,6 +179,22 @@ pub async fn new_remote_branch(
     }
 
     if let Some(new_config_path) = new_config_path {
+        // start from the shared config, what `.gitrepos.local` sets stays there
+        let mut mgit_config = MgitConfig::load_shared(config_path).ok_or(
+            crate::error::MgitError::LoadConfigFailed {
+                source: std::io::Error::other("Failed to load config"),
+            },
+        )?;
+        for repo_config in mgit_config.repos.iter_mut().flatten() {
+            if repo_config
+                .local
+                .as_ref()
+                .is_some_and(|local| branched.contains(&local.norm_path()))
+            {
+                repo_config.branch = Some(new_branch.clone());
+            }
+        }
+
         use crate::config::serialize_config;
         let toml_string = serialize_config(&mgit_config);
         tokio::fs::write(new_config_path, toml_string)
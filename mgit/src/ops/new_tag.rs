@@ -1,9 +1,12 @@
 use std::path::{Path, PathBuf};
 
 use crate::config::MgitConfig;
+use crate::config::Workspace;
+use crate::config::{RepoSelection, RepoState};
 use crate::error::MgitResult;
 use crate::git;
-use crate::utils::current_dir;
+use crate::utils::askpass::AskpassSession;
+use crate::utils::lock::WorkspaceLock;
 use crate::utils::path::PathExtension;
 use crate::utils::progress::Progress;
 use crate::utils::StyleMessage;
@@ -15,6 +18,12 @@ pub struct NewTagOptions {
     pub new_tag: String,
     pub push: bool,
     pub ignore: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl NewTagOptions {
@@ -25,17 +34,16 @@ impl NewTagOptions {
         push: bool,
         ignore: Option<Vec<String>>,
     ) -> Self {
-        let path = match path {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => current_dir(),
-        };
-        let config_path = config_path.map_or(path.join(".gitrepos"), |p| p.as_ref().to_path_buf());
+        let (path, config_path) = Workspace::resolve(path, config_path);
         Self {
             path,
             config_path,
             new_tag,
             push,
             ignore,
+            only: None,
+            states: None,
+            wait: false,
         }
     }
 }
@@ -46,7 +54,7 @@ pub async fn new_tag(options: NewTagOptions, progress: impl Progress) -> MgitRes
     let config_path = &options.config_path;
     let new_tag = options.new_tag;
     let push = options.push;
-    let mut ignore = options.ignore.unwrap_or_default();
+    let _askpass = AskpassSession::begin();
 
     progress.on_message(StyleMessage::new().plain_text("New tag:"));
     // if directory doesn't exist, finsh clean
@@ -60,6 +68,7 @@ pub async fn new_tag(options: NewTagOptions, progress: impl Progress) -> MgitRes
             path: config_path.clone(),
         });
     }
+    let _lock = WorkspaceLock::acquire(path, "new-tag", options.wait, &progress).await?;
 
     // load config file(like .gitrepos)
     let Some(mut mgit_config) = MgitConfig::load(config_path) else {
@@ -74,17 +83,25 @@ pub async fn new_tag(options: NewTagOptions, progress: impl Progress) -> MgitRes
         return Ok(StyleMessage::new().plain_text("No repos to create new remote branch"));
     };
 
-    if ignore.contains(&".".to_string()) {
-        ignore.push("".to_string());
-    }
+    let selection = RepoSelection::new(path, options.ignore.as_ref())?
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    selection.check(repo_configs)?;
+    let mut matched = selection.matching(repo_configs);
+    selection
+        .retain_states(path, &mut matched, &mgit_config.default_branch, &progress)
+        .await;
 
     let mut errors = Vec::new();
-    for repo_config in repo_configs.iter_mut() {
+    for (id, repo_config) in repo_configs.iter_mut().enumerate() {
         let Some(local) = repo_config.local.as_ref() else {
             continue;
         };
+        if !matched.contains_key(&id) {
+            continue;
+        }
 
-        if ignore.contains(local) {
+        if selection.is_ignored(local) {
             let rel_path_display = Path::new(local).display_path();
             progress.on_message(
                 StyleMessage::new().plain_text(format!("{}: ignored", rel_path_display)),
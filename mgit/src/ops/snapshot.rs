@@ -1,15 +1,19 @@
 use globset::GlobBuilder;
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use crate::config::LocalConfig;
 use crate::config::MgitConfig;
 use crate::config::RepoConfig;
+use crate::config::RepoIgnore;
 use crate::error::MgitError;
 use crate::error::MgitResult;
 use crate::git;
 
 use crate::utils::current_dir;
+use crate::utils::lock::WorkspaceLock;
 use crate::utils::path::PathExtension;
 use crate::utils::progress::Progress;
 use crate::utils::style_message::StyleMessage;
@@ -26,6 +30,8 @@ pub struct SnapshotOptions {
     pub force: bool,
     pub snapshot_type: SnapshotType,
     pub ignore: Option<Vec<String>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl SnapshotOptions {
@@ -47,6 +53,7 @@ impl SnapshotOptions {
             force: force.unwrap_or(false),
             snapshot_type: snapshot_type.unwrap_or(SnapshotType::Commit),
             ignore,
+            wait: false,
         }
     }
 }
@@ -60,7 +67,7 @@ pub async fn snapshot_repo(
     let config_path = &options.config_path;
     let force = options.force;
     let snapshot_type = &options.snapshot_type;
-    let ignore = options.ignore.clone(); // Clone for closure
+    let ignore = RepoIgnore::load(path, options.ignore.as_ref())?;
 
     progress.on_message(StyleMessage::ops_start("take snapshot", path));
 
@@ -74,14 +81,36 @@ pub async fn snapshot_repo(
         return Err(crate::error::MgitError::DirAlreadyInited { path: path.clone() });
     }
 
+    let _lock = WorkspaceLock::acquire(path, "snapshot", options.wait, &progress).await?;
+
+    // with --force, keep the url form and fallbacks of the replaced config for equivalent remotes,
+    // and the lfs and submodules settings of the same path
+    let previous_repos: HashMap<String, RepoConfig> = MgitConfig::load_shared(config_path)
+        .and_then(|config| config.repos)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|repo| Some((repo.local.as_ref()?.norm_path().display_path(), repo)))
+        .collect();
+
+    // private repos added by `.gitrepos.local` stay out of the shared config
+    let local_repos: HashSet<String> = LocalConfig::load(config_path)
+        .and_then(|local_config| local_config.repos)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|repo| Some(repo.local?.norm_path().display_path()))
+        .filter(|local| !previous_repos.contains_key(local))
+        .collect();
+
     let mut mgit_config = MgitConfig {
         version: None,
         default_branch: Some(String::from("develop")),
         default_remote: None,
+        default_filter: None,
         repos: None,
+        ignored_repos: Vec::new(),
     };
 
-    // search for git repos and create .gitrepos file
+    // search for git repos and create .gitrepos file, `.git` is a file in linked worktrees
     let glob = GlobBuilder::new("**/.git")
         .literal_separator(true)
         .build()
@@ -133,6 +162,14 @@ pub async fn snapshot_repo(
         message: format!("Failed to walk directory: {}", e),
     })?;
 
+    // submodule checkouts come back with the submodule phase of sync of their parent repo
+    let (submodule_paths, repos): (Vec<PathBuf>, Vec<PathBuf>) =
+        repos.iter().cloned().partition(|pb| {
+            repos.iter().any(|parent| {
+                pb != parent && pb.starts_with(parent) && git::is_submodule_of(pb, parent)
+            })
+        });
+
     let mut final_repos: Vec<RepoConfig> = Vec::new();
 
     for pb in repos {
@@ -143,7 +180,10 @@ pub async fn snapshot_repo(
         let norm_str = norm_path.display_path();
 
         // ignore specified path
-        if matches!(&ignore, Some(paths) if paths.contains(&norm_str)) {
+        if ignore.is_ignored(&norm_str) {
+            continue;
+        }
+        if local_repos.contains(&norm_str) {
             continue;
         }
 
@@ -154,7 +194,18 @@ pub async fn snapshot_repo(
         }
 
         // get remote
-        let remote = git::find_remote_url_by_name(&pb, "origin").await.ok();
+        let mut remote = git::find_remote_url_by_name(&pb, "origin").await.ok();
+        let mut fallbacks = None;
+        let previous = previous_repos.get(&norm_str);
+        let lfs = previous.and_then(|previous| previous.lfs);
+        let submodules = previous.and_then(|previous| previous.submodules);
+        let alias = previous.and_then(|previous| previous.alias.clone());
+        if let (Some(previous), Some(current)) = (previous, &remote) {
+            if matches!(&previous.remote, Some(r) if git::is_same_remote_url(r, current)) {
+                remote.clone_from(&previous.remote);
+                fallbacks.clone_from(&previous.fallbacks);
+            }
+        }
         let mut commit: Option<String> = None;
         let mut branch: Option<String> = None;
 
@@ -173,26 +224,32 @@ pub async fn snapshot_repo(
             }
         }
 
-        let sparse = match git::sparse_checkout_list(pb.as_path()).await {
-            Err(_) => None,
-            Ok(content) if content.trim().is_empty() => None,
-            Ok(content) => {
-                let list: Vec<_> = content.trim().lines().map(|s| s.to_string()).collect();
-                Some(list)
-            }
-        };
+        let sparse = git::get_sparse_patterns(pb.as_path()).await;
+        let filter = git::get_partial_clone_filter(pb.as_path(), "origin").await;
 
         let repo_config = RepoConfig {
             local: Some(norm_str.clone()),
+            alias,
             remote,
+            fallbacks,
             branch,
             tag: None,
             commit,
             sparse,
+            filter,
+            lfs,
+            submodules,
             labels: None,
         };
         final_repos.push(repo_config);
         progress.on_message(StyleMessage::new().plain_text(format!("  + {}", norm_str)));
+        for submodule in submodule_paths.iter().filter(|sub| sub.starts_with(&pb)) {
+            let sub_path = submodule.strip_prefix(&pb).unwrap_or(submodule);
+            progress.on_message(
+                StyleMessage::new()
+                    .plain_text(format!("      submodule {}", sub_path.display_path())),
+            );
+        }
     }
 
     final_repos.sort_by(|a, b| {
@@ -0,0 +1,296 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::{repos_to_map_with_ignore, MgitConfig, RepoSelection, RepoState, Workspace};
+use crate::error::{AcquirePermitFailedSnafu, MgitError, MgitResult};
+use crate::git;
+use crate::utils::lock::WorkspaceLock;
+use crate::utils::path::PathExtension;
+use crate::utils::progress::{Progress, RepoInfo};
+use crate::utils::style_message::StyleMessage;
+
+/// What to do with the stashes mgit created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StashAction {
+    /// List them
+    List,
+    /// List them with the files they change
+    Show,
+    /// Restore the newest one and keep it
+    Apply,
+    /// Restore the newest one and drop it unless it conflicts
+    Pop,
+    /// Drop the newest one
+    Drop,
+}
+
+impl StashAction {
+    fn name(&self) -> &'static str {
+        match self {
+            StashAction::List => "stash list",
+            StashAction::Show => "stash show",
+            StashAction::Apply => "stash apply",
+            StashAction::Pop => "stash pop",
+            StashAction::Drop => "stash drop",
+        }
+    }
+
+    /// Whether the action changes the repos
+    fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            StashAction::Apply | StashAction::Pop | StashAction::Drop
+        )
+    }
+}
+
+pub struct StashOptions {
+    pub path: PathBuf,
+    pub config_path: PathBuf,
+    pub action: StashAction,
+    pub thread_count: usize,
+    pub ignore: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
+}
+
+impl StashOptions {
+    pub fn new(
+        path: Option<impl AsRef<Path>>,
+        config_path: Option<impl AsRef<Path>>,
+        action: StashAction,
+        thread: Option<usize>,
+        ignore: Option<Vec<String>>,
+        labels: Option<Vec<String>>,
+    ) -> Self {
+        let (path, config_path) = Workspace::resolve(path, config_path);
+        Self {
+            path,
+            config_path,
+            action,
+            thread_count: thread.unwrap_or(4),
+            ignore,
+            labels,
+            only: None,
+            states: None,
+            wait: false,
+        }
+    }
+}
+
+/// List, restore or drop the stashes `sync` created in every repo of a workspace
+///
+/// Stashes are told from the user's own by the message prefix mgit gives
+/// them. Restoring and dropping act on the newest one of each repo, a
+/// conflicting restore is reported with its files and the stash is kept.
+pub async fn stash_repos(
+    options: StashOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let path = &options.path;
+    let config_path = &options.config_path;
+    let action = options.action;
+
+    progress.on_message(StyleMessage::ops_start(action.name(), path));
+
+    if !config_path.is_file() {
+        return Err(MgitError::ConfigFileNotFound {
+            path: config_path.clone(),
+        });
+    }
+
+    // listing reads the repos only, restoring must not race a sync
+    let _lock = match action.is_mutating() {
+        true => Some(WorkspaceLock::acquire(path, "stash", options.wait, &progress).await?),
+        false => None,
+    };
+
+    let mgit_config = MgitConfig::load(config_path).ok_or(MgitError::LoadConfigFailed {
+        source: std::io::Error::other("Failed to load config"),
+    })?;
+
+    let repo_configs = if let Some(repos) = mgit_config.repos {
+        repos
+    } else {
+        return Ok(StyleMessage::new().plain_text("No repos to stash"));
+    };
+
+    let selection = RepoSelection::new(path, options.ignore.as_ref())?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &mgit_config.default_branch, &progress)
+        .await;
+    progress.on_batch_start(repos_map.len());
+
+    let semaphore = Arc::new(Semaphore::new(options.thread_count));
+    let mut join_set = JoinSet::new();
+    let counter = Arc::new(std::sync::atomic::AtomicUsize::new(1));
+
+    for (id, repo_config) in repos_map {
+        let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
+            AcquirePermitFailedSnafu {
+                message: "Failed to acquire semaphore permit for parallel execution".to_string(),
+            }
+            .build()
+        })?;
+        let counter = Arc::clone(&counter);
+        let progress = progress.clone();
+        let base_path = path.clone();
+
+        join_set.spawn(async move {
+            let _permit = permit;
+            let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let repo_info = RepoInfo::new(id, index, &repo_config);
+            let rel_path = repo_info.rel_path().display_path();
+            let full_path = base_path.join(repo_info.rel_path());
+
+            progress.on_repo_start(&repo_info, "read stashes...".into());
+            if git::is_repository(&full_path).await.is_err() {
+                progress.on_repo_success(&repo_info, "not cloned, skipped".into());
+                return Ok(None);
+            }
+            match exec_stash(&full_path, &rel_path, action, &repo_info, &progress).await {
+                Ok((msg, section)) => {
+                    progress.on_repo_success(&repo_info, msg);
+                    Ok(section.map(|section| (id, section)))
+                }
+                Err(e) => {
+                    progress.on_repo_error(&repo_info, StyleMessage::new());
+                    Err(e)
+                }
+            }
+        });
+    }
+
+    let mut sections = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(res) = join_set.join_next().await {
+        match res {
+            Ok(Ok(Some(section))) => sections.push(section),
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(StyleMessage::new().plain_text(format!("Task failed: {}", e))),
+            _ => {}
+        }
+    }
+
+    progress.on_batch_finish();
+
+    // grouped by repo in config order
+    sections.sort_by_key(|(id, _)| *id);
+    let mut result = StyleMessage::new();
+    if action.is_mutating() && !sections.is_empty() {
+        result = result.join("Stash status:\n".into());
+    }
+    for (_, section) in sections {
+        result = result.join(section);
+    }
+    if !action.is_mutating() && result.is_empty() {
+        result = result.plain_text("No stashes created by mgit\n");
+    }
+
+    if errors.is_empty() {
+        if action.is_mutating() {
+            result = StyleMessage::ops_success(action.name()).join(result);
+        }
+        Ok(result)
+    } else {
+        let msg = StyleMessage::ops_failed(action.name(), errors.len());
+        Err(MgitError::OpsError {
+            message: format!(
+                "{}{}\nErrors:\n{}",
+                result,
+                msg,
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+    }
+}
+
+/// Run the action in one repo
+///
+/// Returns the progress message and the lines of the repo in the result.
+async fn exec_stash(
+    full_path: &Path,
+    rel_path: &str,
+    action: StashAction,
+    repo_info: &RepoInfo<'_>,
+    progress: &impl Progress,
+) -> Result<(StyleMessage, Option<StyleMessage>), StyleMessage> {
+    let stashes = git::list_mgit_stashes(full_path)
+        .await
+        .map_err(|e| StyleMessage::git_error(rel_path, &e))?;
+    let Some(newest) = stashes.first() else {
+        return Ok(("no stash".into(), None));
+    };
+    let count = format!("{} stash(es)", stashes.len());
+
+    match action {
+        StashAction::List | StashAction::Show => {
+            let mut section = StyleMessage::git_stash_repo(rel_path).join("\n".into());
+            for stash in &stashes {
+                section = section.join(StyleMessage::git_stash_entry(stash));
+                section = section.join("\n".into());
+                if action == StashAction::Show {
+                    let stat = git::stash_show(full_path, &stash.reference)
+                        .await
+                        .map_err(|e| StyleMessage::git_error(rel_path, &e))?;
+                    for line in stat.lines().filter(|line| !line.trim().is_empty()) {
+                        section = section.join(format!("    {}\n", line.trim()).into());
+                    }
+                }
+            }
+            Ok((count.into(), Some(section)))
+        }
+        StashAction::Apply | StashAction::Pop => {
+            let pop = action == StashAction::Pop;
+            progress.on_repo_update(repo_info, format!("{}...", action.name()).into());
+            if let Err(e) = git::stash_apply(full_path, &newest.reference, pop).await {
+                let conflicts = git::get_conflicted_files(full_path)
+                    .await
+                    .unwrap_or_default();
+                return Err(match conflicts.is_empty() {
+                    true => StyleMessage::git_error(rel_path, &e),
+                    false => {
+                        StyleMessage::git_stash_conflicts(rel_path, &newest.reference, &conflicts)
+                    }
+                });
+            }
+            let desc = match pop {
+                true => format!("popped {}", newest.message),
+                false => format!("applied {}", newest.message),
+            };
+            let section = StyleMessage::new()
+                .plain_text("  ")
+                .join(StyleMessage::git_stash(rel_path, &desc))
+                .join("\n".into());
+            Ok((desc.into(), Some(section)))
+        }
+        StashAction::Drop => {
+            progress.on_repo_update(repo_info, "stash drop...".into());
+            git::stash_drop(full_path, &newest.reference)
+                .await
+                .map_err(|e| StyleMessage::git_error(rel_path, &e))?;
+            let desc = format!("dropped {}", newest.message);
+            let section = StyleMessage::new()
+                .plain_text("  ")
+                .join(StyleMessage::git_stash(rel_path, &desc))
+                .join("\n".into());
+            Ok((desc.into(), Some(section)))
+        }
+    }
+}
@@ -1,20 +1,29 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
-use crate::config::{cmp_local_remote, repos_to_map_with_ignore, MgitConfig};
+use crate::config::{
+    cmp_local_remote, repos_to_map_with_ignore, MgitConfig, RepoSelection, RepoState, Workspace,
+};
 use crate::git;
-use crate::git::{RemoteRef, ResetType, StashMode};
+use crate::git::{GitCapability, RemoteRef, ResetType, StashMode};
 
 use crate::error::{
     AcquirePermitFailedSnafu, BranchReferenceRequiredSnafu, CreateDirFailedSnafu, MgitResult,
     NoRemoteConfiguredSnafu, StashHardConflictSnafu,
 };
-use crate::ops::{clean_repo, current_dir, exec_fetch, set_tracking_remote_branch, CleanOptions};
+use crate::ops::clean::exec_clean_repo;
+use crate::ops::fetch::uses_partial_clone;
+use crate::ops::{exec_fetch, exec_lfs_fetch, set_tracking_remote_branch, CleanOptions};
+use crate::utils::askpass::AskpassSession;
+use crate::utils::concurrency::{NetConcurrency, NetworkLimits};
+use crate::utils::lock::WorkspaceLock;
 use crate::utils::path::PathExtension;
 use crate::utils::progress::{Progress, RepoInfo};
+use crate::utils::stats::{PhaseTimer, RepoTiming, TimingSummary, WorkspaceStats};
 use crate::utils::style_message::StyleMessage;
 use snafu::ResultExt;
 
@@ -22,6 +31,8 @@ use snafu::ResultExt;
 #[derive(Debug, Default)]
 struct SyncExecResponse {
     stash: Option<StashResponse>,
+    /// Mirror fetched from when the remote was unreachable
+    fallback_url: Option<String>,
 }
 
 /// Stash operation response
@@ -38,8 +49,10 @@ pub struct SyncOptions {
     pub path: PathBuf,
     /// Path to the `.gitrepos` configuration file
     pub config_path: PathBuf,
-    /// Number of threads to use for parallel operations
+    /// Number of repos worked on at once, raised to the highest `net_thread`
     pub thread_count: usize,
+    /// Number of network git commands run at once, `thread_count` when not set
+    pub net_thread: Option<NetConcurrency>,
     /// Whether to suppress status output
     pub silent: bool,
     /// Shallow clone depth (None for full clone)
@@ -48,6 +61,10 @@ pub struct SyncOptions {
     pub ignore: Option<Vec<String>>,
     /// List of labels to filter repositories
     pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
     /// Whether to discard all local changes (hard reset)
     pub hard: bool,
     /// Whether to stash local changes before sync
@@ -56,6 +73,14 @@ pub struct SyncOptions {
     pub no_track: bool,
     /// Whether to skip checking out branches
     pub no_checkout: bool,
+    /// Whether to refresh the mirror cache and seed new repos from it
+    pub cache: bool,
+    /// Whether to leave LFS pointer files instead of downloading the objects
+    pub skip_lfs: bool,
+    /// Whether to fetch and reset repos that already sit on the remote ref
+    pub no_fast_path: bool,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl SyncOptions {
@@ -105,14 +130,21 @@ pub struct SyncOptionsBuilder {
     path: Option<PathBuf>,
     config_path: Option<PathBuf>,
     thread_count: Option<usize>,
+    net_thread: Option<NetConcurrency>,
     silent: Option<bool>,
     depth: Option<usize>,
     ignore: Option<Vec<String>>,
     labels: Option<Vec<String>>,
+    only: Option<Vec<String>>,
+    states: Option<Vec<RepoState>>,
     hard: Option<bool>,
     stash: Option<bool>,
     no_track: Option<bool>,
     no_checkout: Option<bool>,
+    cache: Option<bool>,
+    skip_lfs: Option<bool>,
+    no_fast_path: Option<bool>,
+    wait: Option<bool>,
 }
 
 impl SyncOptionsBuilder {
@@ -134,6 +166,12 @@ impl SyncOptionsBuilder {
         self
     }
 
+    /// Set the number of network git commands run at once, or `auto` to adapt it
+    pub fn net_thread(mut self, net_thread: Option<NetConcurrency>) -> Self {
+        self.net_thread = net_thread;
+        self
+    }
+
     /// Set whether to suppress status output
     pub fn silent(mut self, silent: Option<bool>) -> Self {
         self.silent = silent;
@@ -182,22 +220,64 @@ impl SyncOptionsBuilder {
         self
     }
 
+    /// Go through the mirror cache, `cache.enabled` of the user config when not set
+    pub fn cache(mut self, cache: Option<bool>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Set whether to leave LFS pointer files instead of downloading the objects
+    pub fn skip_lfs(mut self, skip_lfs: Option<bool>) -> Self {
+        self.skip_lfs = skip_lfs;
+        self
+    }
+
+    /// Set whether to fetch and reset repos that already sit on the remote ref
+    pub fn no_fast_path(mut self, no_fast_path: Option<bool>) -> Self {
+        self.no_fast_path = no_fast_path;
+        self
+    }
+
+    /// Set the repos to act on, all repos when not set
+    pub fn only(mut self, only: Option<Vec<String>>) -> Self {
+        self.only = only;
+        self
+    }
+
+    /// Set the states of the repos to act on, all repos when not set
+    pub fn states(mut self, states: Option<Vec<RepoState>>) -> Self {
+        self.states = states;
+        self
+    }
+
+    /// Set whether to wait for the workspace lock
+    pub fn wait(mut self, wait: Option<bool>) -> Self {
+        self.wait = wait;
+        self
+    }
+
     /// Build the SyncOptions
     pub fn build(self) -> SyncOptions {
-        let path = self.path.unwrap_or_else(current_dir);
-        let config_path = self.config_path.unwrap_or_else(|| path.join(".gitrepos"));
+        let (path, config_path) = Workspace::resolve(self.path, self.config_path);
         SyncOptions {
             path,
             config_path,
             thread_count: self.thread_count.unwrap_or(4),
+            net_thread: self.net_thread,
             silent: self.silent.unwrap_or(false),
             depth: self.depth,
             ignore: self.ignore,
             labels: self.labels,
+            only: self.only,
+            states: self.states,
             hard: self.hard.unwrap_or(false),
             stash: self.stash.unwrap_or(false),
             no_track: self.no_track.unwrap_or(false),
             no_checkout: self.no_checkout.unwrap_or(false),
+            cache: self.cache.unwrap_or_else(git::is_cache_enabled),
+            skip_lfs: self.skip_lfs.unwrap_or(false),
+            no_fast_path: self.no_fast_path.unwrap_or(false),
+            wait: self.wait.unwrap_or(false),
         }
     }
 }
@@ -223,13 +303,22 @@ pub async fn sync_repo(
     let path = &options.path;
     let config_path = &options.config_path;
     let thread_count = options.thread_count;
+    let net_thread = options
+        .net_thread
+        .unwrap_or(NetConcurrency::Fixed(thread_count));
     let hard = options.hard;
     let stash = options.stash;
     let silent = options.silent;
     let no_track = options.no_track;
     let no_checkout = options.no_checkout;
     let depth = options.depth;
+    // shallow clones gain little from a full mirror
+    let use_cache = options.cache && depth.is_none();
+    let skip_lfs = options.skip_lfs;
+    // deepening a shallow clone always needs a fetch
+    let fast_path = !options.no_fast_path && depth.is_none();
     let ignore = options.ignore.as_ref();
+    let _askpass = AskpassSession::begin();
 
     progress.on_message(StyleMessage::ops_start("sync repos", path));
 
@@ -249,16 +338,36 @@ pub async fn sync_repo(
         });
     }
 
+    // another run stashing and resetting the same repos would corrupt them
+    let _lock = WorkspaceLock::acquire(path, "sync", options.wait, &progress).await?;
+
     // load config file(like .gitrepos)
     let mgit_config =
         MgitConfig::load(config_path).ok_or(crate::error::MgitError::LoadConfigFailed {
             source: std::io::Error::other("Failed to load config"),
         })?;
 
+    // fail early if the git in use is too old
+    let mut capabilities = vec![
+        GitCapability::ShowCurrentBranch,
+        GitCapability::FetchRecurseSubmodules,
+    ];
+    if let Some(repos) = mgit_config.repos.as_ref() {
+        if repos.iter().any(|repo| repo.sparse.is_some()) {
+            capabilities.push(GitCapability::SparseNoCone);
+        }
+    }
+    if uses_partial_clone(&mgit_config) {
+        capabilities.push(GitCapability::PartialClone);
+    }
+    git::require_capabilities(&capabilities).await?;
+
     // remove unused repositories when use '--config' option
     // also if input_path not exists, skip this process
-    if stash_mode == StashMode::Hard && path.is_dir() {
-        let res = clean_repo(
+    // and when only some repos are synced, the others are left alone
+    let all_repos = options.only.is_none() && options.states.is_none();
+    if stash_mode == StashMode::Hard && path.is_dir() && all_repos {
+        let res = exec_clean_repo(
             CleanOptions::new(
                 Some(path.clone()),
                 Some(config_path.clone()),
@@ -279,14 +388,27 @@ pub async fn sync_repo(
     };
 
     let default_branch = mgit_config.default_branch;
+    let default_filter = mgit_config.default_filter;
 
     // retain repos exclude ignore repositories
-    let repos_map = repos_to_map_with_ignore(repo_configs, ignore, options.labels.as_ref());
-    progress
-        .on_message(StyleMessage::new().plain_text(format!("Repos count: {}", repos_map.len())));
-    progress.on_batch_start(repos_map.len());
-
-    let semaphore = Arc::new(Semaphore::new(thread_count));
+    let selection = RepoSelection::new(path, ignore)?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &default_branch, &progress)
+        .await;
+    // the slowest repos of the previous runs start first, so they don't finish the batch alone
+    let mut stats = WorkspaceStats::load(path);
+    let repos = stats.slowest_first("sync", repos_map);
+    progress.on_message(StyleMessage::new().plain_text(format!("Repos count: {}", repos.len())));
+    progress.on_batch_start(repos.len());
+
+    // repos in flight bound the local work, fetches also wait for a network slot;
+    // enough repos run for the network limit to be reached, also as `auto` grows
+    let semaphore = Arc::new(Semaphore::new(thread_count.max(net_thread.max())));
+    let network_limits = NetworkLimits::new(net_thread);
     let mut join_set = JoinSet::new();
     let counter = std::sync::atomic::AtomicUsize::new(1);
     let counter = Arc::new(counter);
@@ -299,7 +421,8 @@ pub async fn sync_repo(
         track_status: StyleMessage,
     }
 
-    for (id, repo_config) in repos_map {
+    let batch_start = Instant::now();
+    for (id, repo_config) in repos {
         let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
             AcquirePermitFailedSnafu {
                 message: "Failed to acquire semaphore permit for parallel execution".to_string(),
@@ -311,12 +434,18 @@ pub async fn sync_repo(
         let base_path = path.clone();
         let default_branch = Arc::clone(&default_branch);
         let stash_mode = Arc::clone(&stash_mode);
-        let repo_config = repo_config.clone();
+        let network_limits = Arc::clone(&network_limits);
+        let mut repo_config = repo_config.clone();
+        if repo_config.filter.is_none() {
+            repo_config.filter.clone_from(&default_filter);
+        }
 
-        join_set.spawn(async move {
+        join_set.spawn(network_limits.scope(async move {
             let _permit = permit;
             let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
             let mut repo_info = RepoInfo::new(id, index, &repo_config);
+            let repo_start = Instant::now();
+            let timer = PhaseTimer::default();
 
             progress.on_repo_start(&repo_info, "waiting...".into());
 
@@ -334,13 +463,17 @@ pub async fn sync_repo(
                 &mut repo_info,
                 &stash_mode,
                 no_checkout,
+                use_cache,
+                skip_lfs,
+                fast_path,
                 depth.as_ref(),
                 &default_branch,
+                &timer,
                 &progress,
             )
             .await;
 
-            match exec_res {
+            let res = match exec_res {
                 Ok(response) => {
                     // if not silent, show compare stat betweent local and remote
                     let msg = if silent {
@@ -358,6 +491,10 @@ pub async fn sync_repo(
                         }
                         cmp_msg
                     };
+                    let msg = match &response.fallback_url {
+                        Some(url) => msg.join(StyleMessage::git_fetch_fallback(url)),
+                        None => msg,
+                    };
 
                     // show message in progress bar
                     progress.on_repo_success(&repo_info, msg);
@@ -377,9 +514,16 @@ pub async fn sync_repo(
                     // track status: track remote branch
                     let mut track_status = StyleMessage::new();
                     if !no_track {
-                        let track_res =
-                            set_tracking_remote_branch(&base_path, &repo_config, &default_branch)
-                                .await;
+                        let track_res = timer
+                            .time(
+                                "track",
+                                set_tracking_remote_branch(
+                                    &base_path,
+                                    &repo_config,
+                                    &default_branch,
+                                ),
+                            )
+                            .await;
                         track_status = track_status.try_join(track_res.ok());
                     }
 
@@ -400,17 +544,25 @@ pub async fn sync_repo(
                         .unwrap_or_else(|| id.to_string());
                     Err(StyleMessage::git_error(repo_rel_path, &e))
                 }
-            }
-        });
+            };
+            let timing = RepoTiming::new(repo_info.rel_path(), repo_start.elapsed(), &timer);
+            (timing, res)
+        }));
     }
 
     let mut succ_repos = Vec::new();
     let mut error_repos = Vec::new();
+    let mut timings = Vec::new();
 
     while let Some(res) = join_set.join_next().await {
         match res {
-            Ok(Ok(info)) => succ_repos.push(info),
-            Ok(Err(e)) => error_repos.push(e),
+            Ok((timing, res)) => {
+                timings.push(timing);
+                match res {
+                    Ok(info) => succ_repos.push(info),
+                    Err(e) => error_repos.push(e),
+                }
+            }
             Err(e) => {
                 // Task panicked or cancelled
                 error_repos.push(StyleMessage::new().plain_text(format!("Task failed: {}", e)));
@@ -419,6 +571,22 @@ pub async fn sync_repo(
     }
 
     progress.on_batch_finish();
+    if net_thread == NetConcurrency::Auto {
+        tracing::info!(
+            limit = network_limits.current(),
+            "network_concurrency_final"
+        );
+    }
+
+    if let Some(summary) = TimingSummary::new(batch_start.elapsed(), &timings) {
+        if !silent {
+            progress.on_message(StyleMessage::timing_summary(&summary));
+        }
+        stats.record("sync", &summary, &timings);
+        if let Err(e) = stats.save(path) {
+            tracing::warn!(error = %e, "stats_save_failed");
+        }
+    }
 
     if error_repos.is_empty() {
         let mut result = StyleMessage::ops_success("sync");
@@ -460,13 +628,18 @@ pub async fn sync_repo(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn inner_exec(
     input_path: &Path,
     repo_info: &mut RepoInfo<'_>,
     stash_mode: &StashMode,
     no_checkout: bool,
+    use_cache: bool,
+    skip_lfs: bool,
+    fast_path: bool,
     depth: Option<&usize>,
     default_branch: &Option<String>,
+    timer: &PhaseTimer,
     progress: &impl Progress,
 ) -> MgitResult<SyncExecResponse> {
     let full_path = &input_path.join(repo_info.rel_path());
@@ -492,15 +665,37 @@ async fn inner_exec(
 
     let mut stash_mode = stash_mode.to_owned();
     let is_repo_none = git::is_repository(full_path.as_path()).await.is_err();
+    if fast_path
+        && !is_repo_none
+        && timer
+            .time(
+                "check",
+                is_up_to_date(
+                    input_path,
+                    current_repo_info,
+                    no_checkout,
+                    skip_lfs,
+                    progress,
+                ),
+            )
+            .await
+    {
+        return Ok(SyncExecResponse::default());
+    }
+
     // if repository not found, create new one
     if is_repo_none {
         // use --hard
         stash_mode = StashMode::Hard;
 
-        // git init when dir exist
-        exec_init(input_path, current_repo_info, progress).await?;
-        // git remote add url
-        exec_add_remote(input_path, current_repo_info, progress).await?;
+        timer
+            .time("init", async {
+                // git init when dir exist
+                exec_init(input_path, current_repo_info, progress).await?;
+                // git remote add url
+                exec_add_remote(input_path, current_repo_info, progress).await
+            })
+            .await?;
     } else {
         let remote_url = current_repo_info
             .repo_config
@@ -515,8 +710,24 @@ async fn inner_exec(
         git::update_remote_url(full_path, remote_url).await?;
     }
 
+    // a mirror holds every blob, seeding from it would defeat the partial clone
+    let partial_clone = current_repo_info
+        .repo_config
+        .partial_clone_filter()
+        .is_some();
+    if use_cache && !partial_clone {
+        timer
+            .time("cache", exec_cache(input_path, current_repo_info, progress))
+            .await;
+    }
+
     // fetch
-    exec_fetch(input_path, current_repo_info, depth, progress).await?;
+    let fallback_url = timer
+        .time(
+            "fetch",
+            exec_fetch(input_path, current_repo_info, depth, progress),
+        )
+        .await?;
 
     // priority: commit/tag/branch(default-branch)
     let remote_ref = current_repo_info
@@ -530,120 +741,309 @@ async fn inner_exec(
     // check remote-ref valid
     git::is_remote_ref_valid(full_path, remote_ref_str).await?;
 
-    let mut exec_response = SyncExecResponse::default();
+    let mut exec_response = SyncExecResponse {
+        fallback_url,
+        ..Default::default()
+    };
 
-    match stash_mode {
-        StashMode::Normal => {
-            // try stash -> checkout -> reset -> stash pop
-            if !no_checkout {
-                // stash
-                let stash_response = exec_stash(input_path, current_repo_info, progress).await?;
+    // soft and mixed resets leave the worktree, and so its LFS files and submodules, as they are
+    let update_worktree = stash_mode == StashMode::Hard || !no_checkout;
+    let discard_changes = stash_mode == StashMode::Hard;
+
+    // partial clones narrow the worktree first, so the checkout only downloads matching blobs
+    if partial_clone {
+        if let Some(dirs) = current_repo_info.repo_config.sparse.as_ref() {
+            timer
+                .time("sparse", git::sparse_checkout_set(&full_path, dirs))
+                .await?;
+        }
+    }
+
+    // LFS objects are downloaded in their own phase below, checkouts only write pointers
+    let checkout = async {
+        match stash_mode {
+            StashMode::Normal => {
+                // try stash -> checkout -> reset -> stash pop
+                if !no_checkout {
+                    // stash
+                    let stash_response = timer
+                        .time("stash", exec_stash(input_path, current_repo_info, progress))
+                        .await?;
+
+                    // checkout
+                    let mut result = timer
+                        .time(
+                            "checkout",
+                            exec_checkout(input_path, current_repo_info, progress, false),
+                        )
+                        .await;
+
+                    if result.is_ok() {
+                        // reset --hard
+                        result = timer
+                            .time(
+                                "reset",
+                                exec_reset(
+                                    input_path,
+                                    current_repo_info,
+                                    progress,
+                                    ResetType::Hard,
+                                ),
+                            )
+                            .await;
+                    }
+
+                    // stash pop, whether checkout succ or failed, whether reset succ or failed
+                    if matches!(stash_response, StashResponse::Stash(_)) {
+                        let _ = timer
+                            .time(
+                                "stash",
+                                exec_stash_pop(input_path, current_repo_info, progress),
+                            )
+                            .await;
+                    }
+                    result
+                } else {
+                    // reset --soft
+                    timer
+                        .time(
+                            "reset",
+                            exec_reset(input_path, current_repo_info, progress, ResetType::Soft),
+                        )
+                        .await
+                }
+            }
+
+            StashMode::Stash => {
+                // stash with `--stash` option, maybe return error if need to initial commit
+                let stash_response = timer
+                    .time("stash", exec_stash(input_path, current_repo_info, progress))
+                    .await?;
+
+                let mut result: MgitResult<()> = Ok(());
+                let mut reset_type = ResetType::Mixed;
 
                 // checkout
-                let mut result =
-                    exec_checkout(input_path, current_repo_info, progress, false).await;
+                if !no_checkout {
+                    result = timer
+                        .time(
+                            "checkout",
+                            exec_checkout(input_path, current_repo_info, progress, true),
+                        )
+                        .await;
+                    reset_type = ResetType::Hard;
+                }
 
                 if result.is_ok() {
-                    // reset --hard
-                    result =
-                        exec_reset(input_path, current_repo_info, progress, ResetType::Hard).await;
+                    result = timer
+                        .time(
+                            "reset",
+                            exec_reset(input_path, current_repo_info, progress, reset_type),
+                        )
+                        .await;
                 }
 
-                // stash pop, whether checkout succ or failed, whether reset succ or failed
                 if matches!(stash_response, StashResponse::Stash(_)) {
-                    let _ = exec_stash_pop(input_path, current_repo_info, progress).await;
+                    // undo if checkout failed or reset failed
+                    if let Err(e) = result {
+                        // if reset failed, pop stash if stash something this time
+                        let _ = timer
+                            .time(
+                                "stash",
+                                exec_stash_pop(input_path, current_repo_info, progress),
+                            )
+                            .await;
+                        return Err(e);
+                    }
+
+                    // save stash message
+                    exec_response.stash = Some(stash_response);
                 }
                 result
-            } else {
-                // reset --soft
-                exec_reset(input_path, current_repo_info, progress, ResetType::Soft).await
             }
-        }
 
-        StashMode::Stash => {
-            // stash with `--stash` option, maybe return error if need to initial commit
-            let stash_response = exec_stash(input_path, current_repo_info, progress).await?;
+            StashMode::Hard => {
+                // lock file deletion logic - before clean operation
+                // shallow.lock lives in the shared git dir, index.lock in the one of the work tree
+                let lock_files = [
+                    (git::git_common_dir(full_path), "shallow.lock"),
+                    (git::git_dir(full_path), "index.lock"),
+                ];
+                for (git_dir, lock_file) in &lock_files {
+                    let Some(lock_path) = git_dir.as_ref().map(|dir| dir.join(lock_file)) else {
+                        continue;
+                    };
+                    if lock_path.exists() {
+                        let repo_path = repo_info.rel_path();
+                        tracing::info!(
+                            repo = %repo_path,
+                            lock_file = lock_file,
+                            "lock_file_detected_hard_mode_force_delete"
+                        );
+                        match tokio::fs::remove_file(&lock_path).await {
+                            Ok(_) => {
+                                tracing::info!(
+                                    repo = %repo_path,
+                                    lock_file = lock_file,
+                                    "lock_file_delete_success"
+                                );
+                            }
+                            Err(e) => {
+                                tracing::warn!(
+                                    repo = %repo_path,
+                                    lock_file = lock_file,
+                                    error = %e,
+                                    "lock_file_delete_failed"
+                                );
+                            }
+                        }
+                    }
+                }
 
-            let mut result: MgitResult<()> = Ok(());
-            let mut reset_type = ResetType::Mixed;
+                // clean
+                if !is_repo_none {
+                    timer
+                        .time("clean", exec_clean(input_path, current_repo_info, progress))
+                        .await?;
+                }
 
-            // checkout
-            if !no_checkout {
-                result = exec_checkout(input_path, current_repo_info, progress, true).await;
-                reset_type = ResetType::Hard;
-            }
+                // checkout
+                if !no_checkout {
+                    timer
+                        .time(
+                            "checkout",
+                            exec_checkout(input_path, current_repo_info, progress, true),
+                        )
+                        .await?;
+                }
 
-            if result.is_ok() {
-                result = exec_reset(input_path, current_repo_info, progress, reset_type).await;
+                // reset --hard
+                timer
+                    .time(
+                        "reset",
+                        exec_reset(input_path, current_repo_info, progress, ResetType::Hard),
+                    )
+                    .await
             }
+        }?;
 
-            if matches!(stash_response, StashResponse::Stash(_)) {
-                // undo if checkout failed or reset failed
-                if let Err(e) = result {
-                    // if reset failed, pop stash if stash something this time
-                    let _ = exec_stash_pop(input_path, current_repo_info, progress).await;
-                    return Err(e);
+        timer
+            .time("sparse", async {
+                match current_repo_info.repo_config.sparse.as_ref() {
+                    Some(dirs) => git::sparse_checkout_set(&full_path, dirs).await,
+                    None => git::sparse_checkout_disable(&full_path).await,
                 }
+            })
+            .await
+    };
+    git::without_lfs_smudge(checkout).await?;
 
-                // save stash message
-                exec_response.stash = Some(stash_response);
-            }
-            result
-        }
+    if update_worktree && !skip_lfs {
+        timer
+            .time("lfs", exec_lfs(input_path, current_repo_info, progress))
+            .await?;
+    }
 
-        StashMode::Hard => {
-            // lock file deletion logic - before clean operation
-            let lock_files = [".git/shallow.lock", ".git/index.lock"];
-            for lock_file in &lock_files {
-                let lock_path = full_path.join(lock_file);
-                if lock_path.exists() {
-                    let repo_path = repo_info.rel_path();
-                    tracing::info!(
-                        repo = %repo_path,
-                        lock_file = lock_file,
-                        "lock_file_detected_hard_mode_force_delete"
-                    );
-                    match tokio::fs::remove_file(&lock_path).await {
-                        Ok(_) => {
-                            tracing::info!(
-                                repo = %repo_path,
-                                lock_file = lock_file,
-                                "lock_file_delete_success"
-                            );
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                repo = %repo_path,
-                                lock_file = lock_file,
-                                error = %e,
-                                "lock_file_delete_failed"
-                            );
-                        }
-                    }
-                }
-            }
+    if update_worktree {
+        timer
+            .time(
+                "submodules",
+                exec_submodules(
+                    input_path,
+                    current_repo_info,
+                    depth,
+                    discard_changes,
+                    progress,
+                ),
+            )
+            .await?;
+    }
 
-            // clean
-            if !is_repo_none {
-                exec_clean(input_path, current_repo_info, progress).await?;
-            }
+    Ok(exec_response)
+}
 
-            // checkout
-            if !no_checkout {
-                exec_checkout(input_path, current_repo_info, progress, true).await?;
-            }
+/// Whether the repo already sits on the remote ref with a clean worktree
+///
+/// Local state is checked first, then a single `git ls-remote` replaces the
+/// fetch. Any doubt falls back to the full sync.
+async fn is_up_to_date(
+    input_path: &Path,
+    repo_info: &RepoInfo<'_>,
+    no_checkout: bool,
+    skip_lfs: bool,
+    progress: &impl Progress,
+) -> bool {
+    let full_path = input_path.join(repo_info.rel_path());
+    let repo_config = repo_info.repo_config;
+    progress.on_repo_update(repo_info, "check remote...".into());
 
-            // reset --hard
-            exec_reset(input_path, current_repo_info, progress, ResetType::Hard).await
+    let Ok(remote_name) = repo_config.get_remote_name(&full_path).await else {
+        return false;
+    };
+    let Ok(remote_ref) = repo_config.get_remote_ref(&full_path).await else {
+        return false;
+    };
+    // branch a full sync checks out, local commit of the ref and its name on the remote
+    let (branch, local_ref, remote_refname) = match &remote_ref {
+        RemoteRef::Commit(commit) if commit.len() >= 7 => {
+            (format!("commits/{}", &commit[..7]), commit.clone(), None)
         }
-    }?;
+        RemoteRef::Commit(_) => return false,
+        RemoteRef::Tag(tag) => (
+            format!("tags/{}", tag),
+            format!("refs/tags/{}", tag),
+            Some(format!("refs/tags/{}", tag)),
+        ),
+        RemoteRef::Branch(tracking) => match repo_config.branch.as_ref() {
+            Some(branch) => (
+                branch.clone(),
+                tracking.clone(),
+                Some(format!("refs/heads/{}", branch)),
+            ),
+            None => return false,
+        },
+    };
 
-    match current_repo_info.repo_config.sparse.as_ref() {
-        Some(dirs) => git::sparse_checkout_set(&full_path, dirs).await,
-        None => git::sparse_checkout_disable(&full_path).await,
-    }?;
+    let Ok(target) = git::get_ref_commit(&full_path, &local_ref).await else {
+        return false;
+    };
+    if git::get_current_commit(&full_path).await.ok().as_ref() != Some(&target) {
+        return false;
+    }
+    // downloaded LFS objects can't be told from pointer files cheaply, leave them to the LFS phase
+    if !skip_lfs
+        && repo_config.lfs != Some(false)
+        && (repo_config.lfs == Some(true) || git::uses_lfs(&full_path, &local_ref).await)
+    {
+        return false;
+    }
+    if !no_checkout && git::get_current_branch(&full_path).await.ok().as_ref() != Some(&branch) {
+        return false;
+    }
+    if git::get_sparse_patterns(&full_path).await != repo_config.sparse {
+        return false;
+    }
+    if let Some(filter) = repo_config.partial_clone_filter() {
+        let current = git::get_partial_clone_filter(&full_path, &remote_name).await;
+        if current.as_deref() != Some(filter) {
+            return false;
+        }
+    }
+    if repo_config.submodules != Some(false) && !git::are_submodules_up_to_date(&full_path).await {
+        return false;
+    }
+    if !git::is_worktree_clean(&full_path).await.unwrap_or(false) {
+        return false;
+    }
 
-    Ok(exec_response)
+    // a configured commit never moves
+    let Some(remote_refname) = remote_refname else {
+        return true;
+    };
+    match git::ls_remote_ref(&full_path, &remote_name, &remote_refname).await {
+        Ok(Some(remote_commit)) => remote_commit == target,
+        _ => false,
+    }
 }
 
 async fn exec_init(
@@ -672,6 +1072,90 @@ async fn exec_add_remote(
     git::add_remote_url(full_path, url).await
 }
 
+/// Refresh the mirror of the repo and seed the repo from it
+///
+/// Existing repos are seeded too, so the fetch from the remote that follows
+/// finds the objects in place and only negotiates. The cache only saves
+/// downloads, so failures are logged and the regular fetch goes on.
+async fn exec_cache(input_path: &Path, repo_info: &RepoInfo<'_>, progress: &impl Progress) {
+    let Some(url) = repo_info.repo_config.remote.as_ref() else {
+        return;
+    };
+    progress.on_repo_update(repo_info, "update cache...".into());
+    let mirror = match git::update_mirror(url).await {
+        Ok(mirror) => mirror,
+        Err(e) => {
+            tracing::warn!(repo = repo_info.rel_path(), error = %e, "cache_update_failed");
+            return;
+        }
+    };
+
+    progress.on_repo_update(repo_info, "seed from cache...".into());
+    let full_path = input_path.join(repo_info.rel_path());
+    let seeded = match repo_info.repo_config.get_remote_name(&full_path).await {
+        Ok(remote_name) => git::seed_from_mirror(&full_path, &remote_name, &mirror).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = seeded {
+        tracing::warn!(repo = repo_info.rel_path(), error = %e, "cache_seed_failed");
+    }
+}
+
+/// Download the LFS objects of the repo and write them over the pointer files
+async fn exec_lfs(
+    input_path: &Path,
+    repo_info: &RepoInfo<'_>,
+    progress: &impl Progress,
+) -> MgitResult<()> {
+    if !exec_lfs_fetch(input_path, repo_info, progress).await? {
+        return Ok(());
+    }
+    progress.on_repo_update(repo_info, "lfs checkout...".into());
+    git::lfs_checkout(input_path.join(repo_info.rel_path())).await
+}
+
+/// Check out the recorded commit of every submodule present in the worktree
+///
+/// Submodules left out by sparse patterns have no directory and are skipped.
+/// `discard_changes` drops local changes of the submodules, like `--hard` does for the repo.
+async fn exec_submodules(
+    input_path: &Path,
+    repo_info: &RepoInfo<'_>,
+    depth: Option<&usize>,
+    discard_changes: bool,
+    progress: &impl Progress,
+) -> MgitResult<()> {
+    if repo_info.repo_config.submodules == Some(false) {
+        return Ok(());
+    }
+    let full_path = input_path.join(repo_info.rel_path());
+    let submodules: Vec<String> = git::list_submodules(&full_path)
+        .await
+        .into_iter()
+        .filter(|submodule| full_path.join(submodule).is_dir())
+        .collect();
+    if submodules.is_empty() {
+        return Ok(());
+    }
+
+    progress.on_repo_update(repo_info, "submodule init...".into());
+    git::submodule_init(&full_path).await?;
+    git::submodule_sync(&full_path).await?;
+
+    let total = submodules.len();
+    for (index, submodule) in submodules.iter().enumerate() {
+        progress.on_repo_update(
+            repo_info,
+            format!("submodule {} ({}/{})...", submodule, index + 1, total).into(),
+        );
+        git::submodule_update(&full_path, submodule, depth, discard_changes).await?;
+        if discard_changes {
+            git::submodule_clean(&full_path, submodule).await?;
+        }
+    }
+    Ok(())
+}
+
 async fn exec_clean(
     input_path: &Path,
     repo_info: &RepoInfo<'_>,
@@ -720,8 +1204,12 @@ async fn exec_stash(
     let full_path = input_path.join(repo_info.rel_path());
     let msg = git::stash(full_path).await?;
 
-    let response = match msg.find("WIP") {
-        Some(idx) => StashResponse::Stash(msg.trim()[idx..].to_string()),
+    // nothing is stashed without local changes
+    let response = match msg
+        .trim()
+        .strip_prefix("Saved working directory and index state ")
+    {
+        Some(desc) => StashResponse::Stash(desc.to_string()),
         None => StashResponse::None,
     };
     Ok(response)
@@ -773,6 +1261,17 @@ async fn exec_checkout(
         }
     }
 
+    // don't move a branch that another worktree has checked out
+    if let Some(other) = git::find_branch_worktree(&full_path, &branch).await? {
+        return Err(crate::error::MgitError::OpsError {
+            message: format!(
+                "branch '{}' is checked out in worktree {}",
+                branch,
+                other.display()
+            ),
+        });
+    }
+
     let suffix = StyleMessage::git_checking_out(&branch);
     progress.on_repo_update(repo_info, suffix);
 
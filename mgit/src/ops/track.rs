@@ -4,13 +4,15 @@ use std::sync::Arc;
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
-use crate::config::{repos_to_map_with_ignore, MgitConfig, RepoConfig};
+use crate::config::{
+    repos_to_map_with_ignore, MgitConfig, RepoConfig, RepoSelection, RepoState, Workspace,
+};
 use crate::error::MgitError;
 use crate::error::MgitResult;
 use crate::git;
-use crate::git::RemoteRef;
+use crate::git::{GitCapability, RemoteRef};
 
-use crate::utils::current_dir;
+use crate::utils::lock::WorkspaceLock;
 use crate::utils::progress::{Progress, RepoInfo};
 use crate::utils::StyleMessage;
 
@@ -21,6 +23,12 @@ pub struct TrackOptions {
     pub path: PathBuf,
     pub config_path: PathBuf,
     pub ignore: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
 }
 
 impl TrackOptions {
@@ -29,15 +37,14 @@ impl TrackOptions {
         config_path: Option<impl AsRef<Path>>,
         ignore: Option<Vec<String>>,
     ) -> Self {
-        let path = match path {
-            Some(p) => p.as_ref().to_path_buf(),
-            None => current_dir(),
-        };
-        let config_path = config_path.map_or(path.join(".gitrepos"), |p| p.as_ref().to_path_buf());
+        let (path, config_path) = Workspace::resolve(path, config_path);
         Self {
             path,
             config_path,
             ignore,
+            only: None,
+            states: None,
+            wait: false,
         }
     }
 }
@@ -62,12 +69,17 @@ pub async fn track(
             path: config_path.clone(),
         });
     }
+
+    let _lock = WorkspaceLock::acquire(path, "track", options.wait, &progress).await?;
     // load config file(like .gitrepos)
     let mgit_config =
         MgitConfig::load(config_path).ok_or(crate::error::MgitError::LoadConfigFailed {
             source: std::io::Error::other("Failed to load config"),
         })?;
 
+    // fail early if the git in use is too old
+    git::require_capabilities(&[GitCapability::ShowCurrentBranch]).await?;
+
     // handle track
     let repo_configs = if let Some(repos) = mgit_config.repos {
         repos
@@ -78,7 +90,13 @@ pub async fn track(
     let default_branch = mgit_config.default_branch;
 
     // retain repos exclude ignore repositories
-    let repos_map = repos_to_map_with_ignore(repo_configs, ignore, None);
+    let selection = RepoSelection::new(path, ignore)?
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(path, &mut repos_map, &default_branch, &progress)
+        .await;
 
     progress.on_batch_start(repos_map.len());
 
@@ -0,0 +1,508 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use snafu::ResultExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::{
+    repos_to_map_with_ignore, MgitConfig, RepoConfig, RepoSelection, RepoState, Workspace,
+};
+use crate::error::{AcquirePermitFailedSnafu, CreateDirFailedSnafu, MgitError, MgitResult};
+use crate::git;
+use crate::git::RemoteRef;
+use crate::utils::current_dir;
+use crate::utils::lock::WorkspaceLock;
+use crate::utils::path::PathExtension;
+use crate::utils::progress::{Progress, RepoInfo};
+use crate::utils::state_dir::state_dir;
+use crate::utils::style_message::StyleMessage;
+
+pub struct WorktreeAddOptions {
+    /// Directory of the new workspace
+    pub target: PathBuf,
+    /// Existing workspace whose clones provide the object stores
+    pub path: PathBuf,
+    /// Config of the new workspace, copied to `<target>/.gitrepos`
+    pub config_path: PathBuf,
+    pub thread_count: usize,
+    pub ignore: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    /// Only act on these repos, by `local`, `alias` or glob, all repos when not set
+    pub only: Option<Vec<String>>,
+    /// Only act on the repos in one of these states, all repos when not set
+    pub states: Option<Vec<RepoState>>,
+    /// Whether to wait for other runs holding the lock of the new workspace
+    pub wait: bool,
+}
+
+impl WorktreeAddOptions {
+    pub fn new(
+        target: impl AsRef<Path>,
+        path: Option<impl AsRef<Path>>,
+        config_path: Option<impl AsRef<Path>>,
+        thread: Option<usize>,
+        ignore: Option<Vec<String>>,
+        labels: Option<Vec<String>>,
+    ) -> Self {
+        let (path, config_path) = Workspace::resolve(path, config_path);
+        Self {
+            target: absolute(target),
+            path,
+            config_path,
+            thread_count: thread.unwrap_or(4),
+            ignore,
+            labels,
+            only: None,
+            states: None,
+            wait: false,
+        }
+    }
+}
+
+pub struct WorktreeListOptions {
+    pub path: PathBuf,
+    pub config_path: PathBuf,
+}
+
+impl WorktreeListOptions {
+    pub fn new(path: Option<impl AsRef<Path>>, config_path: Option<impl AsRef<Path>>) -> Self {
+        let (path, config_path) = Workspace::resolve(path, config_path);
+        Self { path, config_path }
+    }
+}
+
+pub struct WorktreeRemoveOptions {
+    /// Workspace created by `worktree add`
+    pub target: PathBuf,
+    /// Remove work trees with local changes too
+    pub force: bool,
+    /// Whether to wait for other runs holding the workspace lock
+    pub wait: bool,
+}
+
+impl WorktreeRemoveOptions {
+    pub fn new(target: impl AsRef<Path>, force: Option<bool>) -> Self {
+        Self {
+            target: absolute(target),
+            force: force.unwrap_or(false),
+            wait: false,
+        }
+    }
+}
+
+/// `git worktree` runs in the source repo, so targets must not be relative
+fn absolute(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => current_dir().join(path),
+    }
+}
+
+fn load_config(config_path: &Path) -> MgitResult<MgitConfig> {
+    if !config_path.is_file() {
+        return Err(MgitError::ConfigFileNotFound {
+            path: config_path.to_path_buf(),
+        });
+    }
+    MgitConfig::load(config_path).ok_or(MgitError::LoadConfigFailed {
+        source: std::io::Error::other("Failed to load config"),
+    })
+}
+
+/// Create a workspace of linked worktrees from the clones of an existing workspace
+///
+/// Every repo of the config gets a detached `git worktree` at its commit, tag
+/// or remote branch. Branches are then checked out unless they are already
+/// checked out in another worktree, in which case the repo stays detached.
+pub async fn worktree_add(
+    options: WorktreeAddOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let target = &options.target;
+    let source = &options.path;
+
+    progress.on_message(StyleMessage::ops_start("add worktrees", target));
+
+    let mgit_config = load_config(&options.config_path)?;
+
+    let target_config_path = target.join(".gitrepos");
+    if target_config_path.exists() {
+        return Err(MgitError::DirAlreadyInited {
+            path: target.clone(),
+        });
+    }
+    tokio::fs::create_dir_all(target)
+        .await
+        .with_context(|_| CreateDirFailedSnafu {
+            path: target.clone(),
+        })?;
+
+    let repo_configs = if let Some(repos) = mgit_config.repos {
+        repos
+    } else {
+        let _lock = WorkspaceLock::acquire(target, "worktree add", options.wait, &progress).await?;
+        copy_config(&options.config_path, &target_config_path).await?;
+        return Ok(StyleMessage::new().plain_text("No repos to add"));
+    };
+
+    let selection = RepoSelection::new(source, options.ignore.as_ref())?
+        .labels(options.labels.as_ref())
+        .only(options.only.as_ref())?
+        .states(options.states.as_ref());
+    let mut repos_map = repos_to_map_with_ignore(repo_configs, &selection)?;
+    selection
+        .retain_states(
+            source,
+            &mut repos_map,
+            &mgit_config.default_branch,
+            &progress,
+        )
+        .await;
+    progress.on_batch_start(repos_map.len());
+
+    let counter = Arc::new(std::sync::atomic::AtomicUsize::new(1));
+    let default_branch = Arc::new(mgit_config.default_branch);
+    let mut errors = Vec::new();
+
+    // git only checks the root repo out into an empty directory, so it goes
+    // before the lock and the config are written into the new workspace
+    let root_id = repos_map
+        .iter()
+        .find(|(_, repo_config)| is_root(repo_config))
+        .map(|(id, _)| *id);
+    if let Some((id, mut repo_config)) = root_id.and_then(|id| repos_map.remove_entry(&id)) {
+        if repo_config.branch.is_none() {
+            repo_config.branch = default_branch.as_ref().clone();
+        }
+        let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let repo_info = RepoInfo::new(id, index, &repo_config);
+        if let Err(e) = exec_add(source, target, &repo_info, &progress).await {
+            errors.push(e);
+        }
+    }
+
+    let _lock = WorkspaceLock::acquire(target, "worktree add", options.wait, &progress).await?;
+    copy_config(&options.config_path, &target_config_path).await?;
+
+    let semaphore = Arc::new(Semaphore::new(options.thread_count));
+    let mut join_set = JoinSet::new();
+
+    for (id, mut repo_config) in repos_map {
+        let permit = Arc::clone(&semaphore).acquire_owned().await.map_err(|_| {
+            AcquirePermitFailedSnafu {
+                message: "Failed to acquire semaphore permit for parallel execution".to_string(),
+            }
+            .build()
+        })?;
+        let counter = Arc::clone(&counter);
+        let progress = progress.clone();
+        let source = source.clone();
+        let target = target.clone();
+        if repo_config.branch.is_none() {
+            repo_config.branch = default_branch.as_ref().clone();
+        }
+
+        join_set.spawn(async move {
+            let _permit = permit;
+            let index = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let repo_info = RepoInfo::new(id, index, &repo_config);
+            exec_add(&source, &target, &repo_info, &progress).await
+        });
+    }
+
+    while let Some(res) = join_set.join_next().await {
+        match res {
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(StyleMessage::new().plain_text(format!("Task failed: {}", e))),
+            _ => {}
+        }
+    }
+
+    progress.on_batch_finish();
+
+    if errors.is_empty() {
+        Ok(StyleMessage::ops_success("worktree add"))
+    } else {
+        let msg = StyleMessage::ops_failed("worktree add", errors.len());
+        Err(MgitError::OpsError {
+            message: format!(
+                "{}\nErrors:\n{}",
+                msg,
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+    }
+}
+
+/// Whether the repo is the workspace root itself, loaded with an empty `local`
+fn is_root(repo_config: &RepoConfig) -> bool {
+    matches!(repo_config.local.as_deref(), Some("" | "."))
+}
+
+async fn copy_config(config_path: &Path, target_config_path: &Path) -> MgitResult<()> {
+    tokio::fs::copy(config_path, target_config_path)
+        .await
+        .map(|_| ())
+        .map_err(|e| MgitError::OpsError {
+            message: format!("Failed to write {}: {}", target_config_path.display(), e),
+        })
+}
+
+/// Add the worktree of one repo, reporting it to `progress`
+async fn exec_add(
+    source: &Path,
+    target: &Path,
+    repo_info: &RepoInfo<'_>,
+    progress: &impl Progress,
+) -> Result<(), StyleMessage> {
+    let rel_path = repo_info.rel_path().display_path();
+
+    progress.on_repo_start(repo_info, "add worktree...".into());
+    match add_repo_worktree(source, target, repo_info).await {
+        Ok(msg) => {
+            progress.on_repo_success(repo_info, msg);
+            Ok(())
+        }
+        Err(e) => {
+            progress.on_repo_error(repo_info, StyleMessage::new());
+            Err(StyleMessage::git_error(rel_path, &e))
+        }
+    }
+}
+
+async fn add_repo_worktree(
+    source: &Path,
+    target: &Path,
+    repo_info: &RepoInfo<'_>,
+) -> MgitResult<StyleMessage> {
+    let rel_path = repo_info.rel_path();
+    let source_repo = source.join(rel_path);
+    let target_repo = target.join(rel_path);
+
+    git::is_repository(&source_repo)
+        .await
+        .map_err(|_| MgitError::OpsError {
+            message: format!(
+                "{} not found in {}, run sync there first",
+                rel_path.display_path(),
+                source.display()
+            ),
+        })?;
+    if git::is_repository(&target_repo).await.is_ok() {
+        return Ok(StyleMessage::new().plain_text("already exists"));
+    }
+
+    let remote_ref = repo_info.repo_config.get_remote_ref(&source_repo).await?;
+    let remote_ref_str = match &remote_ref {
+        RemoteRef::Commit(r) | RemoteRef::Tag(r) | RemoteRef::Branch(r) => r.clone(),
+    };
+
+    if let Some(parent) = target_repo.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|_| CreateDirFailedSnafu {
+                path: parent.to_path_buf(),
+            })?;
+    }
+    git::add_worktree(&source_repo, &target_repo, &remote_ref_str).await?;
+
+    if let Some(dirs) = repo_info.repo_config.sparse.as_ref() {
+        git::sparse_checkout_set(&target_repo, dirs).await?;
+    }
+
+    let (RemoteRef::Branch(_), Some(branch)) = (&remote_ref, &repo_info.repo_config.branch) else {
+        return Ok(StyleMessage::git_worktree_detached(&remote_ref_str));
+    };
+    // a branch can only be checked out in one worktree at a time
+    if let Some(other) = git::find_branch_worktree(&target_repo, branch).await? {
+        tracing::info!(
+            repo = rel_path,
+            branch = branch.as_str(),
+            worktree = %other.display(),
+            "worktree_branch_in_use"
+        );
+        return Ok(StyleMessage::git_worktree_detached(&remote_ref_str));
+    }
+    let args = ["checkout", "-B", branch, &remote_ref_str, "--no-track"];
+    git::checkout(&target_repo, &args).await?;
+    git::set_tracking_remote_branch(
+        &target_repo,
+        rel_path,
+        branch,
+        &remote_ref_str,
+        &remote_ref_str,
+    )
+    .await
+}
+
+/// List the linked worktrees of every repo of a workspace
+pub async fn worktree_list(options: WorktreeListOptions) -> MgitResult<StyleMessage> {
+    let path = &options.path;
+    let mgit_config = load_config(&options.config_path)?;
+
+    let mut result = StyleMessage::new();
+    let mut count = 0;
+    for repo_config in mgit_config.repos.iter().flatten() {
+        let Some(rel_path) = repo_config.local.as_ref() else {
+            continue;
+        };
+        let full_path = path.join(rel_path);
+        if git::is_repository(&full_path).await.is_err() {
+            continue;
+        }
+        for worktree in git::list_worktrees(&full_path).await? {
+            if worktree.is_main {
+                continue;
+            }
+            result = result.join(StyleMessage::git_worktree(
+                rel_path,
+                &worktree.path,
+                worktree.branch.as_deref(),
+            ));
+            result = result.join("\n".into());
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return Ok(StyleMessage::new().plain_text("No worktrees"));
+    }
+    Ok(result)
+}
+
+/// Remove a workspace created by `worktree add`
+///
+/// Only linked worktrees are removed, a repo cloned into the workspace by
+/// other means is reported as an error and left alone.
+pub async fn worktree_remove(
+    options: WorktreeRemoveOptions,
+    progress: impl Progress + 'static,
+) -> MgitResult<StyleMessage> {
+    let target = &options.target;
+
+    progress.on_message(StyleMessage::ops_start("remove worktrees", target));
+
+    let config_path = target.join(".gitrepos");
+    let mgit_config = load_config(&config_path)?;
+    let lock = WorkspaceLock::acquire(target, "worktree remove", options.wait, &progress).await?;
+    let repo_configs: Vec<RepoConfig> = mgit_config.repos.unwrap_or_default();
+
+    progress.on_batch_start(repo_configs.len());
+
+    // the root repo holds the others, it goes last
+    let mut repo_configs: Vec<(usize, &RepoConfig)> = repo_configs.iter().enumerate().collect();
+    repo_configs.sort_by_key(|(_, repo_config)| is_root(repo_config));
+
+    let mut errors = Vec::new();
+    for (id, repo_config) in repo_configs {
+        if repo_config.local.is_none() {
+            continue;
+        }
+        let repo_info = RepoInfo::new(id, id + 1, repo_config);
+        let rel_path = repo_info.rel_path().display_path();
+        let target_repo = target.join(repo_info.rel_path());
+
+        progress.on_repo_start(&repo_info, "remove worktree...".into());
+        if !target_repo.exists() {
+            progress.on_repo_success(&repo_info, "not found, skipped".into());
+            continue;
+        }
+        // removing it would take the repos left behind along with --force
+        if is_root(repo_config) && !errors.is_empty() {
+            progress.on_repo_error(&repo_info, StyleMessage::new());
+            errors.push(StyleMessage::git_error(
+                rel_path,
+                &MgitError::OpsError {
+                    message: "kept as other worktrees were not removed".to_string(),
+                },
+            ));
+            continue;
+        }
+
+        let res = match git::git_common_dir(&target_repo) {
+            Some(common_dir) if git::is_linked_worktree(&target_repo) && is_root(repo_config) => {
+                remove_root_worktree(&common_dir, target, &config_path, options.force).await
+            }
+            Some(common_dir) if git::is_linked_worktree(&target_repo) => {
+                git::remove_worktree(&common_dir, &target_repo, options.force).await
+            }
+            _ => Err(MgitError::OpsError {
+                message: "not a linked worktree".to_string(),
+            }),
+        };
+        match res {
+            Ok(_) => progress.on_repo_success(&repo_info, StyleMessage::new()),
+            Err(e) => {
+                progress.on_repo_error(&repo_info, StyleMessage::new());
+                errors.push(StyleMessage::git_error(rel_path, &e));
+            }
+        }
+    }
+
+    progress.on_batch_finish();
+
+    if errors.is_empty() {
+        tokio::fs::remove_file(&config_path).await.ok();
+        drop(lock);
+        tokio::fs::remove_dir_all(state_dir(target)).await.ok();
+        remove_empty_dirs(target);
+        Ok(StyleMessage::ops_success("worktree remove"))
+    } else {
+        let msg = StyleMessage::ops_failed("worktree remove", errors.len());
+        Err(MgitError::OpsError {
+            message: format!(
+                "{}\nErrors:\n{}",
+                msg,
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        })
+    }
+}
+
+/// Remove the worktree of the root repo, which is the workspace itself
+///
+/// The config copied by `worktree add` would keep git from removing it, so it
+/// is dropped first unless the repo tracks it, and put back if git refuses.
+async fn remove_root_worktree(
+    common_dir: &Path,
+    target: &Path,
+    config_path: &Path,
+    force: bool,
+) -> MgitResult<()> {
+    let untracked = git::get_untrack_files(target).await?;
+    let copied = match untracked.lines().any(|file| file.trim() == ".gitrepos") {
+        true => tokio::fs::read(config_path).await.ok(),
+        false => None,
+    };
+    if copied.is_some() {
+        tokio::fs::remove_file(config_path).await.ok();
+    }
+    let res = git::remove_worktree(common_dir, target, force).await;
+    if let (Err(_), Some(content)) = (&res, copied) {
+        tokio::fs::write(config_path, content).await.ok();
+    }
+    res
+}
+
+/// Remove `dir` and its sub directories if they hold no files
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    // fails when not empty
+    std::fs::remove_dir(dir).ok();
+}
@@ -0,0 +1,373 @@
+//! Credential prompting for git and ssh child processes.
+//!
+//! git runs with stdin detached, so it can't ask for credentials on a terminal.
+//! Once a [`ShellInteraction`] is registered, `GIT_ASKPASS` and `SSH_ASKPASS`
+//! point at the current executable, which forwards every prompt over a loopback
+//! socket back to the registering process. Prompts are answered one at a time,
+//! and answers are cached per host or key until the last [`AskpassSession`]
+//! ends, or until git rejects them.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use crate::utils::shell::{Auth, ShellInteraction};
+
+/// Address of the askpass server, set on git child processes
+pub const ASKPASS_ADDR_ENV: &str = "MGIT_ASKPASS_ADDR";
+
+/// Per-process secret the askpass helper must present
+pub const ASKPASS_TOKEN_ENV: &str = "MGIT_ASKPASS_TOKEN";
+
+const REPLY_OK: u8 = b'+';
+const REPLY_CANCEL: u8 = b'-';
+
+/// A prompt written by git or ssh to the askpass helper
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AskpassPrompt {
+    Username {
+        host: String,
+    },
+    Password {
+        host: String,
+    },
+    /// Passphrase of a private key, by its path
+    Passphrase {
+        key: String,
+    },
+    SshTrust {
+        host: String,
+        fingerprint: String,
+    },
+    Unsupported(String),
+}
+
+impl AskpassPrompt {
+    /// Classify the prompt text passed as the first argument of the helper
+    pub fn parse(prompt: &str) -> Self {
+        // git: "Username for 'https://example.com': "
+        // git: "Password for 'https://user@example.com': "
+        let http_re = regex::Regex::new(r"^(Username|Password) for '([^']*)'").unwrap();
+        if let Some(caps) = http_re.captures(prompt.trim()) {
+            let host = url_host(&caps[2]);
+            return match &caps[1] {
+                "Username" => AskpassPrompt::Username { host },
+                _ => AskpassPrompt::Password { host },
+            };
+        }
+
+        // ssh: "git@example.com's password: "
+        let ssh_password_re = regex::Regex::new(r"^[^@\s]+@([^']+)'s password:").unwrap();
+        if let Some(caps) = ssh_password_re.captures(prompt.trim()) {
+            return AskpassPrompt::Password {
+                host: caps[1].to_string(),
+            };
+        }
+
+        // ssh: "Enter passphrase for key '/home/user/.ssh/id_ed25519': "
+        let passphrase_re = regex::Regex::new(r"^Enter passphrase for (?:key )?'([^']+)'").unwrap();
+        if let Some(caps) = passphrase_re.captures(prompt.trim()) {
+            return AskpassPrompt::Passphrase {
+                key: caps[1].to_string(),
+            };
+        }
+
+        // ssh: "The authenticity of host 'example.com (1.2.3.4)' can't be established.
+        //       ED25519 key fingerprint is SHA256:xxxx.
+        //       Are you sure you want to continue connecting (yes/no/[fingerprint])? "
+        if prompt.contains("continue connecting") {
+            let host_re = regex::Regex::new(r"authenticity of host '([^' ]+)").unwrap();
+            let fingerprint_re = regex::Regex::new(r"fingerprint is (\S+?)\.?\s*$").unwrap();
+            let host = host_re
+                .captures(prompt)
+                .map(|caps| caps[1].to_string())
+                .unwrap_or_default();
+            let fingerprint = prompt
+                .lines()
+                .find_map(|line| fingerprint_re.captures(line.trim()))
+                .map(|caps| caps[1].to_string());
+            if let Some(fingerprint) = fingerprint {
+                return AskpassPrompt::SshTrust { host, fingerprint };
+            }
+        }
+
+        AskpassPrompt::Unsupported(prompt.trim().to_string())
+    }
+}
+
+/// Strip scheme, user info and path from a prompt url, keeping `host[:port]`
+fn url_host(url: &str) -> String {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map(|(_, h)| h)
+        .unwrap_or(authority);
+    host.to_string()
+}
+
+/// Host whose credentials git or ssh rejected, from the stderr of a failed command
+///
+/// `Some("")` when some ssh key was rejected without naming the host.
+pub fn rejected_host(stderr: &str) -> Option<String> {
+    // git: "fatal: Authentication failed for 'https://example.com/foo.git/'"
+    let http_re = regex::Regex::new(r"Authentication failed for '([^']*)'").unwrap();
+    if let Some(caps) = http_re.captures(stderr) {
+        return Some(url_host(&caps[1]));
+    }
+    // ssh: "git@example.com: Permission denied (publickey,password)."
+    let ssh_re = regex::Regex::new(r"(?m)^(?:[^@\s]+@)?([^:\s]+): Permission denied").unwrap();
+    if let Some(caps) = ssh_re.captures(stderr) {
+        return Some(caps[1].to_string());
+    }
+    // ssh: "Load key \"/home/user/.ssh/id_ed25519\": incorrect passphrase supplied to decrypt private key"
+    stderr.contains("incorrect passphrase").then(String::new)
+}
+
+struct AskpassServer {
+    addr: SocketAddr,
+    token: String,
+    program: PathBuf,
+}
+
+#[derive(Default)]
+struct AskpassCache {
+    /// `None` records a cancelled prompt so it isn't asked again for every repo
+    auth: HashMap<String, Option<Auth>>,
+    /// Passphrases by private key path
+    passphrase: HashMap<String, Option<String>>,
+    trust: HashMap<String, bool>,
+}
+
+static SERVER: OnceLock<Option<AskpassServer>> = OnceLock::new();
+static SHELL: Mutex<Option<Arc<dyn ShellInteraction>>> = Mutex::new(None);
+static CACHE: OnceLock<Mutex<AskpassCache>> = OnceLock::new();
+static ACTIVE_SESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn cache() -> &'static Mutex<AskpassCache> {
+    CACHE.get_or_init(Default::default)
+}
+
+/// Route git and ssh prompts of this process to `shell`
+///
+/// Without a registered shell, child processes keep the old behavior and fail
+/// when credentials are needed.
+pub fn set_shell(shell: Arc<dyn ShellInteraction>) {
+    *SHELL.lock().unwrap() = Some(shell);
+    SERVER.get_or_init(start_server);
+}
+
+fn start_server() -> Option<AskpassServer> {
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(e) => {
+            tracing::warn!(error = %e, "askpass_current_exe_failed");
+            return None;
+        }
+    };
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::warn!(error = %e, "askpass_bind_failed");
+            return None;
+        }
+    };
+    let addr = listener.local_addr().ok()?;
+    let token = new_token();
+
+    let thread_token = token.clone();
+    let spawned = std::thread::Builder::new()
+        .name("mgit-askpass".to_string())
+        .spawn(move || {
+            // one connection at a time, so prompts never interleave
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = serve(stream, &thread_token) {
+                    tracing::warn!(error = %e, "askpass_serve_failed");
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::warn!(error = %e, "askpass_spawn_failed");
+        return None;
+    }
+
+    tracing::info!(addr = %addr, "askpass_server_started");
+    Some(AskpassServer {
+        addr,
+        token,
+        program,
+    })
+}
+
+fn new_token() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    (0..2)
+        .map(|i| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u32(std::process::id());
+            hasher.write_usize(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+fn serve(mut stream: TcpStream, token: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request = String::new();
+    stream.read_to_string(&mut request)?;
+
+    let (request_token, prompt) = request.split_once('\n').unwrap_or((&request, ""));
+    if request_token != token {
+        tracing::warn!("askpass_token_mismatch");
+        return stream.write_all(&[REPLY_CANCEL]);
+    }
+
+    match answer(&AskpassPrompt::parse(prompt)) {
+        Some(reply) => {
+            stream.write_all(&[REPLY_OK])?;
+            stream.write_all(reply.as_bytes())
+        }
+        None => stream.write_all(&[REPLY_CANCEL]),
+    }
+}
+
+fn answer(prompt: &AskpassPrompt) -> Option<String> {
+    let shell = SHELL.lock().unwrap().clone()?;
+    tracing::info!(prompt = ?prompt, "askpass_prompt");
+
+    match prompt {
+        AskpassPrompt::Username { host } => ask_auth(shell.as_ref(), host).map(|a| a.username),
+        AskpassPrompt::Password { host } => ask_auth(shell.as_ref(), host).map(|a| a.password),
+        AskpassPrompt::Passphrase { key } => {
+            if let Some(cached) = cache().lock().unwrap().passphrase.get(key) {
+                return cached.clone();
+            }
+            let passphrase = shell.ask_ssh_passphrase(key);
+            cache()
+                .lock()
+                .unwrap()
+                .passphrase
+                .insert(key.clone(), passphrase.clone());
+            passphrase
+        }
+        AskpassPrompt::SshTrust { host, fingerprint } => {
+            let cached = cache().lock().unwrap().trust.get(fingerprint).copied();
+            let trusted = cached.unwrap_or_else(|| {
+                let trusted = shell.ask_ssh_trust(&format!("{} ({})", fingerprint, host));
+                cache()
+                    .lock()
+                    .unwrap()
+                    .trust
+                    .insert(fingerprint.clone(), trusted);
+                trusted
+            });
+            Some(if trusted { "yes" } else { "no" }.to_string())
+        }
+        AskpassPrompt::Unsupported(prompt) => {
+            shell.warn(&format!("unsupported credential prompt: {}", prompt));
+            None
+        }
+    }
+}
+
+fn ask_auth(shell: &dyn ShellInteraction, host: &str) -> Option<Auth> {
+    if let Some(cached) = cache().lock().unwrap().auth.get(host) {
+        return cached.clone();
+    }
+    let auth = shell.ask_http_auth(host);
+    cache()
+        .lock()
+        .unwrap()
+        .auth
+        .insert(host.to_string(), auth.clone());
+    auth
+}
+
+/// Drop the cached answers git or ssh rejected, so the next repo asks again
+///
+/// Called with the stderr of every failed git command.
+pub(crate) fn forget_rejected(stderr: &str) {
+    let Some(host) = rejected_host(stderr) else {
+        return;
+    };
+    tracing::info!(host = %host, "askpass_answer_rejected");
+    let mut cache = cache().lock().unwrap();
+    cache.auth.remove(&host);
+    // ssh doesn't say which key it tried
+    cache.passphrase.clear();
+}
+
+/// Keep cached answers alive while an op is running
+///
+/// Answers are dropped when the last session of the process ends.
+pub struct AskpassSession {
+    _private: (),
+}
+
+impl AskpassSession {
+    pub fn begin() -> Self {
+        ACTIVE_SESSIONS.fetch_add(1, Ordering::SeqCst);
+        Self { _private: () }
+    }
+}
+
+impl Drop for AskpassSession {
+    fn drop(&mut self) {
+        if ACTIVE_SESSIONS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            *cache().lock().unwrap() = AskpassCache::default();
+        }
+    }
+}
+
+/// Point `GIT_ASKPASS` and `SSH_ASKPASS` of a git command at the askpass helper
+pub(crate) fn apply_env(command: &mut Command) {
+    let Some(Some(server)) = SERVER.get() else {
+        return;
+    };
+    command
+        .env("GIT_ASKPASS", &server.program)
+        .env("SSH_ASKPASS", &server.program)
+        .env("SSH_ASKPASS_REQUIRE", "force")
+        .env(ASKPASS_ADDR_ENV, server.addr.to_string())
+        .env(ASKPASS_TOKEN_ENV, &server.token);
+}
+
+/// Run as askpass helper when started by git or ssh
+///
+/// Returns the exit code of the helper, or `None` for a normal start.
+/// Must be called first thing in `main`.
+pub fn run_helper() -> Option<i32> {
+    let addr = std::env::var(ASKPASS_ADDR_ENV).ok()?;
+    let token = std::env::var(ASKPASS_TOKEN_ENV).ok()?;
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+
+    let reply = (|| -> std::io::Result<Vec<u8>> {
+        let mut stream = TcpStream::connect(&addr)?;
+        stream.write_all(format!("{}\n{}", token, prompt).as_bytes())?;
+        stream.shutdown(Shutdown::Write)?;
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply)?;
+        Ok(reply)
+    })();
+
+    match reply {
+        Ok(reply) if reply.first() == Some(&REPLY_OK) => {
+            println!("{}", String::from_utf8_lossy(&reply[1..]));
+            Some(0)
+        }
+        _ => Some(1),
+    }
+}
@@ -1,9 +1,15 @@
 use snafu::ResultExt;
 use std::path::Path;
 use std::process::Stdio;
-use tokio::process::Command;
+use tokio::io::AsyncReadExt;
+use tokio::process::{ChildStderr, Command};
 
 use crate::error::{GitCommandFailedSnafu, MgitResult, ProcessWaitFailedSnafu};
+use crate::git;
+use crate::git::host::apply_host_settings;
+use crate::git::lfs;
+use crate::utils::askpass;
+use crate::utils::concurrency::acquire_network;
 use crate::utils::process_guard::ProcessGuard;
 use crate::utils::progress::{Progress, RepoInfo};
 
@@ -16,6 +22,10 @@ pub const DEFAULT_RETRY_DELAY_MS: u64 = 400;
 pub async fn exec_cmd(path: impl AsRef<Path>, cmd: &str, args: &[&str]) -> MgitResult<String> {
     let mut command = Command::new(cmd);
     command.current_dir(&path).args(args);
+    askpass::apply_env(&mut command);
+    lfs::apply_env(&mut command);
+    apply_host_settings(&mut command).await;
+    let permit = acquire_network(&command).await;
 
     #[cfg(target_os = "windows")]
     {
@@ -42,10 +52,14 @@ pub async fn exec_cmd(path: impl AsRef<Path>, cmd: &str, args: &[&str]) -> MgitR
 
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if let Some(permit) = permit {
+        permit.finish((!output.status.success()).then_some(stderr.as_str()));
+    }
 
     if output.status.success() {
         Ok(stdout)
     } else {
+        askpass::forget_rejected(&stderr);
         let command_str = format!("{} {}", cmd, args.join(" "));
         Err(crate::error::MgitError::GitCommandError {
             code: output.status.code().unwrap_or(-1),
@@ -59,6 +73,11 @@ pub async fn exec_cmd_with_progress(
     command: &mut Command,
     progress: &impl Progress,
 ) -> MgitResult<()> {
+    askpass::apply_env(command);
+    lfs::apply_env(command);
+    apply_host_settings(command).await;
+    let permit = acquire_network(command).await;
+
     #[cfg(target_os = "windows")]
     {
         const CREATE_NO_WINDOW: u32 = 0x08000000;
@@ -67,10 +86,10 @@ pub async fn exec_cmd_with_progress(
 
     command
         .stdin(Stdio::null())
-        .stdout(Stdio::piped())
+        .stdout(Stdio::null())
         .stderr(Stdio::piped());
 
-    let child = command.spawn().context(GitCommandFailedSnafu {
+    let mut child = command.spawn().context(GitCommandFailedSnafu {
         command: format!("{:?}", command),
     })?;
 
@@ -79,16 +98,27 @@ pub async fn exec_cmd_with_progress(
 
     progress.on_repo_update(repo_info, "running...".into());
 
-    let output = child
-        .wait_with_output()
-        .await
-        .context(ProcessWaitFailedSnafu)?;
+    let stderr_str = match child.stderr.take() {
+        Some(stderr) => read_progress(stderr, repo_info, progress).await,
+        None => String::new(),
+    };
+    let status = child.wait().await.context(ProcessWaitFailedSnafu)?;
+
+    if let Some(permit) = permit {
+        permit.finish((!status.success()).then_some(stderr_str.as_str()));
+    }
 
-    if !output.status.success() {
-        let stderr_str = String::from_utf8_lossy(&output.stderr).to_string();
-        let command_str = format!("{:?}", command);
+    if !status.success() {
+        askpass::forget_rejected(&stderr_str);
+        // program and args only, the environment may hold credentials
+        let std_command = command.as_std();
+        let command_str = std::iter::once(std_command.get_program())
+            .chain(std_command.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
         Err(crate::error::MgitError::GitCommandError {
-            code: output.status.code().unwrap_or(-1),
+            code: status.code().unwrap_or(-1),
             output: format!("Command '{}' failed: {}", command_str, stderr_str),
         })
     } else {
@@ -96,6 +126,43 @@ pub async fn exec_cmd_with_progress(
     }
 }
 
+/// Forward the `--progress` output of git as it is written, returning the final lines
+///
+/// Progress updates end with `\r` and overwrite each other on a terminal, so
+/// only lines ending with `\n` are kept for error messages.
+async fn read_progress(
+    mut stderr: ChildStderr,
+    repo_info: &RepoInfo<'_>,
+    progress: &impl Progress,
+) -> String {
+    let mut output = Vec::new();
+    let mut segment = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = match stderr.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        for &byte in &buf[..n] {
+            if byte != b'\r' && byte != b'\n' {
+                segment.push(byte);
+                continue;
+            }
+            let line = String::from_utf8_lossy(&segment);
+            if let Some(transfer) = git::parse_transfer_progress(&line) {
+                progress.on_repo_transfer(repo_info, &transfer);
+            }
+            if byte == b'\n' {
+                output.extend_from_slice(&segment);
+                output.push(b'\n');
+            }
+            segment.clear();
+        }
+    }
+    output.extend_from_slice(&segment);
+    String::from_utf8_lossy(&output).to_string()
+}
+
 pub async fn retry<T, F, Fut>(times: usize, sleep: std::time::Duration, f: F) -> MgitResult<T>
 where
     F: Fn() -> Fut,
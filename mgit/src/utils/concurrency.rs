@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::process::Command;
+use tokio::sync::Notify;
+
+use crate::config::UserConfig;
+use crate::git::host::{command_remote_host, network_subcommand};
+
+/// Network limit `auto` starts from
+pub const AUTO_INITIAL_NET_THREADS: usize = 4;
+
+/// Highest network limit `auto` grows to
+pub const AUTO_MAX_NET_THREADS: usize = 32;
+
+/// A command this many times slower than the running average counts as congestion
+const SLOWDOWN_FACTOR: u32 = 4;
+
+/// Weight of the latest sample in the running average latency
+const LATENCY_WEIGHT: f64 = 0.2;
+
+/// Lowercase stderr fragments of servers throttling or dropping connections
+const THROTTLE_PATTERNS: &[&str] = &[
+    "429",
+    "too many requests",
+    "503",
+    "connection reset",
+    "early eof",
+    "unexpected disconnect",
+    "the remote end hung up unexpectedly",
+    "rpc failed",
+    "timed out",
+];
+
+tokio::task_local! {
+    static NETWORK_LIMITS: Arc<NetworkLimits>;
+}
+
+/// How many network git commands may run at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetConcurrency {
+    Fixed(usize),
+    /// Start from a few commands and adapt to fetch latency and throttling errors
+    Auto,
+}
+
+impl NetConcurrency {
+    /// Highest number of network commands this allows at once
+    pub fn max(&self) -> usize {
+        match self {
+            Self::Fixed(count) => *count,
+            Self::Auto => AUTO_MAX_NET_THREADS,
+        }
+    }
+}
+
+impl FromStr for NetConcurrency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Self::Auto);
+        }
+        match s.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(Self::Fixed(count)),
+            _ => Err(format!("expected a positive number or `auto`, got `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for NetConcurrency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(count) => write!(f, "{}", count),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// Whether a failed command reads like a server throttling or dropping connections
+pub fn is_throttled(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    THROTTLE_PATTERNS
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+/// Limits of the network git commands started by `exec_cmd`
+///
+/// One limit covers every remote, hosts with `max-connections` in the user
+/// config get a second one. Local git commands are not limited here.
+pub struct NetworkLimits {
+    global: Limit,
+    hosts: Mutex<HashMap<String, Arc<Limit>>>,
+    auto: bool,
+}
+
+impl NetworkLimits {
+    pub fn new(concurrency: NetConcurrency) -> Arc<Self> {
+        let global = match concurrency {
+            NetConcurrency::Fixed(count) => Limit::new(count, count, false),
+            NetConcurrency::Auto => {
+                Limit::new(AUTO_INITIAL_NET_THREADS, AUTO_MAX_NET_THREADS, true)
+            }
+        };
+        Arc::new(Self {
+            global,
+            hosts: Mutex::default(),
+            auto: concurrency == NetConcurrency::Auto,
+        })
+    }
+
+    /// Run `f` with its network git commands held to these limits
+    ///
+    /// Like any task-local, the limits don't reach tasks spawned by `f`.
+    pub async fn scope<F: Future>(self: Arc<Self>, f: F) -> F::Output {
+        NETWORK_LIMITS.scope(self, f).await
+    }
+
+    /// Current global limit, which moves in `auto` mode
+    pub fn current(&self) -> usize {
+        self.global.current()
+    }
+
+    fn host_limit(&self, host: &str) -> Option<Arc<Limit>> {
+        let max = UserConfig::get().host_settings(host)?.max_connections?;
+        let limit = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Limit::new(max, max, self.auto)))
+            .clone();
+        Some(limit)
+    }
+}
+
+/// Wait until a network git command may start, `None` when not limited
+pub(crate) async fn acquire_network(command: &Command) -> Option<NetworkPermit> {
+    let limits = NETWORK_LIMITS.try_with(Arc::clone).ok()?;
+    network_subcommand(command)?;
+
+    // resolving the host may start a git process, only do it when it matters
+    let has_host_limits = UserConfig::get()
+        .hosts
+        .iter()
+        .flatten()
+        .any(|host| host.max_connections.is_some());
+    let host_limit = match has_host_limits {
+        true => command_remote_host(command)
+            .await
+            .and_then(|host| limits.host_limit(&host)),
+        false => None,
+    };
+
+    if let Some(limit) = &host_limit {
+        limit.acquire().await;
+    }
+    limits.global.acquire().await;
+    Some(NetworkPermit {
+        limits,
+        host_limit,
+        start: Instant::now(),
+    })
+}
+
+/// A running network git command, released on drop
+pub(crate) struct NetworkPermit {
+    limits: Arc<NetworkLimits>,
+    host_limit: Option<Arc<Limit>>,
+    start: Instant,
+}
+
+impl NetworkPermit {
+    /// Feed the outcome of the command to `auto` mode, `stderr` of failed commands
+    pub(crate) fn finish(self, stderr: Option<&str>) {
+        let outcome = match stderr {
+            None => Outcome::Success(self.start.elapsed()),
+            Some(stderr) if is_throttled(stderr) => Outcome::Throttled,
+            Some(_) => Outcome::Failed,
+        };
+        self.limits.global.record(&outcome);
+        if let Some(limit) = &self.host_limit {
+            limit.record(&outcome);
+        }
+    }
+}
+
+impl Drop for NetworkPermit {
+    fn drop(&mut self) {
+        self.limits.global.release();
+        if let Some(limit) = &self.host_limit {
+            limit.release();
+        }
+    }
+}
+
+/// Result of a network command as seen by `auto` mode
+#[derive(Debug)]
+pub(crate) enum Outcome {
+    Success(Duration),
+    Throttled,
+    /// Failures unrelated to load, like a missing ref, leave the limit alone
+    Failed,
+}
+
+/// A counting semaphore whose size can shrink while permits are out
+///
+/// In `auto` mode the size follows additive increase, multiplicative
+/// decrease: it grows by one after a limit's worth of normal commands, drops
+/// by one on a command much slower than average and halves on throttling.
+pub(crate) struct Limit {
+    state: Mutex<LimitState>,
+    notify: Notify,
+    max: usize,
+    auto: bool,
+}
+
+#[derive(Debug, Default)]
+struct LimitState {
+    limit: usize,
+    active: usize,
+    successes: usize,
+    average: Option<Duration>,
+}
+
+impl Limit {
+    pub(crate) fn new(initial: usize, max: usize, auto: bool) -> Self {
+        let max = max.max(1);
+        Self {
+            state: Mutex::new(LimitState {
+                limit: initial.clamp(1, max),
+                ..Default::default()
+            }),
+            notify: Notify::new(),
+            max,
+            auto,
+        }
+    }
+
+    pub(crate) fn current(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    pub(crate) async fn acquire(&self) {
+        loop {
+            // created before checking, so a release in between still wakes us
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.active < state.limit {
+                    state.active += 1;
+                    return;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    pub(crate) fn release(&self) {
+        self.state.lock().unwrap().active -= 1;
+        self.notify.notify_waiters();
+    }
+
+    pub(crate) fn record(&self, outcome: &Outcome) {
+        if !self.auto {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let before = state.limit;
+        match outcome {
+            Outcome::Success(latency) => {
+                let latency = *latency;
+                let slow = state
+                    .average
+                    .is_some_and(|average| latency > average * SLOWDOWN_FACTOR);
+                state.average = Some(match state.average {
+                    Some(average) => {
+                        average.mul_f64(1.0 - LATENCY_WEIGHT) + latency.mul_f64(LATENCY_WEIGHT)
+                    }
+                    None => latency,
+                });
+                if slow {
+                    state.limit = (state.limit - 1).max(1);
+                    state.successes = 0;
+                } else {
+                    state.successes += 1;
+                    if state.successes >= state.limit && state.limit < self.max {
+                        state.limit += 1;
+                        state.successes = 0;
+                    }
+                }
+            }
+            Outcome::Throttled => {
+                state.limit = (state.limit / 2).max(1);
+                state.successes = 0;
+            }
+            Outcome::Failed => {}
+        }
+
+        let after = state.limit;
+        drop(state);
+        if after > before {
+            tracing::info!(limit = after, "network_concurrency_increased");
+            self.notify.notify_waiters();
+        } else if after < before {
+            tracing::warn!(limit = after, "network_concurrency_decreased");
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use crate::error::{CreateDirFailedSnafu, IoSnafu, MgitResult, WorkspaceLockedSnafu};
+use crate::utils::progress::Progress;
+use crate::utils::state_dir::{create_state_dir, state_dir};
+use crate::utils::stats::format_duration;
+use crate::utils::style_message::StyleMessage;
+
+/// How often a waiting run checks the lock again
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// An unreadable lock younger than this may still be being written
+const LOCK_WRITE_GRACE: Duration = Duration::from_secs(5);
+
+/// Holder of a workspace lock, as written to the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub host: String,
+    /// Operation holding the lock, like `sync`
+    pub op: String,
+    /// Start time in seconds since the unix epoch
+    pub started: u64,
+}
+
+impl LockHolder {
+    fn current(op: &str) -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            pid: std::process::id(),
+            host: hostname(),
+            op: op.to_string(),
+            started,
+        }
+    }
+
+    /// Whether the holder is a process of this host that no longer runs
+    ///
+    /// Processes of other hosts, on a shared disk, can't be checked and are
+    /// never stale.
+    pub fn is_stale(&self) -> bool {
+        self.host == hostname() && !process_alive(self.pid)
+    }
+}
+
+impl fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let started = UNIX_EPOCH + Duration::from_secs(self.started);
+        let elapsed = SystemTime::now()
+            .duration_since(started)
+            .unwrap_or_default();
+        write!(
+            f,
+            "`{}` (pid {} on {}, started {} ago)",
+            self.op,
+            self.pid,
+            self.host,
+            format_duration(elapsed)
+        )
+    }
+}
+
+/// Advisory lock of a workspace, held by ops that change its repos
+///
+/// The lock is `lock` in the state directory of the workspace, created
+/// exclusively and removed when dropped. Locks left by crashed runs are
+/// detected from the pid and taken over.
+#[derive(Debug)]
+pub struct WorkspaceLock {
+    file: PathBuf,
+}
+
+impl WorkspaceLock {
+    /// Lock file of the workspace at `path`
+    pub fn file_path(path: impl AsRef<Path>) -> PathBuf {
+        state_dir(path).join("lock")
+    }
+
+    /// Lock the workspace at `path` for `op`
+    ///
+    /// Fails with `WorkspaceLocked` naming the holder when another run holds
+    /// the lock, unless `wait` is set.
+    pub async fn acquire(
+        path: impl AsRef<Path>,
+        op: &str,
+        wait: bool,
+        progress: &impl Progress,
+    ) -> MgitResult<Self> {
+        let path = path.as_ref();
+        let file = create_state_dir(path)
+            .with_context(|_| CreateDirFailedSnafu {
+                path: state_dir(path),
+            })?
+            .join("lock");
+
+        let holder = LockHolder::current(op);
+        let content = toml::to_string(&holder).unwrap_or_default();
+        let mut waiting = false;
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&file)
+            {
+                Ok(mut lock_file) => {
+                    let lock = Self { file };
+                    lock_file.write_all(content.as_bytes()).context(IoSnafu)?;
+                    return Ok(lock);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e).context(IoSnafu),
+            }
+
+            let current = read_holder(&file);
+            let stale = match &current {
+                Some(current) => current.is_stale(),
+                None => is_abandoned(&file),
+            };
+            if stale {
+                remove_stale(&file, current.as_ref());
+                continue;
+            }
+
+            let current = current.map_or_else(|| "unknown process".to_string(), |h| h.to_string());
+            if !wait {
+                return Err(WorkspaceLockedSnafu {
+                    path: path.to_path_buf(),
+                    holder: current,
+                }
+                .build());
+            }
+            if !waiting {
+                progress.on_message(StyleMessage::lock_waiting(path, &current));
+                waiting = true;
+            }
+            tokio::time::sleep(LOCK_POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.file).ok();
+    }
+}
+
+/// Holder written in a lock file, `None` when missing or not fully written
+pub fn read_holder(file: impl AsRef<Path>) -> Option<LockHolder> {
+    let content = std::fs::read_to_string(file).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Remove a stale lock unless another run replaced it since it was read
+///
+/// The lock is renamed away first, so that of the runs taking it over at once
+/// only one removes it, and put back when it is no longer the stale one.
+fn remove_stale(file: &Path, stale: Option<&LockHolder>) {
+    let moved = file.with_file_name(format!("lock.{}.stale", std::process::id()));
+    if std::fs::rename(file, &moved).is_err() {
+        // already taken over by another run
+        return;
+    }
+    let unchanged = match (read_holder(&moved), stale) {
+        (Some(holder), Some(stale)) => holder == *stale,
+        (None, None) => is_abandoned(&moved),
+        _ => false,
+    };
+    if unchanged {
+        tracing::warn!(path = %file.display(), holder = ?stale, "stale_lock_removed");
+    } else {
+        // fails when yet another run locked the workspace meanwhile
+        std::fs::hard_link(&moved, file).ok();
+    }
+    std::fs::remove_file(&moved).ok();
+}
+
+/// Whether an unreadable lock file is too old to be still being written
+fn is_abandoned(file: &Path) -> bool {
+    std::fs::metadata(file)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > LOCK_WRITE_GRACE)
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer outlives the call and its length is passed along
+    let res = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if res != 0 {
+        return "unknown".to_string();
+    }
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+#[cfg(windows)]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // signal 0 only checks the process, EPERM means it runs as another user
+    // SAFETY: kill with signal 0 sends nothing
+    let res = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    res == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_ACCESS_DENIED, STILL_ACTIVE,
+    };
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is checked before use and closed afterwards
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return GetLastError() == ERROR_ACCESS_DENIED;
+        }
+        let mut code = 0u32;
+        let res = GetExitCodeProcess(handle, &mut code);
+        CloseHandle(handle);
+        res != 0 && code == STILL_ACTIVE as u32
+    }
+}
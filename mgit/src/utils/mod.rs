@@ -1,9 +1,14 @@
+pub mod askpass;
 pub mod cmd;
+pub mod concurrency;
 pub mod label;
+pub mod lock;
 pub mod path;
 pub mod process_guard;
 pub mod progress;
 pub mod shell;
+pub mod state_dir;
+pub mod stats;
 pub mod style_message;
 pub mod upgrade_check;
 
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::config::RepoConfig;
 use crate::utils::style_message::StyleMessage;
 
@@ -23,6 +25,73 @@ impl<'a> RepoInfo<'a> {
     }
 }
 
+/// Phase of a git transfer, as printed by `git fetch --progress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferPhase {
+    /// `remote: Counting objects`
+    Counting,
+    /// `remote: Compressing objects`
+    Compressing,
+    /// `Receiving objects`
+    Receiving,
+    /// `Unpacking objects`, instead of receiving when few objects are fetched
+    Unpacking,
+    /// `Resolving deltas`
+    Resolving,
+}
+
+impl fmt::Display for TransferPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Counting => "counting objects",
+            Self::Compressing => "compressing objects",
+            Self::Receiving => "receiving objects",
+            Self::Unpacking => "unpacking objects",
+            Self::Resolving => "resolving deltas",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Progress of a git transfer phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub phase: TransferPhase,
+    pub percent: u8,
+    /// Bytes received so far, only known while receiving or unpacking objects
+    pub bytes: Option<u64>,
+    /// Bytes per second, only known while receiving or unpacking objects
+    pub rate: Option<u64>,
+}
+
+impl fmt::Display for TransferProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}%", self.phase, self.percent)?;
+        if let Some(bytes) = self.bytes {
+            write!(f, ", {}", format_size(bytes))?;
+        }
+        if let Some(rate) = self.rate {
+            write!(f, " | {}/s", format_size(rate))?;
+        }
+        Ok(())
+    }
+}
+
+/// Human readable size, like `5.7 MiB`
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", size, UNITS[0]),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
+
 pub trait Progress: Send + Sync + Clone {
     /// set total repo count, all repositories will execute in parallel
     fn on_batch_start(&self, total: usize);
@@ -46,4 +115,8 @@ pub trait Progress: Send + Sync + Clone {
     /// that is not tied to a specific repo's spinner state. Default no-op so
     /// implementations that don't care (e.g. tests) can ignore it.
     fn on_message(&self, _message: StyleMessage) {}
+
+    /// transfer progress of a running git fetch, reported many times per second.
+    /// Default no-op.
+    fn on_repo_transfer(&self, _repo_info: &RepoInfo, _transfer: &TransferProgress) {}
 }
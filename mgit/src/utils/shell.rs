@@ -7,5 +7,6 @@ pub struct Auth {
 pub trait ShellInteraction: Send + Sync {
     fn warn(&self, msg: &str);
     fn ask_ssh_trust(&self, fingerprint: &str) -> bool;
-    fn ask_http_auth(&self) -> Option<Auth>;
+    fn ask_http_auth(&self, host: &str) -> Option<Auth>;
+    fn ask_ssh_passphrase(&self, key: &str) -> Option<String>;
 }
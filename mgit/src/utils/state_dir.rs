@@ -0,0 +1,32 @@
+use std::path::{Path, PathBuf};
+
+use crate::git;
+
+/// Directory of the files mgit keeps for the workspace at `path`, like its lock
+///
+/// When the workspace root is a repo itself, `mgit` in its git directory keeps
+/// them out of the working tree, where `sync --stash` and `git status` would
+/// see them. Otherwise it is `.mgit` in the workspace.
+pub fn state_dir(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match git::git_dir(path) {
+        Some(git_dir) => git_dir.join("mgit"),
+        None => path.join(".mgit"),
+    }
+}
+
+/// Create the state directory of the workspace at `path` and return it
+///
+/// `.mgit` ignores its own files, as the workspace may be in the working tree
+/// of another repo or become one with a root repo cloned later.
+pub fn create_state_dir(path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+    let dir = state_dir(path);
+    std::fs::create_dir_all(&dir)?;
+    if dir.file_name().is_some_and(|name| name == ".mgit") {
+        let ignore = dir.join(".gitignore");
+        if !ignore.is_file() {
+            std::fs::write(ignore, "*\n")?;
+        }
+    }
+    Ok(dir)
+}
@@ -0,0 +1,239 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::RepoConfig;
+use crate::utils::path::PathExtension;
+use crate::utils::state_dir::{create_state_dir, state_dir};
+
+/// Slowest repos listed in the timing summary
+pub const SLOWEST_REPOS_COUNT: usize = 5;
+
+/// Phases shown next to each of the slowest repos
+pub(crate) const SLOWEST_PHASES_COUNT: usize = 3;
+
+/// Wall time spent in the phases of one repo, like `fetch` or `checkout`
+///
+/// Phases run more than once, like `reset` after a failed checkout, add up.
+#[derive(Debug, Default)]
+pub struct PhaseTimer {
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+}
+
+impl PhaseTimer {
+    /// Run `f` and add its wall time to `phase`
+    pub async fn time<F: Future>(&self, phase: &'static str, f: F) -> F::Output {
+        let start = Instant::now();
+        let output = f.await;
+        self.record(phase, start.elapsed());
+        output
+    }
+
+    pub fn record(&self, phase: &'static str, elapsed: Duration) {
+        let mut phases = self.phases.lock().unwrap();
+        match phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => phases.push((phase, elapsed)),
+        }
+    }
+
+    /// Recorded phases, in the order they first ran
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        self.phases.lock().unwrap().clone()
+    }
+}
+
+/// Timing of one repo in a batch
+#[derive(Debug, Clone)]
+pub struct RepoTiming {
+    pub path: String,
+    pub total: Duration,
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+impl RepoTiming {
+    pub fn new(path: impl Into<String>, total: Duration, timer: &PhaseTimer) -> Self {
+        Self {
+            path: path.into(),
+            total,
+            phases: timer.phases(),
+        }
+    }
+
+    /// Phases from the slowest one
+    pub fn slowest_phases(&self) -> Vec<(&'static str, Duration)> {
+        let mut phases = self.phases.clone();
+        phases.sort_by_key(|(_, elapsed)| Reverse(*elapsed));
+        phases
+    }
+}
+
+/// Totals of a batch of repos, for the summary printed after `sync` and `fetch`
+#[derive(Debug)]
+pub struct TimingSummary {
+    /// Wall time of the whole batch
+    pub total: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    /// Slowest repos first, at most `SLOWEST_REPOS_COUNT`
+    pub slowest: Vec<RepoTiming>,
+}
+
+impl TimingSummary {
+    pub fn new(total: Duration, timings: &[RepoTiming]) -> Option<Self> {
+        if timings.is_empty() {
+            return None;
+        }
+        let mut sorted = timings.to_vec();
+        sorted.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.path.cmp(&b.path)));
+
+        let durations = sorted.iter().rev().map(|t| t.total).collect::<Vec<_>>();
+        let p50 = percentile(&durations, 50);
+        let p95 = percentile(&durations, 95);
+        sorted.truncate(SLOWEST_REPOS_COUNT);
+        Some(Self {
+            total,
+            p50,
+            p95,
+            slowest: sorted,
+        })
+    }
+}
+
+/// Nearest-rank percentile of durations sorted from the fastest
+pub fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+/// Format a duration for people, like `850ms`, `12.3s` or `2m05s`
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if millis < 60_000 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        let secs = duration.as_secs();
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Durations of previous runs, kept in `stats` of the state directory of the workspace
+///
+/// Each operation keeps the latest duration of every repo it ran on, so a
+/// run on a few labels doesn't forget the others.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorkspaceStats {
+    #[serde(flatten)]
+    pub ops: BTreeMap<String, OpsStats>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OpsStats {
+    /// Wall time of the latest run in milliseconds
+    pub total_ms: u64,
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoStats>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RepoStats {
+    pub total_ms: u64,
+    /// Milliseconds per phase
+    #[serde(default)]
+    pub phases: BTreeMap<String, u64>,
+}
+
+impl WorkspaceStats {
+    /// Stats file of the workspace at `path`
+    pub fn file_path(path: impl AsRef<Path>) -> PathBuf {
+        state_dir(path).join("stats")
+    }
+
+    /// Load the stats of the workspace, empty when missing or unreadable
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let file = Self::file_path(path);
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            return Self::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            tracing::warn!(path = %file.display(), error = %e, "stats_parse_failed");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = create_state_dir(path)?.join("stats");
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(file, content)
+    }
+
+    /// Record the latest run of `ops`
+    pub fn record(&mut self, ops: &str, summary: &TimingSummary, timings: &[RepoTiming]) {
+        let stats = self.ops.entry(ops.to_string()).or_default();
+        stats.total_ms = summary.total.as_millis() as u64;
+        for timing in timings {
+            let phases = timing
+                .phases
+                .iter()
+                .map(|(phase, elapsed)| (phase.to_string(), elapsed.as_millis() as u64))
+                .collect();
+            stats.repos.insert(
+                timing.path.clone(),
+                RepoStats {
+                    total_ms: timing.total.as_millis() as u64,
+                    phases,
+                },
+            );
+        }
+    }
+
+    /// Previous duration of a repo, from `ops` or else from any other operation
+    pub fn previous_ms(&self, ops: &str, repo_path: &str) -> Option<u64> {
+        let repo_ms = |stats: &OpsStats| stats.repos.get(repo_path).map(|repo| repo.total_ms);
+        self.ops.get(ops).and_then(repo_ms).or_else(|| {
+            self.ops
+                .iter()
+                .filter(|(name, _)| name.as_str() != ops)
+                .find_map(|(_, stats)| repo_ms(stats))
+        })
+    }
+
+    /// Order repos so the slowest of previous runs start first
+    ///
+    /// Repos without a previous duration, usually new clones, go before the
+    /// others. Ties keep the order of the config.
+    pub fn slowest_first(
+        &self,
+        ops: &str,
+        repos_map: HashMap<usize, RepoConfig>,
+    ) -> Vec<(usize, RepoConfig)> {
+        let mut repos = repos_map
+            .into_iter()
+            .map(|(id, repo_config)| {
+                let previous = repo_config
+                    .local
+                    .as_ref()
+                    .and_then(|local| self.previous_ms(ops, &local.display_path()));
+                (previous.unwrap_or(u64::MAX), id, repo_config)
+            })
+            .collect::<Vec<_>>();
+        repos.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        repos
+            .into_iter()
+            .map(|(_, id, repo_config)| (id, repo_config))
+            .collect()
+    }
+}
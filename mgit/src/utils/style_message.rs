@@ -1,4 +1,7 @@
+use crate::config::RepoState;
+use crate::git::Stash;
 use crate::utils::path::PathExtension;
+use crate::utils::stats::{format_duration, TimingSummary, SLOWEST_PHASES_COUNT};
 use ansi_term::{Colour, Style};
 use std::fmt::{Display, Formatter};
 use std::path::Path;
@@ -328,6 +331,13 @@ impl StyleMessage {
             .join(desc)
     }
 
+    pub(crate) fn git_fetch_fallback(url: impl AsRef<str>) -> Self {
+        StyleMessage::new().plain_text(" ").styled_text(
+            format!("(fetched from fallback {})", url.as_ref()),
+            style_yellow(),
+        )
+    }
+
     pub(crate) fn git_update_to_date(branch_log: impl AsRef<str>) -> Self {
         StyleMessage::new()
             .plain_text("already update to date.")
@@ -369,6 +379,243 @@ impl StyleMessage {
             .plain_text(": ")
             .styled_text(tag.as_ref(), style_blue())
     }
+
+    pub(crate) fn doctor_finding(
+        severity: impl AsRef<str>,
+        rel_path: impl AsRef<str>,
+        desc: impl AsRef<str>,
+        fixed: bool,
+    ) -> Self {
+        let severity = severity.as_ref();
+        let style = match severity {
+            "error" => style_red_bold(),
+            "warning" => style_yellow(),
+            _ => style_grey(),
+        };
+        let mut msg = StyleMessage::new()
+            .plain_text("  ")
+            .styled_text(format!("[{}]", severity), style)
+            .plain_text(" ");
+        if !rel_path.as_ref().is_empty() {
+            msg = msg
+                .styled_text(rel_path.as_ref().display_path(), style_purple_bold())
+                .plain_text(": ");
+        }
+        msg = msg.plain_text(desc.as_ref());
+        if fixed {
+            msg = msg.plain_text(" ").styled_text("(fixed)", style_green());
+        }
+        msg
+    }
+
+    pub(crate) fn drift_item(
+        rel_path: impl AsRef<str>,
+        kind: impl AsRef<str>,
+        detail: impl AsRef<str>,
+    ) -> Self {
+        let mut msg = StyleMessage::new()
+            .plain_text("  ")
+            .styled_text(rel_path.as_ref().display_path(), style_purple_bold())
+            .plain_text(": ")
+            .styled_text(kind.as_ref(), style_yellow());
+        if !detail.as_ref().is_empty() {
+            msg = msg.plain_text(" ").plain_text(detail.as_ref());
+        }
+        msg
+    }
+
+    pub(crate) fn drift_summary(amount: usize) -> Self {
+        let msg = StyleMessage::new().plain_text("drift finished! ");
+        match amount {
+            0 => msg.plain_text("workspace matches config.\n"),
+            _ => msg
+                .styled_text(amount.to_string(), style_yellow())
+                .plain_text(" repo(s) drifted.\n"),
+        }
+    }
+
+    pub(crate) fn git_worktree(
+        rel_path: impl AsRef<str>,
+        worktree_path: impl AsRef<Path>,
+        branch: Option<&str>,
+    ) -> Self {
+        StyleMessage::new()
+            .plain_text("  ")
+            .styled_text(rel_path.as_ref().display_path(), style_purple_bold())
+            .plain_text(": ")
+            .plain_text(worktree_path.as_ref().display().to_string())
+            .plain_text(" ")
+            .styled_text(format!("[{}]", branch.unwrap_or("detached")), style_blue())
+    }
+
+    pub(crate) fn git_worktree_detached(remote_ref: impl AsRef<str>) -> Self {
+        StyleMessage::new().styled_text(
+            format!("detached at {}", remote_ref.as_ref()),
+            style_yellow(),
+        )
+    }
+
+    pub(crate) fn git_stash_repo(rel_path: impl AsRef<str>) -> Self {
+        StyleMessage::new()
+            .styled_text(rel_path.as_ref().display_path(), style_purple_bold())
+            .plain_text(":")
+    }
+
+    pub(crate) fn git_stash_entry(stash: &Stash) -> Self {
+        StyleMessage::new()
+            .plain_text("  ")
+            .styled_text(&stash.reference, style_yellow())
+            .plain_text(" ")
+            .styled_text(
+                format!("[{}]", stash.branch.as_deref().unwrap_or("detached")),
+                style_blue(),
+            )
+            .plain_text(" ")
+            .plain_text(&stash.message)
+            .plain_text(" ")
+            .styled_text(format!("({})", stash.date), style_grey())
+    }
+
+    pub(crate) fn git_stash_conflicts(
+        rel_path: impl AsRef<str>,
+        reference: impl AsRef<str>,
+        files: &[String],
+    ) -> Self {
+        StyleMessage::new()
+            .styled_text(rel_path.as_ref().display_path(), style_purple_bold())
+            .plain_text(" ")
+            .styled_text(format!("conflicts in {}", files.join(", ")), style_red())
+            .plain_text(format!(", {} kept", reference.as_ref()))
+    }
+
+    pub(crate) fn cache_usage(rel_path: impl AsRef<Path>, size: impl AsRef<str>) -> Self {
+        StyleMessage::new()
+            .plain_text("  ")
+            .styled_text(format!("{:>10}", size.as_ref()), style_blue())
+            .plain_text("  ")
+            .styled_text(rel_path.display_path(), style_purple_bold())
+    }
+
+    pub(crate) fn cache_usage_total(size: impl AsRef<str>) -> Self {
+        StyleMessage::new()
+            .plain_text("total: ")
+            .styled_text(size.as_ref(), style_green_bold())
+            .plain_text("\n")
+    }
+
+    pub fn current_repo_only(local: impl AsRef<str>) -> Self {
+        StyleMessage::new()
+            .plain_text("only ")
+            .styled_text(local.as_ref().display_path(), style_purple_bold())
+            .plain_text(", use ")
+            .styled_text("--all", style_purple_bold())
+            .plain_text(" for every repo of the workspace")
+    }
+
+    pub(crate) fn state_selection(states: &[RepoState], locals: &[&str]) -> Self {
+        let states = states
+            .iter()
+            .map(|state| state.to_string())
+            .collect::<Vec<_>>()
+            .join(" or ");
+        let mut msg = match locals.len() {
+            0 => return StyleMessage::new().plain_text(format!("no repository is {}.", states)),
+            1 => StyleMessage::new()
+                .styled_text("1", style_green_bold())
+                .plain_text(format!(" repository is {}: ", states)),
+            len => StyleMessage::new()
+                .styled_text(len.to_string(), style_green_bold())
+                .plain_text(format!(" repositories are {}: ", states)),
+        };
+        for (i, local) in locals.iter().enumerate() {
+            if i > 0 {
+                msg = msg.plain_text(", ");
+            }
+            msg = msg.styled_text(local.display_path(), style_purple_bold());
+        }
+        msg
+    }
+
+    pub(crate) fn lock_waiting(path: impl AsRef<Path>, holder: impl AsRef<str>) -> Self {
+        StyleMessage::new()
+            .plain_text("waiting for ")
+            .styled_text(path.as_ref().display().to_string(), style_purple_bold())
+            .plain_text(format!(", locked by {}...", holder.as_ref()))
+    }
+
+    pub(crate) fn lfs_not_installed(rel_path: impl AsRef<Path>) -> Self {
+        StyleMessage::new()
+            .styled_text("warning: ", style_yellow())
+            .styled_text(rel_path.as_ref().display_path(), style_purple_bold())
+            .plain_text(" uses Git LFS but git-lfs is not installed, pointer files are kept")
+    }
+
+    pub(crate) fn timing_summary(summary: &TimingSummary) -> Self {
+        let mut msg = StyleMessage::new()
+            .plain_text("Timing: total ")
+            .styled_text(format_duration(summary.total), style_green_bold())
+            .plain_text(format!(
+                ", p50 {}, p95 {}\n",
+                format_duration(summary.p50),
+                format_duration(summary.p95)
+            ))
+            .plain_text("Slowest repos:\n");
+        for timing in &summary.slowest {
+            let phases = timing
+                .slowest_phases()
+                .iter()
+                .take(SLOWEST_PHASES_COUNT)
+                .map(|(phase, elapsed)| format!("{} {}", phase, format_duration(*elapsed)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            msg = msg
+                .plain_text("  ")
+                .styled_text(
+                    format!("{:>8}", format_duration(timing.total)),
+                    style_yellow(),
+                )
+                .plain_text("  ")
+                .styled_text(timing.path.display_path(), style_purple_bold());
+            if !phases.is_empty() {
+                msg = msg.plain_text(format!(" ({})", phases));
+            }
+            msg = msg.plain_text("\n");
+        }
+        msg
+    }
+
+    pub(crate) fn cache_pruned(amount: usize) -> Self {
+        let msg = StyleMessage::new().plain_text("prune cache finished! ");
+        match amount {
+            0 => msg.plain_text("no mirror is removed.\n"),
+            _ => msg
+                .styled_text(amount.to_string(), style_green_bold())
+                .plain_text(" mirror(s) removed.\n"),
+        }
+    }
+
+    pub(crate) fn doctor_summary(errors: usize, warnings: usize, fixed: usize) -> Self {
+        let mut msg = StyleMessage::new().plain_text("doctor finished! ");
+        msg = match errors {
+            0 => msg.plain_text("0 error(s), "),
+            _ => msg
+                .styled_text(errors.to_string(), style_red_bold())
+                .plain_text(" error(s), "),
+        };
+        msg = match warnings {
+            0 => msg.plain_text("0 warning(s)"),
+            _ => msg
+                .styled_text(warnings.to_string(), style_yellow())
+                .plain_text(" warning(s)"),
+        };
+        if fixed > 0 {
+            msg = msg
+                .plain_text(", ")
+                .styled_text(fixed.to_string(), style_green_bold())
+                .plain_text(" fixed");
+        }
+        msg.plain_text(".\n")
+    }
 }
 
 impl Display for StyleMessage {
@@ -80,3 +80,241 @@ mod style_message_tests {
         assert_eq!(msg.to_plain_text(), cloned.to_plain_text());
     }
 }
+
+#[cfg(test)]
+mod askpass_tests {
+    use crate::utils::askpass::{rejected_host, AskpassPrompt};
+
+    /// Test prompts written by git for https remotes
+    #[test]
+    fn test_askpass_prompt_http() {
+        assert_eq!(
+            AskpassPrompt::parse("Username for 'https://example.com': "),
+            AskpassPrompt::Username {
+                host: "example.com".to_string()
+            }
+        );
+        assert_eq!(
+            AskpassPrompt::parse("Password for 'https://user@example.com:8443': "),
+            AskpassPrompt::Password {
+                host: "example.com:8443".to_string()
+            }
+        );
+    }
+
+    /// Test prompts written by ssh
+    #[test]
+    fn test_askpass_prompt_ssh() {
+        let prompt = "The authenticity of host 'example.com (127.0.0.1)' can't be established.\n\
+            ED25519 key fingerprint is SHA256:abcDEF123+/xyz.\n\
+            Are you sure you want to continue connecting (yes/no/[fingerprint])? ";
+        assert_eq!(
+            AskpassPrompt::parse(prompt),
+            AskpassPrompt::SshTrust {
+                host: "example.com".to_string(),
+                fingerprint: "SHA256:abcDEF123+/xyz".to_string()
+            }
+        );
+        assert_eq!(
+            AskpassPrompt::parse("git@example.com's password: "),
+            AskpassPrompt::Password {
+                host: "example.com".to_string()
+            }
+        );
+        assert_eq!(
+            AskpassPrompt::parse("Enter passphrase for key '/home/u/.ssh/id_rsa': "),
+            AskpassPrompt::Passphrase {
+                key: "/home/u/.ssh/id_rsa".to_string()
+            }
+        );
+    }
+
+    /// Test finding the host whose credentials were rejected
+    #[test]
+    fn test_rejected_host() {
+        assert_eq!(
+            rejected_host("fatal: Authentication failed for 'https://example.com/foo.git/'\n"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            rejected_host("git@example.com: Permission denied (publickey,password).\n"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            rejected_host("fatal: could not create work tree dir 'foo': Permission denied\n"),
+            None
+        );
+        assert_eq!(rejected_host("fatal: repository not found\n"), None);
+    }
+}
+
+#[cfg(test)]
+mod concurrency_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::task::JoinSet;
+
+    use crate::utils::concurrency::{
+        is_throttled, Limit, NetConcurrency, Outcome, AUTO_MAX_NET_THREADS,
+    };
+
+    /// Test parsing of `--net-thread` values
+    #[test]
+    fn test_net_concurrency_from_str() {
+        assert_eq!("8".parse(), Ok(NetConcurrency::Fixed(8)));
+        assert_eq!("auto".parse(), Ok(NetConcurrency::Auto));
+        assert_eq!("AUTO".parse(), Ok(NetConcurrency::Auto));
+        assert!("0".parse::<NetConcurrency>().is_err());
+        assert!("fast".parse::<NetConcurrency>().is_err());
+        assert_eq!(NetConcurrency::Fixed(8).max(), 8);
+        assert_eq!(NetConcurrency::Auto.max(), AUTO_MAX_NET_THREADS);
+    }
+
+    /// Test that concurrent acquires never hold more permits than the limit
+    #[tokio::test]
+    async fn test_limit_concurrent_acquires() {
+        let limit = Arc::new(Limit::new(3, 3, false));
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let mut join_set = JoinSet::new();
+        for _ in 0..12 {
+            let (limit, active, peak) = (limit.clone(), active.clone(), peak.clone());
+            join_set.spawn(async move {
+                limit.acquire().await;
+                let running = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                limit.release();
+            });
+        }
+        while join_set.join_next().await.is_some() {}
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    /// Test detection of throttling in git stderr
+    #[test]
+    fn test_is_throttled() {
+        assert!(is_throttled(
+            "error: RPC failed; HTTP 429 curl 22 The requested URL returned error: 429"
+        ));
+        assert!(is_throttled("fatal: read error: Connection reset by peer"));
+        assert!(is_throttled("fatal: early EOF"));
+        assert!(!is_throttled(
+            "fatal: couldn't find remote ref refs/heads/missing"
+        ));
+    }
+
+    /// Test additive increase and multiplicative decrease of `auto` limits
+    #[test]
+    fn test_auto_limit() {
+        let limit = Limit::new(4, 6, true);
+        let normal = Outcome::Success(Duration::from_millis(100));
+        for _ in 0..4 {
+            limit.record(&normal);
+        }
+        assert_eq!(limit.current(), 5);
+        for _ in 0..20 {
+            limit.record(&normal);
+        }
+        assert_eq!(limit.current(), 6);
+
+        limit.record(&Outcome::Success(Duration::from_secs(10)));
+        assert_eq!(limit.current(), 5);
+        limit.record(&Outcome::Throttled);
+        assert_eq!(limit.current(), 2);
+        limit.record(&Outcome::Failed);
+        assert_eq!(limit.current(), 2);
+        limit.record(&Outcome::Throttled);
+        limit.record(&Outcome::Throttled);
+        assert_eq!(limit.current(), 1);
+    }
+
+    /// Test that fixed limits never move
+    #[test]
+    fn test_fixed_limit() {
+        let limit = Limit::new(8, 8, false);
+        limit.record(&Outcome::Throttled);
+        limit.record(&Outcome::Success(Duration::from_secs(10)));
+        assert_eq!(limit.current(), 8);
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::config::RepoConfig;
+    use crate::utils::stats::{
+        format_duration, percentile, PhaseTimer, RepoTiming, TimingSummary, WorkspaceStats,
+    };
+
+    fn repo_timing(path: &str, millis: u64) -> RepoTiming {
+        let timer = PhaseTimer::default();
+        timer.record("fetch", Duration::from_millis(millis / 2));
+        timer.record("checkout", Duration::from_millis(millis / 4));
+        timer.record("fetch", Duration::from_millis(millis / 4));
+        RepoTiming::new(path, Duration::from_millis(millis), &timer)
+    }
+
+    /// Test nearest-rank percentiles and duration formatting
+    #[test]
+    fn test_percentile() {
+        let durations = (1..=20).map(Duration::from_secs).collect::<Vec<_>>();
+        assert_eq!(percentile(&durations, 50), Duration::from_secs(10));
+        assert_eq!(percentile(&durations, 95), Duration::from_secs(19));
+        assert_eq!(percentile(&durations[..1], 95), Duration::from_secs(1));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(12_340)), "12.3s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+    }
+
+    /// Test the summary and the order of repos in the next run
+    #[test]
+    fn test_slowest_first() {
+        let timings = vec![
+            repo_timing("fast", 100),
+            repo_timing("slow", 4000),
+            repo_timing("medium", 800),
+        ];
+        let summary = TimingSummary::new(Duration::from_secs(5), &timings).unwrap();
+        assert_eq!(summary.slowest[0].path, "slow");
+        assert_eq!(summary.p50, Duration::from_millis(800));
+        assert_eq!(
+            summary.slowest[0].slowest_phases(),
+            vec![
+                ("fetch", Duration::from_millis(3000)),
+                ("checkout", Duration::from_millis(1000))
+            ]
+        );
+
+        let mut stats = WorkspaceStats::default();
+        stats.record("sync", &summary, &timings);
+        let stats: WorkspaceStats = toml::from_str(&toml::to_string(&stats).unwrap()).unwrap();
+        assert_eq!(stats.ops["sync"].repos["slow"].phases["fetch"], 3000);
+        assert_eq!(stats.previous_ms("fetch", "medium"), Some(800));
+
+        let repos_map = ["fast", "slow", "medium", "new"]
+            .iter()
+            .enumerate()
+            .map(|(id, local)| {
+                let repo_config = RepoConfig {
+                    local: Some(local.to_string()),
+                    ..Default::default()
+                };
+                (id, repo_config)
+            })
+            .collect::<HashMap<_, _>>();
+        let order = stats
+            .slowest_first("sync", repos_map)
+            .into_iter()
+            .map(|(_, repo_config)| repo_config.local.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["new", "slow", "medium", "fast"]);
+    }
+}
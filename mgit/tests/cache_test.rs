@@ -0,0 +1,98 @@
+use mgit::error::MgitResult;
+use mgit::git;
+use mgit::ops::{self, CacheOptions, CachePruneOptions, SyncOptions};
+use std::path::PathBuf;
+
+use crate::common::{
+    commit_file, create_local_remote, create_test_dir, exec_cmd, failed_message, TestProgress,
+    TomlBuilder, DEFAULT_BRANCH,
+};
+
+mod common;
+
+/// 测试内容：
+///     1、运行命令: mgit cache update <path>，为仓库创建镜像
+///     2、remote 新增提交后运行 mgit sync <path>，先刷新镜像，再从镜像初始化新仓库
+///     3、新仓库不依赖镜像 (没有 alternates)
+///     4、remote 再次新增提交后运行 mgit sync <path>，已有仓库同样先从镜像拉取
+///     5、运行命令: mgit cache du / mgit cache prune --older-than 0
+///
+/// 测试目录结构:
+///   test_cache1
+///     ├─cache (镜像缓存)
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git, sync 时创建)
+#[tokio::test]
+async fn cli_cache1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_cache1");
+    let cache_path = tmp_dir.path().join("cache");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+    std::env::set_var(git::CACHE_DIR_ENV, &cache_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let remote_url = create_local_remote(&remote_path);
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", &remote_url, Some(DEFAULT_BRANCH), None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    ops::cache_update(
+        CacheOptions::new(Some(path.clone()), None::<PathBuf>, None, None, None),
+        TestProgress,
+    )
+    .await?;
+    let mirror_path = git::mirror_path(&remote_url).unwrap();
+    assert!(mirror_path.starts_with(&cache_path));
+    assert!(git::is_mirror(&mirror_path));
+
+    commit_file(&remote_path, "2.txt", "2");
+    let remote_head = exec_cmd(&remote_path, "git", &["rev-parse", "HEAD"]).unwrap();
+
+    ops::sync_repo(
+        SyncOptions::builder()
+            .path(Some(path.clone()))
+            .cache(Some(true))
+            .build(),
+        TestProgress,
+    )
+    .await?;
+
+    let repo_path = path.join("foobar");
+    let repo_head = exec_cmd(&repo_path, "git", &["rev-parse", "HEAD"]).unwrap();
+    assert_eq!(repo_head.trim(), remote_head.trim());
+    let mirror_head = exec_cmd(&mirror_path, "git", &["rev-parse", DEFAULT_BRANCH]).unwrap();
+    assert_eq!(mirror_head.trim(), remote_head.trim());
+    assert!(!repo_path.join(".git/objects/info/alternates").exists());
+
+    // existing repos are seeded from the refreshed mirror too, leaving nothing to the remote
+    commit_file(&remote_path, "3.txt", "3");
+    ops::sync_repo(
+        SyncOptions::builder()
+            .path(Some(path.clone()))
+            .cache(Some(true))
+            .build(),
+        TestProgress,
+    )
+    .await?;
+    let remote_ref = format!("refs/remotes/origin/{}", DEFAULT_BRANCH);
+    let reflog = exec_cmd(
+        &repo_path,
+        "git",
+        &["reflog", "-1", "--format=%gs", &remote_ref],
+    )
+    .unwrap();
+    assert!(reflog.contains(&mirror_path.to_string_lossy().to_string()));
+
+    let usage = ops::cache_du(TestProgress).await?.to_plain_text();
+    let mirror_rel_path = mirror_path.strip_prefix(&cache_path).unwrap();
+    assert!(usage.contains(&mirror_rel_path.to_string_lossy().to_string()));
+
+    ops::cache_prune(CachePruneOptions::new(Some(0)), TestProgress).await?;
+    assert!(!mirror_path.exists());
+
+    Ok(())
+}
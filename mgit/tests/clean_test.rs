@@ -301,6 +301,62 @@ async fn cli_clean3() -> MgitResult<()> {
     Ok(())
 }
 
+/// 测试内容：
+///     1、.mgitignore 忽略 foobar-1-1, 运行命令 mgit clean <path> --ignore foobar-*3
+///     2、被忽略的仓库不会被清除, 其他不在配置文件 (.gitrepos) 中的仓库被清除
+///
+/// 测试目录结构:
+///   test_clean_mgitignore
+///     ├─.gitrepos
+///     ├─.mgitignore
+///     └─... (same as cli_clean1())
+#[tokio::test]
+async fn cli_clean_mgitignore() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_clean_mgitignore");
+    let path = tmp_dir.path().to_path_buf();
+    let rel_paths = [
+        "foobar-1",
+        "foobar-1/foobar-1-1",
+        "foobar-1/foobar-1-2/foobar-1-2-1",
+        "foobar-2",
+        "foobar-2/foobar-2-1",
+        "foobar-3",
+    ];
+
+    create_repos_tree(&path, &rel_paths);
+
+    let toml_string = TomlBuilder::default()
+        .default_branch("develop")
+        .join_repo(rel_paths[3], IMGUI_REPO, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string.trim()).expect(failed_message::WRITE_FILE);
+    std::fs::write(path.join(".mgitignore"), "# local tools\nfoobar-1-1/\n")
+        .expect(failed_message::WRITE_FILE);
+
+    ops::clean_repo(
+        CleanOptions {
+            ignore: Some(vec!["foobar-*3".to_string()]),
+            ..CleanOptions::new(Some(path.clone()), None::<PathBuf>, None)
+        },
+        TestProgress,
+    )
+    .await?;
+
+    for rel_path in rel_paths {
+        let kept = matches!(rel_path, "foobar-1/foobar-1-1" | "foobar-2" | "foobar-3");
+        assert_eq!(
+            path.join(rel_path).join(".git").is_dir(),
+            kept,
+            "{}",
+            rel_path
+        );
+    }
+    assert!(path.join("foobar-1/foobar-1-1/foo/1.txt").is_file());
+    assert!(!path.join("foobar-1/1.txt").is_file());
+
+    Ok(())
+}
+
 pub fn create_repos_tree(path: &PathBuf, rel_paths: &[&str]) {
     if path.exists() {
         std::fs::remove_dir_all(path).unwrap();
@@ -40,6 +40,7 @@ pub const CSBOOKS_REPO: &str = if cfg!(feature = "use_gitea") {
 pub mod failed_message {
     pub const GIT_INIT: &str = "git init failed";
     pub const GIT_ADD_REMOTE: &str = "git add remote failed";
+    pub const GIT_CLONE: &str = "git clone failed";
     pub const GIT_STAGE: &str = "git stage failed";
     pub const GIT_COMMIT: &str = "git commit failed";
     pub const GIT_STATUS: &str = "git status failed";
@@ -130,6 +131,7 @@ pub fn csbooks_repo() -> &'static str {
     }
 }
 
+#[allow(dead_code)]
 pub struct TomlBuilder {
     toml_string: String,
 }
@@ -144,6 +146,7 @@ impl Default for TomlBuilder {
     }
 }
 
+#[allow(dead_code)]
 impl TomlBuilder {
     pub fn build(self) -> String {
         self.toml_string
@@ -204,6 +207,32 @@ pub fn check_git_author_identity(path: &std::path::PathBuf) {
     }
 }
 
+/// Helper function to create a repo on the default branch
+#[allow(dead_code)]
+pub fn init_repo(path: &Path) {
+    std::fs::create_dir_all(path).unwrap();
+    exec_cmd(path, "git", &["init", "-b", DEFAULT_BRANCH]).expect(failed_message::GIT_INIT);
+    check_git_author_identity(&path.to_path_buf());
+}
+
+/// Helper function to write a file and commit it, with its content as message
+#[allow(dead_code)]
+pub fn commit_file(path: &Path, file_name: &str, content: &str) {
+    let file_path = path.join(file_name);
+    std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+    std::fs::write(file_path, content).expect(failed_message::WRITE_FILE);
+    exec_cmd(path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(path, "git", &["commit", "-m", content]).expect(failed_message::GIT_COMMIT);
+}
+
+/// Helper function to create a local remote with `1.txt` committed, returns its url
+#[allow(dead_code)]
+pub fn create_local_remote(path: &Path) -> String {
+    init_repo(path);
+    commit_file(path, "1.txt", "1");
+    path.to_string_lossy().replace('\\', "/")
+}
+
 /// Helper function to write config file
 #[allow(dead_code)]
 pub fn write_config_file(path: &std::path::Path, content: &str) {
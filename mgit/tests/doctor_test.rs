@@ -0,0 +1,168 @@
+use mgit::error::MgitResult;
+use mgit::ops;
+use mgit::ops::DoctorOptions;
+use std::path::{Path, PathBuf};
+
+use crate::common::{
+    commit_file, create_test_dir, exec_cmd, failed_message, init_repo, TestProgress, TomlBuilder,
+    DEFAULT_BRANCH,
+};
+
+mod common;
+
+const CONFIG_REMOTE: &str = "https://example.com/mgit/foobar.git";
+
+/// 测试内容：
+///     1、运行命令 mgit doctor <path>
+///     2、仓库残留 .git/index.lock，且 remote 地址与配置不一致
+///     3、不带 --fix 时报告 error 并返回失败，不修改仓库
+///
+/// 测试目录结构:
+///   test_doctor1
+///     └─foobar (.git)
+///        └──1.txt
+#[tokio::test]
+async fn cli_doctor1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_doctor1");
+    let path = tmp_dir.path().to_path_buf();
+    let repo_path = create_broken_repo(&path);
+
+    let res = ops::doctor(
+        DoctorOptions::new(Some(path.clone()), None::<PathBuf>, None, None, None, None),
+        TestProgress,
+    )
+    .await;
+    assert!(res.is_err());
+
+    // nothing is touched without --fix
+    assert!(repo_path.join(".git/index.lock").is_file());
+    let url = exec_cmd(&repo_path, "git", &["remote", "get-url", "origin"])
+        .expect(failed_message::GIT_CONFIG);
+    assert_ne!(url.trim(), CONFIG_REMOTE);
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit doctor <path> --fix
+///     2、删除残留的 .git/index.lock，重设 remote 地址
+///     3、修复后再次检查无 error
+///
+/// 测试目录结构:
+///   test_doctor2
+///     └─foobar (.git)
+///        └──1.txt
+#[tokio::test]
+async fn cli_doctor2() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_doctor2");
+    let path = tmp_dir.path().to_path_buf();
+    let repo_path = create_broken_repo(&path);
+
+    ops::doctor(
+        DoctorOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            Some(true),
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    assert!(!repo_path.join(".git/index.lock").exists());
+    let url = exec_cmd(&repo_path, "git", &["remote", "get-url", "origin"])
+        .expect(failed_message::GIT_CONFIG);
+    assert_eq!(url.trim(), CONFIG_REMOTE);
+
+    ops::doctor(
+        DoctorOptions::new(Some(path.clone()), None::<PathBuf>, None, None, None, None),
+        TestProgress,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit doctor <path> --fix
+///     2、detached HEAD 只报告 warning，不视为失败
+///     3、配置中的仓库目录不存在只报告 info
+///
+/// 测试目录结构:
+///   test_doctor3
+///     ├─foobar (.git, detached HEAD)
+///     │  └──1.txt
+///     └─missing (不存在)
+#[tokio::test]
+async fn cli_doctor3() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_doctor3");
+    let path = tmp_dir.path().to_path_buf();
+    let repo_path = create_broken_repo(&path);
+
+    std::fs::remove_file(repo_path.join(".git/index.lock")).unwrap();
+    exec_cmd(
+        &repo_path,
+        "git",
+        &["remote", "set-url", "origin", CONFIG_REMOTE],
+    )
+    .expect(failed_message::GIT_CONFIG);
+    exec_cmd(&repo_path, "git", &["checkout", "--detach"]).expect(failed_message::GIT_CHECKOUT);
+
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", CONFIG_REMOTE, None, None, None)
+        .join_repo("missing", CONFIG_REMOTE, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string.trim()).expect(failed_message::WRITE_FILE);
+
+    ops::doctor(
+        DoctorOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            Some(true),
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    // detached HEAD is reported but never repaired
+    let head = exec_cmd(&repo_path, "git", &["branch", "--show-current"])
+        .expect(failed_message::GIT_BRANCH);
+    assert!(head.trim().is_empty());
+    assert!(!path.join("missing").exists());
+
+    Ok(())
+}
+
+fn create_broken_repo(path: &Path) -> PathBuf {
+    let repo_path = path.join("foobar");
+    init_repo(&repo_path);
+    exec_cmd(
+        &repo_path,
+        "git",
+        &[
+            "remote",
+            "add",
+            "origin",
+            "https://example.com/mgit/other.git",
+        ],
+    )
+    .expect(failed_message::GIT_ADD_REMOTE);
+    commit_file(&repo_path, "1.txt", "foobar");
+
+    // simulate an interrupted git process
+    std::fs::File::create(repo_path.join(".git/index.lock")).unwrap();
+
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", CONFIG_REMOTE, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string.trim()).expect(failed_message::WRITE_FILE);
+
+    repo_path
+}
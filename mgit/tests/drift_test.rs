@@ -0,0 +1,212 @@
+use mgit::config::MgitConfig;
+use mgit::error::MgitResult;
+use mgit::ops;
+use mgit::ops::DriftOptions;
+use std::path::{Path, PathBuf};
+
+use crate::common::{
+    commit_file, create_test_dir, exec_cmd, failed_message, init_repo, TestProgress, TomlBuilder,
+    DEFAULT_BRANCH,
+};
+
+mod common;
+
+const CONFIG_REMOTE: &str = "https://example.com/mgit/foobar.git";
+const WORKSPACE_REMOTE: &str = "https://example.com/mgit/other.git";
+
+/// 测试内容：
+///     1、运行命令 mgit drift <path>
+///     2、只报告差异，不修改配置文件
+///
+/// 测试目录结构:
+///   test_drift1
+///     ├─foobar (.git, remote/branch 与配置不一致)
+///     ├─extra (.git, 不在配置中)
+///     └─missing (配置中存在，磁盘上不存在)
+#[tokio::test]
+async fn cli_drift1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_drift1");
+    let path = tmp_dir.path().to_path_buf();
+    create_drift_workspace(&path);
+    let toml_before = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+
+    ops::drift(
+        DriftOptions::new(Some(path.clone()), None::<PathBuf>, None, None, None, None),
+        TestProgress,
+    )
+    .await?;
+
+    let toml_after = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+    assert_eq!(toml_before, toml_after);
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit drift <path> --write
+///     2、按工作区更新有差异的仓库配置
+///     3、缺失和多余的仓库未显式指定时不改动
+///     4、配置文件中的注释和仓库顺序保持不变
+///
+/// 测试目录结构:
+///   test_drift2
+///     ├─foobar (.git, remote/branch 与配置不一致)
+///     ├─extra (.git, 不在配置中)
+///     └─missing (配置中存在，磁盘上不存在)
+#[tokio::test]
+async fn cli_drift2() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_drift2");
+    let path = tmp_dir.path().to_path_buf();
+    create_drift_workspace(&path);
+
+    // comment and an order that isn't sorted by local
+    let toml_string = TomlBuilder::default()
+        .default_branch("develop")
+        .join_repo("missing", CONFIG_REMOTE, None, None, None)
+        .join_repo("foobar", CONFIG_REMOTE, None, None, None)
+        .build();
+    let toml_string = format!("# shared by the team\n{}", toml_string.trim());
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    ops::drift(
+        DriftOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            Some(vec![]),
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    let config = MgitConfig::load(path.join(".gitrepos")).unwrap();
+    let repos = config.repos.unwrap();
+    assert_eq!(repos.len(), 2);
+
+    let foobar = repos
+        .iter()
+        .find(|r| r.local.as_deref() == Some("foobar"))
+        .unwrap();
+    assert_eq!(foobar.remote.as_deref(), Some(WORKSPACE_REMOTE));
+    assert_eq!(foobar.branch.as_deref(), Some(DEFAULT_BRANCH));
+    assert!(repos.iter().any(|r| r.local.as_deref() == Some("missing")));
+    assert!(!repos.iter().any(|r| r.local.as_deref() == Some("extra")));
+
+    let toml_after = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+    assert!(toml_after.starts_with("# shared by the team\n"));
+    assert!(toml_after.find("\"missing\"") < toml_after.find("\"foobar\""));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit drift <path> --write missing extra
+///     2、显式指定时移除缺失的仓库、加入多余的仓库
+///
+/// 测试目录结构:
+///   test_drift3
+///     ├─foobar (.git, remote/branch 与配置不一致)
+///     ├─extra (.git, 不在配置中)
+///     └─missing (配置中存在，磁盘上不存在)
+#[tokio::test]
+async fn cli_drift3() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_drift3");
+    let path = tmp_dir.path().to_path_buf();
+    create_drift_workspace(&path);
+
+    ops::drift(
+        DriftOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            Some(vec!["missing".to_string(), "extra".to_string()]),
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    let config = MgitConfig::load(path.join(".gitrepos")).unwrap();
+    let repos = config.repos.unwrap();
+    assert!(!repos.iter().any(|r| r.local.as_deref() == Some("missing")));
+
+    let extra = repos
+        .iter()
+        .find(|r| r.local.as_deref() == Some("extra"))
+        .unwrap();
+    assert_eq!(extra.remote.as_deref(), Some(WORKSPACE_REMOTE));
+
+    // foobar is not selected, keep it as is
+    let foobar = repos
+        .iter()
+        .find(|r| r.local.as_deref() == Some("foobar"))
+        .unwrap();
+    assert_eq!(foobar.remote.as_deref(), Some(CONFIG_REMOTE));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit drift <path> --write
+///     2、remote 写法不同但指向同一仓库时不视为差异，也不改写配置
+///
+/// 测试目录结构:
+///   test_drift4
+///     └─foobar (.git, remote 为 scp 写法)
+#[tokio::test]
+async fn cli_drift4() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_drift4");
+    let path = tmp_dir.path().to_path_buf();
+    create_drift_workspace(&path);
+
+    let config_remote = "ssh://git@EXAMPLE.com:22/mgit/other";
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", config_remote, None, None, None)
+        .join_repo("extra", WORKSPACE_REMOTE, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string.trim()).expect(failed_message::WRITE_FILE);
+    let toml_before = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+
+    ops::drift(
+        DriftOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            Some(vec![]),
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    let toml_after = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+    assert_eq!(toml_before, toml_after);
+
+    Ok(())
+}
+
+fn create_drift_workspace(path: &Path) {
+    for rel_path in ["foobar", "extra"] {
+        let repo_path = path.join(rel_path);
+        init_repo(&repo_path);
+        exec_cmd(
+            &repo_path,
+            "git",
+            &["remote", "add", "origin", WORKSPACE_REMOTE],
+        )
+        .expect(failed_message::GIT_ADD_REMOTE);
+        commit_file(&repo_path, "1.txt", rel_path);
+    }
+
+    let toml_string = TomlBuilder::default()
+        .default_branch("develop")
+        .join_repo("foobar", CONFIG_REMOTE, None, None, None)
+        .join_repo("missing", CONFIG_REMOTE, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string.trim()).expect(failed_message::WRITE_FILE);
+}
@@ -1,10 +1,16 @@
 use mgit::error::MgitResult;
 use mgit::ops;
 use mgit::ops::{FetchOptions, InitOptions};
+use mgit::utils::cmd::{DEFAULT_RETRY_COUNT, DEFAULT_RETRY_DELAY_MS};
+use mgit::utils::progress::{Progress, RepoInfo, TransferPhase, TransferProgress};
+use mgit::utils::style_message::StyleMessage;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::common::{
-    create_test_dir, csbooks_repo, exec_cmd, failed_message, mgit_repo, TestProgress, TomlBuilder,
+    create_local_remote, create_test_dir, csbooks_repo, exec_cmd, failed_message, init_repo,
+    mgit_repo, TestProgress, TomlBuilder, DEFAULT_BRANCH,
 };
 
 mod common;
@@ -54,6 +60,7 @@ async fn cli_fetch_simple() -> MgitResult<()> {
             None,
             None,
             None,
+            None,
         ),
         TestProgress,
     )
@@ -110,6 +117,7 @@ async fn cli_fetch_new_remote_url() -> MgitResult<()> {
             None,
             None,
             None,
+            None,
         ),
         TestProgress,
     )
@@ -123,3 +131,168 @@ async fn cli_fetch_new_remote_url() -> MgitResult<()> {
     }
     Ok(())
 }
+
+/// 测试内容：
+///     1、运行命令: mgit fetch <path>
+///     2、remote 无法连接时从 fallbacks 镜像拉取到同一 remote-tracking 分支
+///     3、remote url 保持不变
+///     4、remote 无法连接时不重试, 直接使用镜像
+///
+/// 测试目录结构:
+///   test_fetch_fallback
+///     ├─mirror (.git, 镜像仓库)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_fetch_fallback() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_fetch_fallback");
+    let mirror_path = tmp_dir.path().join("mirror");
+    let path = tmp_dir.path().join("workspace");
+    let repo_path = path.join("foobar");
+    let primary = "http://127.0.0.1:9/unreachable/foobar.git";
+
+    let mirror_url = create_local_remote(&mirror_path);
+    let mirror_head = exec_cmd(&mirror_path, "git", &["rev-parse", "HEAD"]).unwrap();
+
+    init_repo(&repo_path);
+    exec_cmd(&repo_path, "git", &["remote", "add", "origin", primary])
+        .expect(failed_message::GIT_ADD_REMOTE);
+
+    let toml_string = format!(
+        r#"default-branch = "{}"
+
+[[repos]]
+local = "foobar"
+remote = "{}"
+fallbacks = ["{}"]
+"#,
+        DEFAULT_BRANCH, primary, mirror_url
+    );
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let start = Instant::now();
+    ops::fetch_repos(
+        FetchOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ),
+        TestProgress,
+    )
+    .await?;
+    // the retries of the remote alone would sleep this long
+    let retry_delay =
+        Duration::from_millis(DEFAULT_RETRY_DELAY_MS * (DEFAULT_RETRY_COUNT as u64 - 1));
+    assert!(start.elapsed() < retry_delay);
+
+    let tracking_head = exec_cmd(
+        &repo_path,
+        "git",
+        &[
+            "rev-parse",
+            &format!("refs/remotes/origin/{}", DEFAULT_BRANCH),
+        ],
+    )
+    .unwrap();
+    assert_eq!(tracking_head.trim(), mirror_head.trim());
+
+    let remote_url = exec_cmd(&repo_path, "git", &["remote", "get-url", "origin"]).unwrap();
+    assert_eq!(remote_url.trim(), primary);
+
+    Ok(())
+}
+
+/// Progress keeping the transfer events of fetches
+#[derive(Clone, Default)]
+struct TransferRecorder {
+    transfers: Arc<Mutex<Vec<TransferProgress>>>,
+}
+
+impl Progress for TransferRecorder {
+    fn on_batch_start(&self, _total: usize) {}
+
+    fn on_batch_finish(&self) {}
+
+    fn on_repo_start(&self, _repo_info: &RepoInfo, _message: StyleMessage) {}
+
+    fn on_repo_update(&self, _repo_info: &RepoInfo, _message: StyleMessage) {}
+
+    fn on_repo_success(&self, _repo_info: &RepoInfo, _message: StyleMessage) {}
+
+    fn on_repo_error(&self, _repo_info: &RepoInfo, _message: StyleMessage) {}
+
+    fn on_repo_transfer(&self, _repo_info: &RepoInfo, transfer: &TransferProgress) {
+        self.transfers.lock().unwrap().push(transfer.clone());
+    }
+}
+
+/// 测试内容：
+///     1、运行命令: mgit fetch <path>
+///     2、git fetch --progress 的输出逐行转发为 transfer 进度, 接收对象阶段到达 100%
+///
+/// 测试目录结构:
+///   test_fetch_transfer_progress
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_fetch_transfer_progress() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_fetch_transfer_progress");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+    let repo_path = path.join("foobar");
+
+    init_repo(&remote_path);
+    for index in 0..200 {
+        std::fs::write(
+            remote_path.join(format!("{}.txt", index)),
+            index.to_string(),
+        )
+        .expect(failed_message::WRITE_FILE);
+    }
+    exec_cmd(&remote_path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&remote_path, "git", &["commit", "-m", "1"]).expect(failed_message::GIT_COMMIT);
+
+    let remote_url = remote_path.to_string_lossy().replace('\\', "/");
+    init_repo(&repo_path);
+    exec_cmd(&repo_path, "git", &["remote", "add", "origin", &remote_url])
+        .expect(failed_message::GIT_ADD_REMOTE);
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let progress = TransferRecorder::default();
+    ops::fetch_repos(
+        FetchOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ),
+        progress.clone(),
+    )
+    .await?;
+
+    let transfers = progress.transfers.lock().unwrap();
+    let received = transfers
+        .iter()
+        .rev()
+        .find(|t| t.phase == TransferPhase::Receiving && t.percent == 100)
+        .expect("no transfer progress received");
+    assert!(received.bytes.is_some_and(|bytes| bytes > 0));
+
+    Ok(())
+}
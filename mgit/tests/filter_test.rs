@@ -0,0 +1,114 @@
+use mgit::error::MgitResult;
+use mgit::ops;
+use mgit::ops::{FetchOptions, SyncOptions};
+use std::path::{Path, PathBuf};
+
+use crate::common::{
+    commit_file, create_test_dir, exec_cmd, failed_message, init_repo, TestProgress, DEFAULT_BRANCH,
+};
+
+mod common;
+
+/// Number of objects referenced by the repo but not downloaded
+fn missing_objects(path: &Path) -> usize {
+    let args = ["rev-list", "--objects", "--all", "--missing=print"];
+    let output = exec_cmd(path, "git", &args).expect(failed_message::GIT_REV_LIST);
+    output.lines().filter(|line| line.starts_with('?')).count()
+}
+
+/// 测试内容：
+///     1、.gitrepos 配置 default-filter = "blob:none"
+///         - foobar-1 配置 sparse = ["/a/"]
+///         - foobar-2 配置 filter = "", 完整克隆
+///     2、运行命令: mgit sync <path>
+///         - foobar-1 的 origin 成为 promisor remote, 只下载 a 目录的 blob
+///     3、remote 有新提交后运行命令: mgit fetch <path>
+///         - 依然不下载 blob
+///
+/// 测试目录结构:
+///   test_filter1
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─foobar-1 (.git)
+///         └─foobar-2 (.git)
+#[tokio::test]
+async fn cli_filter1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_filter1");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    init_repo(&remote_path);
+    exec_cmd(
+        &remote_path,
+        "git",
+        &["config", "uploadpack.allowFilter", "true"],
+    )
+    .expect(failed_message::GIT_CONFIG);
+    commit_file(&remote_path, "a/1.txt", "1");
+    commit_file(&remote_path, "b/2.txt", "2");
+    commit_file(&remote_path, "b/3.txt", "3");
+
+    std::fs::create_dir_all(&path).unwrap();
+    let remote_url = remote_path.to_string_lossy().replace('\\', "/");
+    let toml_string = format!(
+        r#"default-branch = "{branch}"
+default-filter = "blob:none"
+
+[[repos]]
+local = "foobar-1"
+remote = "{url}"
+sparse = ["/a/"]
+
+[[repos]]
+local = "foobar-2"
+remote = "{url}"
+filter = ""
+"#,
+        branch = DEFAULT_BRANCH,
+        url = remote_url
+    );
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    let partial_path = path.join("foobar-1");
+    let promisor = exec_cmd(&partial_path, "git", &["config", "remote.origin.promisor"])
+        .expect(failed_message::GIT_CONFIG);
+    assert_eq!(promisor.trim(), "true");
+    let filter = exec_cmd(
+        &partial_path,
+        "git",
+        &["config", "remote.origin.partialclonefilter"],
+    )
+    .expect(failed_message::GIT_CONFIG);
+    assert_eq!(filter.trim(), "blob:none");
+    assert!(partial_path.join("a/1.txt").is_file());
+    assert!(!partial_path.join("b").exists());
+    assert_eq!(missing_objects(&partial_path), 2);
+
+    let full_path = path.join("foobar-2");
+    assert!(exec_cmd(&full_path, "git", &["config", "remote.origin.promisor"]).is_err());
+    assert_eq!(missing_objects(&full_path), 0);
+
+    commit_file(&remote_path, "b/4.txt", "4");
+    ops::fetch_repos(
+        FetchOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ),
+        TestProgress,
+    )
+    .await?;
+    assert_eq!(missing_objects(&partial_path), 3);
+    assert_eq!(missing_objects(&full_path), 0);
+
+    Ok(())
+}
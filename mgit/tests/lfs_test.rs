@@ -0,0 +1,170 @@
+use mgit::error::MgitResult;
+use mgit::ops;
+use mgit::ops::SyncOptions;
+
+use crate::common::{
+    create_test_dir, exec_cmd, failed_message, init_repo, TestProgress, TomlBuilder, DEFAULT_BRANCH,
+};
+
+mod common;
+
+const LFS_POINTER: &str = "version https://git-lfs.github.com/spec/v1
+oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393
+size 12345
+";
+
+fn is_lfs_installed() -> bool {
+    exec_cmd(".", "git", &["lfs", "version"]).is_ok()
+}
+
+/// 测试内容：
+///     1、remote 仓库的 .gitattributes 中有 filter=lfs
+///     2、运行命令: mgit sync <path> --skip-lfs
+///         - 工作区保留 LFS pointer 文件
+///     3、未安装 git-lfs 时运行命令: mgit sync <path>
+///         - 同步成功并保留 pointer 文件
+///     4、配置 lfs = true, 未安装 git-lfs 时运行命令: mgit sync <path> --no-checkout
+///         - 不更新工作区, 同步成功
+///     5、配置 lfs = true, 未安装 git-lfs 时运行命令: mgit sync <path>
+///         - 提示 git-lfs 未安装
+///
+/// 测试目录结构:
+///   test_lfs1
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_lfs1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_lfs1");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    init_repo(&remote_path);
+    std::fs::write(
+        remote_path.join(".gitattributes"),
+        "*.bin filter=lfs diff=lfs merge=lfs -text\n",
+    )
+    .expect(failed_message::WRITE_FILE);
+    std::fs::write(remote_path.join("data.bin"), LFS_POINTER).expect(failed_message::WRITE_FILE);
+    exec_cmd(&remote_path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&remote_path, "git", &["commit", "-m", "1"]).expect(failed_message::GIT_COMMIT);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let remote_url = remote_path.to_string_lossy().replace('\\', "/");
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), &toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .skip_lfs(Some(true))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    let content = std::fs::read_to_string(path.join("foobar/data.bin")).unwrap();
+    assert_eq!(content.replace("\r\n", "\n"), LFS_POINTER);
+
+    if !is_lfs_installed() {
+        let options = SyncOptions::builder().path(Some(path.clone())).build();
+        ops::sync_repo(options, TestProgress).await?;
+        let content = std::fs::read_to_string(path.join("foobar/data.bin")).unwrap();
+        assert_eq!(content.replace("\r\n", "\n"), LFS_POINTER);
+
+        let toml_string = format!("{}lfs = true\n", toml_string);
+        std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+        // the worktree is left alone, and so are its LFS files
+        let options = SyncOptions::builder()
+            .path(Some(path.clone()))
+            .no_checkout(Some(true))
+            .build();
+        ops::sync_repo(options, TestProgress).await?;
+
+        let options = SyncOptions::builder().path(Some(path.clone())).build();
+        let err = ops::sync_repo(options, TestProgress).await.unwrap_err();
+        assert!(err.to_string().contains("git-lfs is not installed"));
+    }
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、remote 仓库用 git-lfs 提交了两个目录下的 .bin 文件, 未安装 git-lfs 时跳过
+///     2、运行命令: mgit sync <path>, 配置 sparse = ["/a/"]
+///         - 只取回并检出 a 目录下的 LFS 文件
+///         - lfs.fetchinclude 与 sparse 一致
+///
+/// 测试目录结构:
+///   test_lfs2
+///     ├─source (.git)
+///     ├─remote.git
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_lfs2() -> MgitResult<()> {
+    if !is_lfs_installed() {
+        return Ok(());
+    }
+
+    let tmp_dir = create_test_dir("test_lfs2");
+    let source_path = tmp_dir.path().join("source");
+    let remote_path = tmp_dir.path().join("remote.git");
+    let path = tmp_dir.path().join("workspace");
+    let remote_url = remote_path.to_string_lossy().replace('\\', "/");
+
+    std::fs::create_dir_all(&remote_path).unwrap();
+    exec_cmd(
+        &remote_path,
+        "git",
+        &["init", "--bare", "-b", DEFAULT_BRANCH],
+    )
+    .expect(failed_message::GIT_INIT);
+
+    // local remotes are served by the standalone transfer agent of git-lfs
+    init_repo(&source_path);
+    std::fs::create_dir_all(source_path.join("a")).unwrap();
+    std::fs::create_dir_all(source_path.join("b")).unwrap();
+    exec_cmd(&source_path, "git", &["lfs", "install", "--local"]).unwrap();
+    exec_cmd(&source_path, "git", &["lfs", "track", "*.bin"]).unwrap();
+    std::fs::write(source_path.join("a/1.bin"), "a binary").expect(failed_message::WRITE_FILE);
+    std::fs::write(source_path.join("b/2.bin"), "b binary").expect(failed_message::WRITE_FILE);
+    exec_cmd(&source_path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&source_path, "git", &["commit", "-m", "1"]).expect(failed_message::GIT_COMMIT);
+    exec_cmd(
+        &source_path,
+        "git",
+        &["remote", "add", "origin", &remote_url],
+    )
+    .expect(failed_message::GIT_ADD_REMOTE);
+    exec_cmd(&source_path, "git", &["push", "origin", DEFAULT_BRANCH]).unwrap();
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = format!(
+        r#"default-branch = "{}"
+
+[[repos]]
+local = "foobar"
+remote = "{}"
+sparse = ["/a/"]
+"#,
+        DEFAULT_BRANCH, remote_url
+    );
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    let repo_path = path.join("foobar");
+    let content = std::fs::read_to_string(repo_path.join("a/1.bin")).unwrap();
+    assert_eq!(content, "a binary");
+    assert!(!repo_path.join("b/2.bin").exists());
+
+    let include = exec_cmd(&repo_path, "git", &["config", "--get", "lfs.fetchinclude"])
+        .expect(failed_message::GIT_CONFIG);
+    assert_eq!(include.trim(), "/a/");
+
+    Ok(())
+}
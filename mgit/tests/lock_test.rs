@@ -0,0 +1,199 @@
+use mgit::error::{MgitError, MgitResult};
+use mgit::ops;
+use mgit::ops::{DoctorOptions, SnapshotOptions, SyncOptions};
+use mgit::utils::lock::{read_holder, WorkspaceLock};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::common::{
+    create_local_remote, create_test_dir, exec_cmd, failed_message, init_repo, TestProgress,
+    TomlBuilder, DEFAULT_BRANCH,
+};
+
+mod common;
+
+/// Create a remote with one commit and a workspace syncing it to `foobar`
+fn create_workspace(root: &Path) -> std::path::PathBuf {
+    let remote_path = root.join("remote");
+    let path = root.join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+    path
+}
+
+/// 测试内容：
+///     1、workspace 被其他进程锁定时运行 mgit sync <path>
+///         - 立即失败, 错误信息包含持有者的操作和 pid
+///     2、运行命令 mgit sync <path> --wait, 锁释放后继续同步
+///         - 同步成功, .mgit/lock 被删除
+///
+/// 测试目录结构:
+///   test_lock_concurrent
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─.mgit
+///         │   ├─.gitignore
+///         │   └─lock
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_lock_concurrent() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_lock_concurrent");
+    let path = create_workspace(tmp_dir.path());
+
+    let lock = WorkspaceLock::acquire(&path, "clean", false, &TestProgress).await?;
+    let holder = read_holder(WorkspaceLock::file_path(&path)).unwrap();
+    assert_eq!(holder.pid, std::process::id());
+    assert!(path.join(".mgit/.gitignore").is_file());
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    match ops::sync_repo(options, TestProgress).await {
+        Err(MgitError::WorkspaceLocked { holder, .. }) => {
+            assert!(holder.contains("`clean`"));
+            assert!(holder.contains(&std::process::id().to_string()));
+        }
+        res => panic!("expected a locked workspace, got {:?}", res.map(|_| ())),
+    }
+    assert!(!path.join("foobar").exists());
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        drop(lock);
+    });
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .wait(Some(true))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(path.join("foobar/1.txt").is_file());
+    assert!(!WorkspaceLock::file_path(&path).exists());
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、.mgit/lock 的持有进程已退出时运行 mgit sync <path>
+///         - 识别为残留的锁并接管, 同步成功
+///
+/// 测试目录结构:
+///   test_lock_stale
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─.mgit
+///         │   └─lock
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_lock_stale() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_lock_stale");
+    let path = create_workspace(tmp_dir.path());
+
+    // a lock of this host, left by a process that has exited
+    let mut child = std::process::Command::new("git")
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let dead_pid = child.id();
+    child.wait().unwrap();
+
+    let lock = WorkspaceLock::acquire(&path, "sync", false, &TestProgress).await?;
+    let mut holder = read_holder(WorkspaceLock::file_path(&path)).unwrap();
+    drop(lock);
+    holder.pid = dead_pid;
+    assert!(holder.is_stale());
+    std::fs::write(
+        WorkspaceLock::file_path(&path),
+        toml::to_string(&holder).unwrap(),
+    )
+    .expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(path.join("foobar/1.txt").is_file());
+    assert!(!WorkspaceLock::file_path(&path).exists());
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、workspace 根目录本身是 git 仓库时, 锁位于其 git 目录中
+///         - git status 中看不到锁
+///     2、锁被占用时运行 mgit snapshot <path> --force
+///         - 立即失败, 不改写 .gitrepos
+///
+/// 测试目录结构:
+///   test_lock_root_repo
+///     ├─remote (.git)
+///     └─workspace (.git)
+///         └─.gitrepos
+#[tokio::test]
+async fn cli_lock_root_repo() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_lock_root_repo");
+    let path = create_workspace(tmp_dir.path());
+    init_repo(&path);
+    exec_cmd(&path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&path, "git", &["commit", "-m", "1"]).expect(failed_message::GIT_COMMIT);
+
+    let lock = WorkspaceLock::acquire(&path, "sync", false, &TestProgress).await?;
+    assert!(WorkspaceLock::file_path(&path).starts_with(path.join(".git")));
+    assert!(!path.join(".mgit").exists());
+    let status = exec_cmd(&path, "git", &["status", "--porcelain"]).unwrap();
+    assert_eq!(status.trim(), "");
+
+    let config = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+    let options = SnapshotOptions::new(Some(&path), None::<PathBuf>, Some(true), None, None);
+    let res = ops::snapshot_repo(options, TestProgress).await;
+    assert!(matches!(res, Err(MgitError::WorkspaceLocked { .. })));
+    assert_eq!(
+        std::fs::read_to_string(path.join(".gitrepos")).unwrap(),
+        config
+    );
+
+    drop(lock);
+    assert!(!WorkspaceLock::file_path(&path).exists());
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、锁被占用时运行 mgit doctor <path> --fix
+///         - 立即失败, 残留的 index.lock 不被删除
+///     2、锁释放后运行 mgit doctor <path> --fix
+///         - 删除残留的 index.lock
+///
+/// 测试目录结构:
+///   test_lock_doctor_fix
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git/index.lock)
+#[tokio::test]
+async fn cli_lock_doctor_fix() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_lock_doctor_fix");
+    let path = create_workspace(tmp_dir.path());
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+    let index_lock = path.join("foobar/.git/index.lock");
+    std::fs::File::create(&index_lock).unwrap();
+
+    let lock = WorkspaceLock::acquire(&path, "sync", false, &TestProgress).await?;
+    let options = DoctorOptions::new(Some(&path), None::<PathBuf>, None, None, None, Some(true));
+    let res = ops::doctor(options, TestProgress).await;
+    assert!(matches!(res, Err(MgitError::WorkspaceLocked { .. })));
+    assert!(index_lock.is_file());
+
+    drop(lock);
+    let options = DoctorOptions::new(Some(&path), None::<PathBuf>, None, None, None, Some(true));
+    ops::doctor(options, TestProgress).await?;
+    assert!(!index_lock.exists());
+
+    Ok(())
+}
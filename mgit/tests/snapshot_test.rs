@@ -6,7 +6,8 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use crate::common::{
-    create_test_dir, exec_cmd, failed_message, TestProgress, TomlBuilder, CSBOOKS_REPO,
+    commit_file, create_test_dir, exec_cmd, failed_message, init_repo, TestProgress, TomlBuilder,
+    CSBOOKS_REPO, DEFAULT_BRANCH,
 };
 
 mod common;
@@ -523,3 +524,52 @@ pub async fn create_repos_tree3(path: &PathBuf) {
     exec_cmd(path, "git", &["switch", "-c", "master", commit]).expect(failed_message::GIT_CHECKOUT);
     exec_cmd(path, "git", &["branch", "-u", "origin/master"]).expect(failed_message::GIT_BRANCH);
 }
+
+/// 测试内容：
+///     1、运行命令 mgit snapshot <path> --force
+///     2、工作区 remote 与原配置写法不同但指向同一仓库时，保留原配置的写法
+///
+/// 测试目录结构:
+///   test_snapshot_equivalent_remote
+///     └─foobar (.git, remote 为 scp 写法)
+#[tokio::test]
+async fn cli_snapshot_equivalent_remote() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_snapshot_equivalent_remote");
+    let path = tmp_dir.path().to_path_buf();
+    let repo_path = path.join("foobar");
+
+    init_repo(&repo_path);
+    exec_cmd(
+        &repo_path,
+        "git",
+        &["remote", "add", "origin", "git@Example.com:mgit/foobar.git"],
+    )
+    .expect(failed_message::GIT_ADD_REMOTE);
+    commit_file(&repo_path, "1.txt", "1");
+
+    let config_remote = "https://example.com/mgit/foobar";
+    let toml_string = TomlBuilder::default()
+        .default_branch("develop")
+        .join_repo("foobar", config_remote, Some(DEFAULT_BRANCH), None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string.trim()).expect(failed_message::WRITE_FILE);
+
+    ops::snapshot_repo(
+        SnapshotOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            Some(true),
+            Some(SnapshotType::Branch),
+            None,
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    let config = mgit::config::MgitConfig::load(path.join(".gitrepos")).unwrap();
+    let repos = config.repos.unwrap();
+    assert_eq!(repos.len(), 1);
+    assert_eq!(repos[0].remote.as_deref(), Some(config_remote));
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use mgit::error::MgitResult;
+use mgit::ops::{self, StashAction, StashOptions, SyncOptions};
+use std::path::{Path, PathBuf};
+
+use crate::common::{
+    create_local_remote, create_test_dir, exec_cmd, failed_message, TestProgress, TomlBuilder,
+    DEFAULT_BRANCH,
+};
+
+mod common;
+
+/// 测试内容：
+///     1、运行命令: mgit sync <path> --stash, 本地修改被 stash
+///     2、运行命令: mgit stash list, 按仓库列出 mgit 创建的 stash, 忽略用户自己的 stash
+///     3、运行命令: mgit stash pop, 恢复本地修改并删除 stash
+///
+/// 测试目录结构:
+///   test_stash1
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_stash1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_stash1");
+    let path = create_workspace(tmp_dir.path()).await;
+    let repo_path = path.join("foobar");
+
+    // a stash of the user's own
+    std::fs::write(repo_path.join("1.txt"), "user").expect(failed_message::WRITE_FILE);
+    exec_cmd(&repo_path, "git", &["stash", "push", "-m", "user"])
+        .expect(failed_message::GIT_STASH_LIST);
+
+    std::fs::write(repo_path.join("1.txt"), "local").expect(failed_message::WRITE_FILE);
+    std::fs::write(repo_path.join("2.txt"), "2").expect(failed_message::WRITE_FILE);
+    sync_stash(&path).await;
+    assert!(!repo_path.join("2.txt").exists());
+
+    let msg = ops::stash_repos(stash_options(&path, StashAction::List), TestProgress).await?;
+    let msg = msg.to_string();
+    assert!(msg.contains("foobar"));
+    assert!(msg.contains("mgit: sync"));
+    assert!(!msg.contains("user"));
+
+    ops::stash_repos(stash_options(&path, StashAction::Pop), TestProgress).await?;
+    let content = std::fs::read_to_string(repo_path.join("1.txt")).unwrap();
+    assert_eq!(content, "local");
+    assert!(repo_path.join("2.txt").exists());
+
+    // only the stash of the user is left
+    let output = exec_cmd(&repo_path, "git", &["stash", "list"]).unwrap();
+    assert_eq!(output.lines().count(), 1);
+    let msg = ops::stash_repos(stash_options(&path, StashAction::List), TestProgress).await?;
+    assert!(msg.to_string().contains("No stashes created by mgit"));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令: mgit sync <path> --stash 后，在同一文件上提交冲突的修改
+///     2、运行命令: mgit stash pop, 报告冲突文件，保留 stash
+///
+/// 测试目录结构:
+///   test_stash2
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_stash2() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_stash2");
+    let path = create_workspace(tmp_dir.path()).await;
+    let repo_path = path.join("foobar");
+
+    std::fs::write(repo_path.join("1.txt"), "local").expect(failed_message::WRITE_FILE);
+    sync_stash(&path).await;
+
+    std::fs::write(repo_path.join("1.txt"), "other").expect(failed_message::WRITE_FILE);
+    exec_cmd(&repo_path, "git", &["commit", "-am", "other"]).expect(failed_message::GIT_COMMIT);
+
+    let result = ops::stash_repos(stash_options(&path, StashAction::Pop), TestProgress).await;
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("conflicts in 1.txt"));
+
+    // the stash is kept
+    let output = exec_cmd(&repo_path, "git", &["stash", "list"]).unwrap();
+    assert!(output.contains("mgit: sync"));
+
+    Ok(())
+}
+
+fn stash_options(path: &Path, action: StashAction) -> StashOptions {
+    StashOptions::new(Some(path), None::<PathBuf>, action, None, None, None)
+}
+
+async fn sync_stash(path: &Path) {
+    ops::sync_repo(
+        SyncOptions::builder()
+            .path(Some(path.to_path_buf()))
+            .stash(Some(true))
+            .build(),
+        TestProgress,
+    )
+    .await
+    .unwrap();
+}
+
+async fn create_workspace(tmp_path: &Path) -> PathBuf {
+    let remote_path = tmp_path.join("remote");
+    let path = tmp_path.join("workspace");
+    std::fs::create_dir_all(&path).unwrap();
+    let remote_url = create_local_remote(&remote_path);
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    ops::sync_repo(
+        SyncOptions::builder().path(Some(path.clone())).build(),
+        TestProgress,
+    )
+    .await
+    .unwrap();
+
+    path
+}
@@ -0,0 +1,164 @@
+use mgit::error::MgitResult;
+use mgit::ops;
+use mgit::ops::{CleanOptions, SnapshotOptions, SyncOptions};
+use std::path::{Path, PathBuf};
+
+use crate::common::{
+    commit_file, create_local_remote, create_test_dir, exec_cmd, failed_message, init_repo,
+    TestProgress, TomlBuilder, DEFAULT_BRANCH,
+};
+
+mod common;
+
+/// Local submodule urls use the file transport, which git only allows on request
+fn allow_file_protocol() {
+    std::env::set_var("GIT_CONFIG_COUNT", "1");
+    std::env::set_var("GIT_CONFIG_KEY_0", "protocol.file.allow");
+    std::env::set_var("GIT_CONFIG_VALUE_0", "always");
+}
+
+/// Create the `sub` repo and the `main` repo with `sub` as submodule `libs/sub`
+fn create_remotes(root: &Path) -> (PathBuf, PathBuf) {
+    let sub_path = root.join("sub");
+    let main_path = root.join("main");
+    let sub_url = create_local_remote(&sub_path);
+    init_repo(&main_path);
+    commit_file(&main_path, "main.txt", "main");
+
+    exec_cmd(
+        &main_path,
+        "git",
+        &["submodule", "add", "--quiet", &sub_url, "libs/sub"],
+    )
+    .unwrap();
+    exec_cmd(&main_path, "git", &["commit", "-m", "add sub"]).expect(failed_message::GIT_COMMIT);
+    (main_path, sub_path)
+}
+
+/// 测试内容：
+///     1、运行命令: mgit sync <path>
+///         - 检出 submodule 到记录的 commit
+///     2、submodule 有新提交后再次 sync, submodule 跟随更新
+///     3、修改 submodule 后运行 mgit sync <path> --hard, 修改被丢弃
+///     4、运行命令: mgit clean <path>, submodule 不会被删除
+///     5、运行命令: mgit snapshot <path> --force, submodule 不作为仓库写入 .gitrepos
+///
+/// 测试目录结构:
+///   test_submodule1
+///     ├─sub (.git)
+///     ├─main (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─main (.git)
+///             └─libs
+///                 └─sub (.git)
+#[tokio::test]
+async fn cli_submodule1() -> MgitResult<()> {
+    allow_file_protocol();
+    let tmp_dir = create_test_dir("test_submodule1");
+    let (main_path, sub_path) = create_remotes(tmp_dir.path());
+    let path = tmp_dir.path().join("workspace");
+    std::fs::create_dir_all(&path).unwrap();
+
+    let main_url = main_path.to_string_lossy().replace('\\', "/");
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("main", &main_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let sync = |hard: bool| {
+        let options = SyncOptions::builder()
+            .path(Some(path.clone()))
+            .hard(Some(hard))
+            .build();
+        ops::sync_repo(options, TestProgress)
+    };
+
+    sync(false).await?;
+    let repo_sub_path = path.join("main/libs/sub");
+    assert!(repo_sub_path.join("1.txt").is_file());
+
+    // move the submodule of main to a new commit of sub
+    commit_file(&sub_path, "2.txt", "2");
+    let main_sub_path = main_path.join("libs/sub");
+    exec_cmd(
+        &main_sub_path,
+        "git",
+        &["pull", "--quiet", "origin", DEFAULT_BRANCH],
+    )
+    .unwrap();
+    exec_cmd(&main_path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&main_path, "git", &["commit", "-m", "update sub"]).expect(failed_message::GIT_COMMIT);
+    let sub_head = exec_cmd(&sub_path, "git", &["rev-parse", "HEAD"]).unwrap();
+
+    sync(false).await?;
+    let head = exec_cmd(&repo_sub_path, "git", &["rev-parse", "HEAD"]).unwrap();
+    assert_eq!(head.trim(), sub_head.trim());
+
+    std::fs::write(repo_sub_path.join("1.txt"), "changed").expect(failed_message::WRITE_FILE);
+    std::fs::write(repo_sub_path.join("untracked.txt"), "").expect(failed_message::WRITE_FILE);
+    sync(true).await?;
+    let content = std::fs::read_to_string(repo_sub_path.join("1.txt")).unwrap();
+    assert_eq!(content, "1");
+    assert!(!repo_sub_path.join("untracked.txt").exists());
+
+    ops::clean_repo(
+        CleanOptions::new(Some(path.clone()), None::<PathBuf>, None),
+        TestProgress,
+    )
+    .await?;
+    assert!(repo_sub_path.join("2.txt").is_file());
+
+    ops::snapshot_repo(
+        SnapshotOptions::new(Some(path.clone()), None::<PathBuf>, Some(true), None, None),
+        TestProgress,
+    )
+    .await?;
+    let config = std::fs::read_to_string(path.join(".gitrepos")).unwrap();
+    assert!(config.contains("local = \"main\""));
+    assert!(!config.contains("libs/sub"));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、仓库配置 submodules = false
+///     2、运行命令: mgit sync <path>, 不检出 submodule
+///
+/// 测试目录结构:
+///   test_submodule2
+///     ├─sub (.git)
+///     ├─main (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─main (.git)
+#[tokio::test]
+async fn cli_submodule2() -> MgitResult<()> {
+    allow_file_protocol();
+    let tmp_dir = create_test_dir("test_submodule2");
+    let (main_path, _) = create_remotes(tmp_dir.path());
+    let path = tmp_dir.path().join("workspace");
+    std::fs::create_dir_all(&path).unwrap();
+
+    let main_url = main_path.to_string_lossy().replace('\\', "/");
+    let toml_string = format!(
+        r#"default-branch = "{}"
+
+[[repos]]
+local = "main"
+remote = "{}"
+submodules = false
+"#,
+        DEFAULT_BRANCH, main_url
+    );
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    assert!(path.join("main/main.txt").is_file());
+    assert!(!path.join("main/libs/sub/1.txt").exists());
+
+    Ok(())
+}
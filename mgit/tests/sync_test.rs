@@ -1,11 +1,14 @@
-use mgit::error::MgitResult;
+use mgit::config::RepoState;
+use mgit::error::{MgitError, MgitResult};
 use mgit::ops;
-use mgit::ops::SyncOptions;
+use mgit::ops::{CleanOptions, FetchOptions, SyncOptions};
+use mgit::utils::concurrency::NetConcurrency;
+use mgit::utils::stats::WorkspaceStats;
 use std::{collections::HashSet, path::PathBuf};
 
 use crate::common::{
-    create_test_dir, exec_cmd, failed_message, TestProgress, TomlBuilder, CSBOOKS_REPO,
-    DEFAULT_BRANCH, SBERT_REPO,
+    commit_file, create_local_remote, create_test_dir, exec_cmd, failed_message, init_repo,
+    TestProgress, TomlBuilder, CSBOOKS_REPO, DEFAULT_BRANCH, SBERT_REPO,
 };
 
 mod common;
@@ -1759,3 +1762,448 @@ async fn cli_sync_with_sparse_checkout() -> MgitResult<()> {
 
     Ok(())
 }
+
+/// 测试内容：
+///     1、运行命令 mgit sync <path>, 克隆 foobar
+///     2、remote 新建分支后再次运行 mgit sync <path>
+///         - foobar 已是目标提交且工作区干净, 跳过 fetch, 不出现 origin/feature
+///     3、运行命令 mgit sync <path> --no-fast-path
+///         - 正常 fetch, 出现 origin/feature
+///     4、remote 有新提交后运行 mgit sync <path>, foobar 更新到新提交
+///     5、foobar 有本地修改时运行 mgit sync <path> --hard, 本地修改被还原
+///
+/// 测试目录结构:
+///   test_sync_fast_path
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         └─foobar (.git)
+#[tokio::test]
+async fn cli_sync_fast_path() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_fast_path");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = format!(
+        r#"default-branch = "{branch}"
+
+[[repos]]
+local = "foobar"
+remote = "{url}"
+"#,
+        branch = DEFAULT_BRANCH,
+        url = remote_url
+    );
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let sync = |no_fast_path: bool, hard: bool| {
+        let options = SyncOptions::builder()
+            .path(Some(path.clone()))
+            .hard(Some(hard))
+            .no_fast_path(Some(no_fast_path))
+            .build();
+        ops::sync_repo(options, TestProgress)
+    };
+    let repo_path = path.join("foobar");
+    let has_feature = || {
+        let args = ["rev-parse", "--verify", "--quiet", "origin/feature"];
+        exec_cmd(&repo_path, "git", &args).is_ok()
+    };
+    let head = |dir: &PathBuf| {
+        exec_cmd(dir, "git", &["rev-parse", "HEAD"])
+            .expect(failed_message::GIT_REV_LIST)
+            .trim()
+            .to_string()
+    };
+
+    sync(false, false).await?;
+    assert_eq!(head(&repo_path), head(&remote_path));
+
+    exec_cmd(&remote_path, "git", &["branch", "feature"]).expect(failed_message::GIT_BRANCH);
+    sync(false, false).await?;
+    assert!(!has_feature());
+
+    sync(true, false).await?;
+    assert!(has_feature());
+
+    std::fs::write(remote_path.join("2.txt"), "2").expect(failed_message::WRITE_FILE);
+    exec_cmd(&remote_path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&remote_path, "git", &["commit", "-m", "2"]).expect(failed_message::GIT_COMMIT);
+    sync(false, false).await?;
+    assert_eq!(head(&repo_path), head(&remote_path));
+
+    std::fs::write(repo_path.join("1.txt"), "changed").expect(failed_message::WRITE_FILE);
+    sync(false, true).await?;
+    let content = std::fs::read_to_string(repo_path.join("1.txt")).unwrap();
+    assert_eq!(content, "1");
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit sync <path> -t 8 --net-thread 1, 克隆 4 个仓库
+///     2、运行命令 mgit sync <path> --net-thread auto --no-fast-path
+///         - 网络命令数量自动调整, 所有仓库同步成功
+///
+/// 测试目录结构:
+///   test_sync_net_thread
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─foobar-1 (.git)
+///         ├─foobar-2 (.git)
+///         ├─foobar-3 (.git)
+///         └─foobar-4 (.git)
+#[tokio::test]
+async fn cli_sync_net_thread() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_net_thread");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let mut toml_builder = TomlBuilder::default().default_branch(DEFAULT_BRANCH);
+    for index in 1..=4 {
+        toml_builder =
+            toml_builder.join_repo(&format!("foobar-{}", index), &remote_url, None, None, None);
+    }
+    std::fs::write(path.join(".gitrepos"), toml_builder.build()).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .thread_count(Some(8))
+        .net_thread(Some(NetConcurrency::Fixed(1)))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .net_thread(Some(NetConcurrency::Auto))
+        .no_fast_path(Some(true))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    for index in 1..=4 {
+        assert!(path.join(format!("foobar-{}/1.txt", index)).is_file());
+    }
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit sync <path>, 克隆 2 个仓库
+///         - .mgit/stats 记录每个仓库的耗时和各阶段 (fetch, checkout) 耗时
+///     2、运行命令 mgit fetch <path>
+///         - .mgit/stats 同时保留 sync 和 fetch 的记录
+///
+/// 测试目录结构:
+///   test_sync_stats
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─.mgit
+///         │   ├─.gitignore
+///         │   └─stats
+///         ├─foobar-1 (.git)
+///         └─foobar-2 (.git)
+#[tokio::test]
+async fn cli_sync_stats() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_stats");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar-1", &remote_url, None, None, None)
+        .join_repo("foobar-2", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    assert!(WorkspaceStats::file_path(&path).is_file());
+    let stats = WorkspaceStats::load(&path);
+    let sync_stats = &stats.ops["sync"];
+    assert_eq!(sync_stats.repos.len(), 2);
+    for repo in ["foobar-1", "foobar-2"] {
+        let phases = &sync_stats.repos[repo].phases;
+        assert!(phases.contains_key("fetch"));
+        assert!(phases.contains_key("checkout"));
+    }
+
+    let options = FetchOptions::new(
+        Some(path.clone()),
+        None::<PathBuf>,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    ops::fetch_repos(options, TestProgress).await?;
+
+    let stats = WorkspaceStats::load(&path);
+    assert!(stats.ops.contains_key("sync"));
+    assert!(stats.ops["fetch"].repos["foobar-1"]
+        .phases
+        .contains_key("fetch"));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、workspace 根目录是配置中的仓库 (local = "."), 运行命令 mgit sync <path>
+///         - 耗时统计保存在根目录仓库的 git 目录中, 根目录仓库保持干净
+///     2、再次运行命令 mgit sync <path>
+///         - 快速检查通过, 不再 fetch
+///
+/// 测试目录结构:
+///   test_sync_stats_root_repo
+///     ├─remote (.git)
+///     └─workspace (.git)
+///         ├─.gitrepos
+///         └─1.txt
+#[tokio::test]
+async fn cli_sync_stats_root_repo() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_stats_root_repo");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    init_repo(&remote_path);
+    let remote_url = remote_path.to_string_lossy().replace('\\', "/");
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo(".", &remote_url, None, None, None)
+        .build();
+    std::fs::write(remote_path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+    commit_file(&remote_path, "1.txt", "1");
+    exec_cmd(tmp_dir.path(), "git", &["clone", &remote_url, "workspace"])
+        .expect(failed_message::GIT_CLONE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    assert!(WorkspaceStats::file_path(&path).starts_with(path.join(".git")));
+    assert!(WorkspaceStats::file_path(&path).is_file());
+    assert!(!path.join(".mgit").exists());
+    let status = exec_cmd(&path, "git", &["status", "--porcelain"]).unwrap();
+    assert_eq!(status.trim(), "");
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    let stats = WorkspaceStats::load(&path);
+    // the root repo is recorded with an empty path
+    let phases = &stats.ops["sync"].repos[""].phases;
+    assert!(phases.contains_key("check"));
+    assert!(!phases.contains_key("fetch"));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit sync <path>, 克隆 foobar-1 和 foobar-2
+///     2、新建 .gitrepos.local, foobar-1 切换到 feature 分支, 忽略 foobar-2, 新增 foobar-3
+///     3、运行命令 mgit sync <path>
+///         - foobar-1 检出 feature 分支, foobar-3 被克隆, .gitrepos 不变
+///     4、运行命令 mgit clean <path>
+///         - 被忽略的 foobar-2 不会被删除
+///
+/// 测试目录结构:
+///   test_sync_local_config
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─.gitrepos.local
+///         ├─foobar-1 (.git)
+///         ├─foobar-2 (.git)
+///         └─foobar-3 (.git)
+#[tokio::test]
+async fn cli_sync_local_config() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_local_config");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+    exec_cmd(&remote_path, "git", &["checkout", "-b", "feature"])
+        .expect(failed_message::GIT_CHECKOUT);
+    commit_file(&remote_path, "2.txt", "2");
+    exec_cmd(&remote_path, "git", &["checkout", DEFAULT_BRANCH])
+        .expect(failed_message::GIT_CHECKOUT);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar-1", &remote_url, None, None, None)
+        .join_repo("foobar-2", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), &toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(!path.join("foobar-1/2.txt").exists());
+
+    let local_toml_string = format!(
+        "ignore = [\"foobar-2\"]\n\n[[repos]]\nlocal = \"foobar-1\"\nbranch = \"feature\"\n\n[[repos]]\nlocal = \"foobar-3\"\nremote = \"{}\"\nbranch = \"{}\"\n",
+        remote_url, DEFAULT_BRANCH
+    );
+    std::fs::write(path.join(".gitrepos.local"), local_toml_string)
+        .expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(path.join("foobar-1/2.txt").is_file());
+    assert!(path.join("foobar-3/1.txt").is_file());
+    assert_eq!(
+        std::fs::read_to_string(path.join(".gitrepos")).unwrap(),
+        toml_string
+    );
+
+    ops::clean_repo(
+        CleanOptions::new(Some(path.clone()), None::<PathBuf>, None),
+        TestProgress,
+    )
+    .await?;
+    assert!(path.join("foobar-2/1.txt").is_file());
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit sync <path> --only engine --only tools/*
+///         - 按别名和 glob 选择仓库, 只克隆 client/engine 和 tools/foobar
+///     2、运行命令 mgit sync <path> --only clinet
+///         - 没有匹配的仓库, 报错
+///
+/// 测试目录结构:
+///   test_sync_only
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─client
+///         │   └─engine (.git)
+///         ├─server (未克隆)
+///         └─tools
+///             └─foobar (.git)
+#[tokio::test]
+async fn cli_sync_only() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_only");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = format!(
+        "default-branch = \"{branch}\"\n\n[[repos]]\nlocal = \"client/engine\"\nalias = \"engine\"\nremote = \"{url}\"\n\n[[repos]]\nlocal = \"server\"\nremote = \"{url}\"\n\n[[repos]]\nlocal = \"tools/foobar\"\nremote = \"{url}\"\n",
+        branch = DEFAULT_BRANCH,
+        url = remote_url
+    );
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .only(Some(vec!["engine".to_string(), "tools/*".to_string()]))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(path.join("client/engine/1.txt").is_file());
+    assert!(path.join("tools/foobar/1.txt").is_file());
+    assert!(!path.join("server").exists());
+
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .only(Some(vec!["clinet".to_string()]))
+        .build();
+    match ops::sync_repo(options, TestProgress).await {
+        Err(MgitError::UnknownRepoSelector { selectors }) => assert_eq!(selectors, "`clinet`"),
+        res => panic!("expected an unknown selector, got {:?}", res.map(|_| ())),
+    }
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、运行命令 mgit sync <path>, 克隆 foobar-1 和 foobar-2
+///     2、远端新增提交, 只 fetch foobar-1, foobar-2 有本地改动
+///     3、运行命令 mgit sync <path> --behind
+///         - 只同步落后的 foobar-1, foobar-2 不变
+///     4、运行命令 mgit sync <path> --missing, 删除 foobar-1 后执行
+///         - 只重新克隆 foobar-1
+///
+/// 测试目录结构:
+///   test_sync_states
+///     ├─remote (.git)
+///     └─workspace
+///         ├─.gitrepos
+///         ├─foobar-1 (.git)
+///         └─foobar-2 (.git)
+#[tokio::test]
+async fn cli_sync_states() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_sync_states");
+    let remote_path = tmp_dir.path().join("remote");
+    let path = tmp_dir.path().join("workspace");
+
+    let remote_url = create_local_remote(&remote_path);
+
+    std::fs::create_dir_all(&path).unwrap();
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar-1", &remote_url, None, None, None)
+        .join_repo("foobar-2", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder().path(Some(path.clone())).build();
+    ops::sync_repo(options, TestProgress).await?;
+
+    std::fs::write(remote_path.join("2.txt"), "2").expect(failed_message::WRITE_FILE);
+    exec_cmd(&remote_path, "git", &["add", "-A"]).expect(failed_message::GIT_STAGE);
+    exec_cmd(&remote_path, "git", &["commit", "-m", "2"]).expect(failed_message::GIT_COMMIT);
+    let options = FetchOptions {
+        only: Some(vec!["foobar-1".to_string()]),
+        ..FetchOptions::new(
+            Some(path.clone()),
+            None::<PathBuf>,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    };
+    ops::fetch_repos(options, TestProgress).await?;
+    std::fs::write(path.join("foobar-2/1.txt"), "changed").expect(failed_message::WRITE_FILE);
+
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .states(Some(vec![RepoState::Behind]))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(path.join("foobar-1/2.txt").is_file());
+    assert!(!path.join("foobar-2/2.txt").exists());
+    assert_eq!(
+        std::fs::read_to_string(path.join("foobar-2/1.txt")).unwrap(),
+        "changed"
+    );
+
+    std::fs::remove_dir_all(path.join("foobar-1")).unwrap();
+    let options = SyncOptions::builder()
+        .path(Some(path.clone()))
+        .states(Some(vec![RepoState::Missing]))
+        .build();
+    ops::sync_repo(options, TestProgress).await?;
+    assert!(path.join("foobar-1/2.txt").is_file());
+    assert!(!path.join("foobar-2/2.txt").exists());
+
+    Ok(())
+}
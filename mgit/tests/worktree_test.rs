@@ -0,0 +1,243 @@
+use mgit::error::MgitResult;
+use mgit::git;
+use mgit::ops::{
+    self, CleanOptions, SyncOptions, WorktreeAddOptions, WorktreeListOptions, WorktreeRemoveOptions,
+};
+use std::path::{Path, PathBuf};
+
+use crate::common::{
+    commit_file, create_local_remote, create_test_dir, exec_cmd, failed_message, init_repo,
+    TestProgress, TomlBuilder, DEFAULT_BRANCH,
+};
+
+mod common;
+
+const RELEASE_BRANCH: &str = "release";
+
+/// 测试内容：
+///     1、运行命令: mgit worktree add <dir> --source <path> --config release.gitrepos
+///     2、新工作区的仓库是 worktree (.git 为文件)，检出 release 分支
+///     3、对新工作区运行 mgit sync 和 mgit worktree list
+///     4、运行命令: mgit worktree remove <dir>
+///
+/// 测试目录结构:
+///   test_worktree1
+///     ├─remote (.git, master/release 分支)
+///     ├─release.gitrepos
+///     ├─workspace
+///     │   ├─.gitrepos
+///     │   └─foobar (.git)
+///     └─release
+///         ├─.gitrepos
+///         └─foobar (.git 文件)
+#[tokio::test]
+async fn cli_worktree1() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_worktree1");
+    let (remote_url, path) = create_workspace(tmp_dir.path()).await;
+
+    let release_config = tmp_dir.path().join("release.gitrepos");
+    let toml_string = TomlBuilder::default()
+        .default_branch(RELEASE_BRANCH)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(&release_config, toml_string).expect(failed_message::WRITE_FILE);
+
+    let target = tmp_dir.path().join("release");
+    ops::worktree_add(
+        WorktreeAddOptions::new(
+            &target,
+            Some(&path),
+            Some(&release_config),
+            None,
+            None,
+            None,
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    let repo_path = target.join("foobar");
+    assert!(repo_path.join(".git").is_file());
+    assert!(target.join(".gitrepos").is_file());
+    git::is_repository(&repo_path).await?;
+    assert_eq!(git::get_current_branch(&repo_path).await?, RELEASE_BRANCH);
+
+    ops::sync_repo(
+        SyncOptions::builder().path(Some(target.clone())).build(),
+        TestProgress,
+    )
+    .await?;
+    assert_eq!(git::get_current_branch(&repo_path).await?, RELEASE_BRANCH);
+
+    let list = ops::worktree_list(WorktreeListOptions::new(Some(&path), None::<PathBuf>))
+        .await?
+        .to_plain_text();
+    assert!(list.contains(&format!("[{}]", RELEASE_BRANCH)));
+
+    ops::worktree_remove(WorktreeRemoveOptions::new(&target, None), TestProgress).await?;
+    assert!(!target.exists());
+    let list = ops::worktree_list(WorktreeListOptions::new(Some(&path), None::<PathBuf>))
+        .await?
+        .to_plain_text();
+    assert!(!list.contains(RELEASE_BRANCH));
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、worktree 工作区使用与源工作区相同的分支时保持 detached，不移动源工作区的分支
+///     2、mgit clean 删除不在配置中的 worktree 仓库，并清理源仓库中的 worktree 记录
+///
+/// 测试目录结构:
+///   test_worktree2
+///     ├─remote (.git)
+///     ├─workspace
+///     │   ├─.gitrepos
+///     │   └─foobar (.git)
+///     └─develop
+///         ├─.gitrepos
+///         └─foobar (.git 文件)
+#[tokio::test]
+async fn cli_worktree2() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_worktree2");
+    let (remote_url, path) = create_workspace(tmp_dir.path()).await;
+
+    let target = tmp_dir.path().join("develop");
+    ops::worktree_add(
+        WorktreeAddOptions::new(&target, Some(&path), None::<PathBuf>, None, None, None),
+        TestProgress,
+    )
+    .await?;
+
+    let repo_path = target.join("foobar");
+    assert!(git::is_head_detached(&repo_path).await?);
+    assert_eq!(
+        git::get_current_branch(path.join("foobar")).await?,
+        DEFAULT_BRANCH
+    );
+
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("other", &remote_url, None, None, None)
+        .build();
+    std::fs::write(target.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+    ops::clean_repo(
+        CleanOptions::new(Some(&target), None::<PathBuf>, None),
+        TestProgress,
+    )
+    .await?;
+
+    assert!(!repo_path.exists());
+    let worktrees = git::list_worktrees(path.join("foobar")).await?;
+    assert_eq!(worktrees.len(), 1);
+
+    Ok(())
+}
+
+/// 测试内容：
+///     1、工作区根目录是配置中的仓库 (local = "."), 运行命令: mgit worktree add <dir>
+///         - 根目录仓库的 worktree 就是新工作区，其中的仓库也是 worktree
+///     2、运行命令: mgit worktree remove <dir>
+///         - 先删除其中的仓库，再删除根目录仓库的 worktree
+///
+/// 测试目录结构:
+///   test_worktree3
+///     ├─remote (.git, master/release 分支)
+///     ├─root-remote (.git, master/release 分支)
+///     ├─release.gitrepos
+///     ├─workspace (.git)
+///     │   ├─.gitrepos
+///     │   └─foobar (.git)
+///     └─release (.git 文件)
+///         ├─.gitrepos
+///         └─foobar (.git 文件)
+#[tokio::test]
+async fn cli_worktree3() -> MgitResult<()> {
+    let tmp_dir = create_test_dir("test_worktree3");
+    let (remote_url, path) = create_workspace(tmp_dir.path()).await;
+
+    let root_remote_path = tmp_dir.path().join("root-remote");
+    init_repo(&root_remote_path);
+    std::fs::write(root_remote_path.join(".gitignore"), "foobar/\n")
+        .expect(failed_message::WRITE_FILE);
+    commit_file(&root_remote_path, "root.txt", "root");
+    exec_cmd(&root_remote_path, "git", &["branch", RELEASE_BRANCH])
+        .expect(failed_message::GIT_BRANCH);
+    let root_remote_url = root_remote_path.to_string_lossy().replace('\\', "/");
+
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo(".", &root_remote_url, None, None, None)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+    ops::sync_repo(
+        SyncOptions::builder().path(Some(path.clone())).build(),
+        TestProgress,
+    )
+    .await?;
+    assert!(path.join("root.txt").is_file());
+
+    let release_config = tmp_dir.path().join("release.gitrepos");
+    let toml_string = TomlBuilder::default()
+        .default_branch(RELEASE_BRANCH)
+        .join_repo(".", &root_remote_url, None, None, None)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(&release_config, toml_string).expect(failed_message::WRITE_FILE);
+
+    let target = tmp_dir.path().join("release");
+    ops::worktree_add(
+        WorktreeAddOptions::new(
+            &target,
+            Some(&path),
+            Some(&release_config),
+            None,
+            None,
+            None,
+        ),
+        TestProgress,
+    )
+    .await?;
+
+    assert!(target.join(".git").is_file());
+    assert!(target.join("root.txt").is_file());
+    assert!(target.join(".gitrepos").is_file());
+    assert!(target.join("foobar/.git").is_file());
+    assert_eq!(git::get_current_branch(&target).await?, RELEASE_BRANCH);
+    assert_eq!(
+        git::get_current_branch(target.join("foobar")).await?,
+        RELEASE_BRANCH
+    );
+
+    ops::worktree_remove(WorktreeRemoveOptions::new(&target, None), TestProgress).await?;
+    assert!(!target.exists());
+    assert_eq!(git::list_worktrees(&path).await?.len(), 1);
+    assert_eq!(git::list_worktrees(path.join("foobar")).await?.len(), 1);
+
+    Ok(())
+}
+
+/// Create a remote with `master` and `release` and a workspace synced to `master`
+async fn create_workspace(tmp_path: &Path) -> (String, PathBuf) {
+    let remote_path = tmp_path.join("remote");
+    let path = tmp_path.join("workspace");
+    std::fs::create_dir_all(&path).unwrap();
+    let remote_url = create_local_remote(&remote_path);
+    exec_cmd(&remote_path, "git", &["branch", RELEASE_BRANCH]).expect(failed_message::GIT_BRANCH);
+
+    let toml_string = TomlBuilder::default()
+        .default_branch(DEFAULT_BRANCH)
+        .join_repo("foobar", &remote_url, None, None, None)
+        .build();
+    std::fs::write(path.join(".gitrepos"), toml_string).expect(failed_message::WRITE_FILE);
+
+    ops::sync_repo(
+        SyncOptions::builder().path(Some(path.clone())).build(),
+        TestProgress,
+    )
+    .await
+    .unwrap();
+
+    (remote_url, path)
+}